@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use png::Chunk;
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(chunk) = Chunk::try_from(data) {
+        // a valid chunk must serialize back to exactly the bytes it was read from
+        assert_eq!(chunk.as_bytes(), data);
+        let _ = chunk.to_string();
+    }
+});
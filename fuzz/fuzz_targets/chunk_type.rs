@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use png::ChunkType;
+use std::convert::TryFrom;
+use std::str::{self, FromStr};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bytes) = <[u8; 4]>::try_from(data) {
+        if let Ok(chunk_type) = ChunkType::try_from(bytes) {
+            assert_eq!(chunk_type.bytes(), bytes);
+            assert!(chunk_type.to_string().len() == 4);
+        }
+    }
+
+    if let Ok(s) = str::from_utf8(data) {
+        if let Ok(chunk_type) = ChunkType::from_str(s) {
+            assert!(s.starts_with(&chunk_type.to_string()));
+        }
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use png::{Image, Png};
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(png) = Png::try_from(data) {
+        if let Ok(image) = Image::decode(&png) {
+            let _ = image.encode();
+        }
+
+        let _ = Image::decode_original_precision(&png);
+    }
+});
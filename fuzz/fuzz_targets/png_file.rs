@@ -0,0 +1,24 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use png::chunk_specs::ChunkRegistry;
+use png::{GenerationParameters, Png, XmpPacket};
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(png) = Png::try_from(data) {
+        assert_eq!(png.as_bytes(), data);
+
+        // describing the chunks parses each one with its chunk spec, as `print` does
+        let registry = ChunkRegistry::standard();
+        for chunk in png.chunks() {
+            if let Some(Ok(parsed)) = registry.parse(chunk) {
+                let _ = parsed.validate_against(&png);
+            }
+        }
+
+        let _ = png.to_string();
+        let _ = png.metadata();
+        let _ = XmpPacket::from_png(&png);
+        let _ = GenerationParameters::from_png(&png);
+    }
+});
@@ -0,0 +1,30 @@
+#!/bin/sh
+# Derives a seed corpus for each fuzz target from the images in ../images:
+# whole files for `png_file` and `image_decode`, each of their chunks for
+# `chunk`, and each chunk type code for `chunk_type`.
+set -eu
+cd "$(dirname "$0")"
+mkdir -p corpus/png_file corpus/image_decode corpus/chunk corpus/chunk_type
+
+for image in ../images/*.png; do
+    name=$(basename "$image" .png)
+    size=$(wc -c < "$image")
+    cp "$image" "corpus/png_file/$name"
+    cp "$image" "corpus/image_decode/$name"
+
+    # chunks start after the 8 byte signature and are made up of a 4 byte
+    # length, a 4 byte type, the data, and a 4 byte CRC
+    offset=8
+    index=0
+    while [ $((offset + 12)) -le "$size" ]; do
+        set -- $(od -An -tu1 -j "$offset" -N4 "$image")
+        length=$(( ($1 << 24) | ($2 << 16) | ($3 << 8) | $4 ))
+        chunk_type=$(tail -c +$((offset + 5)) "$image" | head -c 4)
+
+        tail -c +$((offset + 1)) "$image" | head -c $((length + 12)) > "corpus/chunk/$name-$index"
+        printf '%s' "$chunk_type" > "corpus/chunk_type/$chunk_type"
+
+        offset=$((offset + length + 12))
+        index=$((index + 1))
+    done
+done
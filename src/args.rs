@@ -20,7 +20,25 @@ pub enum Subcommands {
     #[clap(setting(AppSettings::ArgRequiredElseHelp))]
     Remove(RemoveArguments),
     #[clap(setting(AppSettings::ArgRequiredElseHelp))]
-    Print(PrintArguments)
+    Print(PrintArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Check(CheckArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Repair(RepairArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Text(TextArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Dpi(DpiArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Icc(IccArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Xmp(XmpArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Ai(AiArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Trailing(TrailingArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Tonemap(TonemapArguments)
 }
 
 #[derive(Args)]
@@ -32,6 +50,12 @@ pub struct EncodeArguments {
     pub chunk_type: String,
     /// The message to encode
     pub message: String,
+    #[clap(short, long)]
+    /// Set the tIME chunk to the current time to record the modification
+    pub update_time: bool,
+    #[clap(long)]
+    /// Keep unknown chunks that are not safe to copy when editing a critical chunk
+    pub keep_unsafe_to_copy: bool,
     /// [Optional] The filepath to write the encoded image to
     pub destination: Option<PathBuf>
 }
@@ -52,11 +76,328 @@ pub struct RemoveArguments {
     pub path: PathBuf,
     /// The 4-byte chunk type code to use to search for messages to remove
     pub chunk_type: String,
+    #[clap(short, long)]
+    /// Set the tIME chunk to the current time to record the modification
+    pub update_time: bool,
+    #[clap(long)]
+    /// Keep unknown chunks that are not safe to copy when editing a critical chunk
+    pub keep_unsafe_to_copy: bool
 }
 
 #[derive(Args)]
 pub struct PrintArguments {
     #[clap(required = true, parse(from_os_str))]
     /// The path to the PNG image to print chunks for
+    pub path: PathBuf,
+    #[clap(long)]
+    /// Recover what is possible from a damaged file and report the problems
+    pub lenient: bool
+}
+
+#[derive(Args)]
+pub struct CheckArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The paths to the PNG images to check
+    pub paths: Vec<PathBuf>,
+    #[clap(long)]
+    /// Print the reports as JSON
+    pub json: bool
+}
+
+#[derive(Args)]
+pub struct RepairArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the damaged PNG image to repair
+    pub path: PathBuf,
+    #[clap(long)]
+    /// Recompute wrong CRCs of critical chunks instead of refusing to repair the image
+    pub recompute_critical_crcs: bool,
+    #[clap(long)]
+    /// Recompute wrong CRCs of ancillary chunks instead of dropping them
+    pub recompute_ancillary_crcs: bool,
+    #[clap(long)]
+    /// Keep unknown chunks that are not safe to copy, even if critical chunks change
+    pub keep_unsafe_to_copy: bool,
+    #[clap(long)]
+    /// Undo the line ending conversion of a text-mode transfer, if the signature shows one
+    pub undo_text_transfer: bool,
+    #[clap(long, conflicts_with = "destination")]
+    /// Overwrite the damaged image with the repaired one
+    pub in_place: bool,
+    /// [Optional] The filepath to write the repaired image to. Defaults to <PATH> with a .repaired.png extension
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct TextArguments {
+    #[clap(subcommand)]
+    pub command: TextSubcommands
+}
+
+/// Manage the tEXt, zTXt, and iTXt keyword/text pairs stored in a PNG image
+#[derive(Subcommand)]
+pub enum TextSubcommands {
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Get(TextGetArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Set(TextSetArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Delete(TextDeleteArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    List(TextListArguments)
+}
+
+#[derive(Args)]
+pub struct TextGetArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to read text from
+    pub path: PathBuf,
+    /// The keyword of the text to print (e.g. Title, Author, Description)
+    pub keyword: String,
+    #[clap(short, long)]
+    /// Only print text written in this language (e.g. en-US)
+    pub language: Option<String>
+}
+
+#[derive(Args)]
+pub struct TextSetArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to store text within
+    pub path: PathBuf,
+    /// The keyword to store the text under (e.g. Title, Author, Description)
+    pub keyword: String,
+    /// The text to store
+    pub text: String,
+    #[clap(short, long)]
+    /// Store the text compressed in a zTXt or iTXt chunk
+    pub compress: bool,
+    #[clap(short, long)]
+    /// The language the text is written in (e.g. en-US). Stores an iTXt chunk
+    pub language: Option<String>,
+    #[clap(short, long)]
+    /// The keyword translated into the language of the text. Stores an iTXt chunk
+    pub translated_keyword: Option<String>,
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct TextDeleteArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to remove text from
+    pub path: PathBuf,
+    /// The keyword of the text to remove
+    pub keyword: String,
+    #[clap(short, long)]
+    /// Only remove text written in this language (e.g. en-US)
+    pub language: Option<String>
+}
+
+#[derive(Args)]
+pub struct TextListArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to list text for
+    pub path: PathBuf
+}
+
+#[derive(Args)]
+pub struct DpiArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to show or set the resolution of
+    pub path: PathBuf,
+    /// [Optional] The resolution in dots per inch to set. Prints the current resolution if omitted
+    pub dpi: Option<f64>,
+    #[clap(short, long)]
+    /// [Optional] A different vertical resolution in dots per inch to set
+    pub vertical: Option<f64>,
+    #[clap(short, long, parse(from_os_str))]
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct IccArguments {
+    #[clap(subcommand)]
+    pub command: IccSubcommands
+}
+
+/// Extract or embed the ICC profile stored in a PNG image
+#[derive(Subcommand)]
+pub enum IccSubcommands {
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Extract(IccExtractArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Embed(IccEmbedArguments)
+}
+
+#[derive(Args)]
+pub struct IccExtractArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to extract the ICC profile from
+    pub path: PathBuf,
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to write the extracted .icc profile to
+    pub profile: PathBuf
+}
+
+#[derive(Args)]
+pub struct IccEmbedArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to embed the ICC profile within
+    pub path: PathBuf,
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the .icc profile to embed
+    pub profile: PathBuf,
+    #[clap(short, long, default_value = "ICC Profile")]
+    /// The name to store the profile under
+    pub name: String,
+    #[clap(short, long)]
+    /// Remove an existing sRGB chunk, which may not appear alongside an ICC profile
+    pub replace_srgb: bool,
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct XmpArguments {
+    #[clap(subcommand)]
+    pub command: XmpSubcommands
+}
+
+/// Read and edit the properties of the XMP packet stored in a PNG image
+#[derive(Subcommand)]
+pub enum XmpSubcommands {
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Get(XmpGetArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Set(XmpSetArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Delete(XmpDeleteArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    List(XmpListArguments)
+}
+
+#[derive(Args)]
+pub struct XmpGetArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to read the XMP property from
+    pub path: PathBuf,
+    /// The qualified name of the property to print (e.g. dc:title, xmp:CreateDate)
+    pub property: String
+}
+
+#[derive(Args)]
+pub struct XmpSetArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to store the XMP property within
+    pub path: PathBuf,
+    /// The qualified name of the property to set (e.g. dc:title, dc:creator)
+    pub property: String,
+    #[clap(required = true)]
+    /// The value to store. Array properties such as dc:creator accept several values
+    pub values: Vec<String>,
+    #[clap(short, long, parse(from_os_str))]
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct XmpDeleteArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to remove the XMP property from
+    pub path: PathBuf,
+    /// The qualified name of the property to remove
+    pub property: String
+}
+
+#[derive(Args)]
+pub struct XmpListArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to list XMP properties for
     pub path: PathBuf
 }
+
+#[derive(Args)]
+pub struct AiArguments {
+    #[clap(subcommand)]
+    pub command: AiSubcommands
+}
+
+#[derive(Subcommand)]
+pub enum AiSubcommands {
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Show(AiShowArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Strip(AiStripArguments)
+}
+
+#[derive(Args)]
+pub struct AiShowArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to show generation parameters for
+    pub path: PathBuf,
+    #[clap(long)]
+    /// Print the parameters as JSON
+    pub json: bool
+}
+
+#[derive(Args)]
+pub struct AiStripArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to strip generation parameters from
+    pub path: PathBuf,
+    #[clap(parse(from_os_str))]
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct TrailingArguments {
+    #[clap(subcommand)]
+    pub command: TrailingSubcommands
+}
+
+/// Extract or strip the data stored after the IEND chunk of a PNG image
+#[derive(Subcommand)]
+pub enum TrailingSubcommands {
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Extract(TrailingExtractArguments),
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Strip(TrailingStripArguments)
+}
+
+#[derive(Args)]
+pub struct TrailingExtractArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to extract the trailing data from
+    pub path: PathBuf,
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to write the trailing data to
+    pub destination: PathBuf
+}
+
+#[derive(Args)]
+pub struct TrailingStripArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the PNG image to strip the trailing data from
+    pub path: PathBuf,
+    #[clap(parse(from_os_str))]
+    /// [Optional] The filepath to write the modified image to
+    pub destination: Option<PathBuf>
+}
+
+#[derive(Args)]
+pub struct TonemapArguments {
+    #[clap(required = true, parse(from_os_str))]
+    /// The path to the cICP-tagged PQ or HLG PNG image to convert
+    pub path: PathBuf,
+    #[clap(required = true, parse(from_os_str))]
+    /// The filepath to write the 8-bit sRGB image to
+    pub destination: PathBuf,
+    #[clap(short, long, default_value = "reinhard")]
+    /// The tone mapping operator to use: clip, reinhard, or hable
+    pub operator: String,
+    #[clap(short, long)]
+    /// [Optional] The peak luminance in cd/m2. Read from cLLI or mDCV if omitted
+    pub peak: Option<f64>
+}
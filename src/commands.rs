@@ -1,32 +1,154 @@
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::fs;
 
-use crate::png::{Chunk, ChunkType, Png};
+use png::{CheckReport, Chunk, ChunkType, GenerationParameters, PngError, RepairChange, RepairOptions, Severity, Png, TransferCorruption, ToneMapOperator, XmpPacket, XmpValue, XMP_KEYWORD};
+use png::{strip_generation_parameters, undo_transfer_corruption};
+use png::chunk_specs::{CABX, HardBinding, ICCP, ITXT, PHYS, TEXT, TIME, ZTXT};
 use crate::args::{
     EncodeArguments, 
     DecodeArguments, 
     RemoveArguments, 
-    PrintArguments
+    PrintArguments,
+    CheckArguments,
+    RepairArguments,
+    TextGetArguments,
+    TextSetArguments,
+    TextDeleteArguments,
+    TextListArguments,
+    DpiArguments,
+    IccExtractArguments,
+    IccEmbedArguments,
+    XmpGetArguments,
+    XmpSetArguments,
+    XmpDeleteArguments,
+    XmpListArguments,
+    AiShowArguments,
+    AiStripArguments,
+    TrailingExtractArguments,
+    TrailingStripArguments,
+    TonemapArguments
 };
 
+/// Writes a PNG file to the given path
+fn save(png: &Png, path: &Path) -> Result<(), String> {
+    match fs::write(path, png.as_bytes()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Could not save to file '{:#?}'", path))
+    }
+}
+
+/// Replaces any tIME chunks in a PNG file with one holding the current time
+fn update_time(png: &mut Png) {
+    while png.remove_chunk("tIME").is_ok() {}
+    png.append_chunk(TIME::now().to_chunk());
+}
+
+/// Recomputes the C2PA hard binding of a PNG file, if it holds Content Credentials
+fn hard_binding(png: &Png) -> Option<Result<HardBinding, String>> {
+    match png.typed_chunk::<CABX>()? {
+        Ok(cabx) => cabx.hard_binding(png),
+        Err(e) => Some(Err(e))
+    }
+}
+
+/// Returns true if the PNG file holds Content Credentials whose hard binding
+/// still matches the bytes of the file
+fn has_valid_provenance(png: &Png) -> bool {
+    matches!(hard_binding(png), Some(Ok(binding)) if binding.is_valid())
+}
+
+/// Warns if an edit broke the Content Credentials of a PNG file that were
+/// valid before the edit
+fn warn_if_provenance_broken(was_valid: bool, png: &Png) {
+    if was_valid && !has_valid_provenance(png) {
+        eprintln!("Warning: this edit invalidates the Content Credentials (C2PA) of the image.");
+    }
+}
+
+/// Drops the unknown chunks that are not safe to copy after a critical
+/// chunk was edited, as the PNG spec requires, unless asked to keep them
+fn drop_unsafe_to_copy(png: &mut Png, chunk_type: &ChunkType, keep: bool) {
+    if !chunk_type.is_critical() || keep {
+        return;
+    }
+
+    for chunk in png.remove_unsafe_to_copy_chunks() {
+        eprintln!("Removed the {} chunk, which is not safe to copy after a critical chunk changes.", chunk.chunk_type());
+    }
+}
+
+/// A keyword and text pair decoded from a tEXt, zTXt, or iTXt chunk
+struct TextEntry {
+    keyword: String,
+    language_tag: String,
+    text: String
+}
+
+impl TextEntry {
+    /// Returns true if this entry is stored under the given keyword. If a
+    /// language tag is given, the entry must also be written in that language.
+    fn matches(&self, keyword: &str, language_tag: Option<&str>) -> bool {
+        self.keyword == keyword 
+            && language_tag.is_none_or(|l| self.language_tag.eq_ignore_ascii_case(l))
+    }
+}
+
+/// Decodes a tEXt, zTXt, or iTXt chunk into a `TextEntry`
+fn text_entry(chunk: &Chunk) -> Option<TextEntry> {
+    match chunk.chunk_type().to_string().as_str() {
+        "tEXt" => TEXT::try_from(chunk.clone()).ok().map(|t| TextEntry {
+            keyword: t.keyword().to_string(),
+            language_tag: String::new(),
+            text: t.text().to_string()
+        }),
+        "zTXt" => ZTXT::try_from(chunk.clone()).ok().map(|t| TextEntry {
+            keyword: t.keyword().to_string(),
+            language_tag: String::new(),
+            text: t.text().to_string()
+        }),
+        "iTXt" => ITXT::try_from(chunk.clone()).ok().map(|t| TextEntry {
+            keyword: t.keyword().to_string(),
+            language_tag: t.language_tag().to_string(),
+            text: t.text().to_string()
+        }),
+        _ => None
+    }
+}
+
+/// Collects all of the valid textual entries in a PNG file
+fn text_entries(png: &Png) -> Vec<TextEntry> {
+    png.chunks().iter().filter_map(text_entry).collect()
+}
+
+/// Returns true if the chunk is a textual chunk matching the given keyword 
+/// and language tag
+fn is_text_with_keyword(chunk: &Chunk, keyword: &str, language_tag: Option<&str>) -> bool {
+    text_entry(chunk).is_some_and(|entry| entry.matches(keyword, language_tag))
+}
+
 /// Encodes a message into a PNG file and saves the result
 pub fn encode(args: &EncodeArguments) -> Result<(), String> {
     let mut png: Png = Png::from_file(&args.path)?;
-    let new_chunk: Chunk = Chunk::new(
-        ChunkType::from_str(args.chunk_type.as_str())?, 
-        args.message.as_bytes().to_vec()
-    );
+    let was_valid: bool = has_valid_provenance(&png);
+    let chunk_type: ChunkType = ChunkType::from_str(args.chunk_type.as_str())?;
+    let new_chunk: Chunk = Chunk::new(chunk_type.clone(), args.message.as_bytes().to_vec());
     png.append_chunk(new_chunk);
+    drop_unsafe_to_copy(&mut png, &chunk_type, args.keep_unsafe_to_copy);
+
+    if args.update_time {
+        update_time(&mut png);
+    }
+
+    warn_if_provenance_broken(was_valid, &png);
 
     let outfile = match &args.destination {
         Some(p) => p,
         None => &args.path
     };
 
-    match fs::write(outfile, png.as_bytes()) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(format!("Could not save to file '{:#?}'", &args.path))
-    }
+    save(&png, outfile)
 }
 
 /// Searches for a message hidden in a PNG file and prints the message if one is found
@@ -48,23 +170,483 @@ pub fn decode(args: &DecodeArguments) -> Result<(), String> {
 /// Removes a chunk from a PNG file and saves the result
 pub fn remove(args: &RemoveArguments) -> Result<(), String> {
     let mut png: Png = Png::from_file(&args.path)?;
-    let chunk_type: &str = args.chunk_type.as_str();
+    let was_valid: bool = has_valid_provenance(&png);
+    let chunk_type: ChunkType = ChunkType::from_str(args.chunk_type.as_str())?;
+
+    let mut removed: bool = false;
 
-    while png.remove_chunk(chunk_type).is_ok() {
-        ()
+    while png.remove_chunk(args.chunk_type.as_str()).is_ok() {
+        removed = true;
     }
 
-    match fs::write(&args.path, png.as_bytes()) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(format!("Could not save to file '{:#?}'", &args.path))
+    if removed {
+        drop_unsafe_to_copy(&mut png, &chunk_type, args.keep_unsafe_to_copy);
+    }
+
+    if args.update_time {
+        update_time(&mut png);
     }
+
+    warn_if_provenance_broken(was_valid, &png);
+
+    save(&png, &args.path)
 }
 
 /// Prints all of the chunks in a PNG file
 pub fn print_chunks(args: &PrintArguments) -> Result<(), String> {
-    let png: Png = Png::from_file(&args.path)?;
+    let png: Png = match args.lenient {
+        true => {
+            let (png, diagnostics) = Png::from_file_lenient(&args.path)?;
+            diagnostics.iter().for_each(|diagnostic| eprintln!("{}", diagnostic));
+            png
+        },
+        false => Png::from_file(&args.path)?
+    };
 
     print!("{}", png);
 
+    match hard_binding(&png) {
+        Some(Ok(binding)) if binding.is_valid() => println!("C2PA hard binding ({}): valid", binding.algorithm()),
+        Some(Ok(binding)) => println!("C2PA hard binding ({}): INVALID, the image was modified after signing", binding.algorithm()),
+        Some(Err(e)) => println!("C2PA hard binding: could not be checked ({})", e),
+        None => ()
+    }
+
+    Ok(())
+}
+
+/// Prints the text stored under a keyword in a PNG file
+pub fn text_get(args: &TextGetArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+
+    for entry in text_entries(&png) {
+        if entry.matches(&args.keyword, args.language.as_deref()) {
+            println!("{}", entry.text);
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores text under a keyword in a PNG file, replacing any existing text 
+/// with the same keyword, and saves the result
+pub fn text_set(args: &TextSetArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+    let language_tag: &str = args.language.as_deref().unwrap_or("");
+    let translated_keyword: &str = args.translated_keyword.as_deref().unwrap_or("");
+
+    // iTXt is the only textual chunk that can hold a language or UTF-8 text
+    let international: bool = !language_tag.is_empty() 
+        || !translated_keyword.is_empty() 
+        || args.text.chars().any(|c| c as u32 > 255);
+
+    let entry: Chunk = match (international, args.compress) {
+        (true, compress) => ITXT::new(&args.keyword, language_tag, translated_keyword, &args.text, compress)?.to_chunk(),
+        (false, true) => ZTXT::new(&args.keyword, &args.text)?.to_chunk(),
+        (false, false) => TEXT::new(&args.keyword, &args.text)?.to_chunk()
+    };
+
+    png.retain_chunks(|c| !is_text_with_keyword(c, &args.keyword, Some(language_tag)));
+    png.append_chunk(entry);
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Removes all text stored under a keyword in a PNG file and saves the result
+pub fn text_delete(args: &TextDeleteArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+
+    png.retain_chunks(|c| !is_text_with_keyword(c, &args.keyword, args.language.as_deref()));
+
+    save(&png, &args.path)
+}
+
+/// Prints every keyword and text pair stored in a PNG file
+pub fn text_list(args: &TextListArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+
+    for entry in text_entries(&png) {
+        match entry.language_tag.is_empty() {
+            true => println!("{}: {}", entry.keyword, entry.text),
+            false => println!("{} [{}]: {}", entry.keyword, entry.language_tag, entry.text)
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the resolution stored in the pHYs chunk of a PNG file
+fn print_dpi(png: &Png) -> Result<(), String> {
+    let phys: PHYS = match png.chunk_by_type("pHYs") {
+        Some(chunk) => PHYS::try_from(chunk.clone())?,
+        None => {
+            println!("No resolution specified");
+            return Ok(());
+        }
+    };
+
+    match phys.dpi() {
+        Some((x, y)) => println!("{:.2} x {:.2} DPI", x, y),
+        None => println!("Unknown unit, aspect ratio {}:{}", 
+            phys.pixels_per_unit_x(), phys.pixels_per_unit_y())
+    }
+
+    Ok(())
+}
+
+/// Prints the resolution of a PNG file, or sets it and saves the result
+pub fn dpi(args: &DpiArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+
+    let dpi_x: f64 = match args.dpi {
+        Some(dpi) => dpi,
+        None => return print_dpi(&png)
+    };
+
+    let phys: PHYS = PHYS::from_dpi(dpi_x, args.vertical.unwrap_or(dpi_x))?;
+
+    while png.remove_chunk("pHYs").is_ok() {}
+    png.insert_chunk_before(phys.to_chunk(), &["IDAT"]);
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Writes the ICC profile embedded in a PNG file to an .icc file
+pub fn icc_extract(args: &IccExtractArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+
+    let iccp: ICCP = match png.chunk_by_type("iCCP") {
+        Some(chunk) => ICCP::try_from(chunk.clone())?,
+        None => return Err("PNG file does not contain an embedded ICC profile.".into())
+    };
+
+    match fs::write(&args.profile, iccp.profile()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Could not save to file '{:#?}'", &args.profile))
+    }
+}
+
+/// Embeds an ICC profile from an .icc file into a PNG file and saves the result
+pub fn icc_embed(args: &IccEmbedArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+
+    let profile: Vec<u8> = match fs::read(&args.profile) {
+        Ok(b) => b,
+        Err(_) => return Err(format!("Could not open file '{:#?}'", &args.profile))
+    };
+    let iccp: ICCP = ICCP::new(&args.name, profile)?;
+
+    // the PNG spec forbids an iCCP chunk and an sRGB chunk from both being present
+    if png.chunk_by_type("sRGB").is_some() {
+        if !args.replace_srgb {
+            return Err("PNG file contains an sRGB chunk, which may not appear alongside an ICC profile. Use --replace-srgb to remove it.".into());
+        }
+
+        while png.remove_chunk("sRGB").is_ok() {}
+    }
+
+    while png.remove_chunk("iCCP").is_ok() {}
+    png.insert_chunk_before(iccp.to_chunk(), &["PLTE", "IDAT"]);
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Reads the XMP packet of a PNG file, or an empty packet if there is none
+fn read_xmp(png: &Png) -> Result<XmpPacket, String> {
+    XmpPacket::from_png(png).unwrap_or_else(|| Ok(XmpPacket::new()))
+}
+
+/// Replaces the XMP packet of a PNG file
+fn write_xmp(png: &mut Png, packet: &XmpPacket) -> Result<(), String> {
+    png.retain_chunks(|c| !is_text_with_keyword(c, XMP_KEYWORD, None));
+    png.insert_chunk_before(packet.to_chunk()?, &["IDAT"]);
+
+    Ok(())
+}
+
+/// Prints the value of an XMP property stored in a PNG file
+pub fn xmp_get(args: &XmpGetArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+    let packet: XmpPacket = read_xmp(&png)?;
+
+    match packet.get(&args.property) {
+        Some(XmpValue::Bag(items)) | Some(XmpValue::Seq(items)) => items.iter().for_each(|i| println!("{}", i)),
+        Some(value) => println!("{}", value.default_text().map(String::from).unwrap_or_else(|| value.to_string())),
+        None => return Err(format!("PNG file does not contain the XMP property '{}'.", args.property))
+    }
+
+    Ok(())
+}
+
+/// Sets the value of an XMP property in a PNG file and saves the result
+pub fn xmp_set(args: &XmpSetArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+    let mut packet: XmpPacket = read_xmp(&png)?;
+
+    packet.set_values(&args.property, args.values.clone())?;
+    write_xmp(&mut png, &packet)?;
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Removes an XMP property from a PNG file and saves the result
+pub fn xmp_delete(args: &XmpDeleteArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+    let mut packet: XmpPacket = read_xmp(&png)?;
+
+    if packet.remove(&args.property).is_none() {
+        return Err(format!("PNG file does not contain the XMP property '{}'.", args.property));
+    }
+
+    write_xmp(&mut png, &packet)?;
+    save(&png, &args.path)
+}
+
+/// Prints every XMP property stored in a PNG file
+pub fn xmp_list(args: &XmpListArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+    print!("{}", read_xmp(&png)?);
+
     Ok(())
 }
+
+/// Checks PNG files for conformance to the PNG spec and prints a report for
+/// each. Returns the exit code for the most serious problem found: 0 if every
+/// file conforms, 1 if there are only warnings, and 2 if there are errors.
+pub fn check(args: &CheckArguments) -> i32 {
+    let reports: Vec<(&Path, CheckReport)> = args.paths
+        .iter()
+        .map(|path| (path.as_path(), CheckReport::check_file(path)))
+        .collect();
+
+    if args.json {
+        let json: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(path, report)| {
+                let mut json: serde_json::Value = report.to_json();
+                json["file"] = path.display().to_string().into();
+                json
+            })
+            .collect();
+
+        println!("{:#}", serde_json::Value::from(json));
+    } else {
+        for (path, report) in reports.iter() {
+            print!("{}: {}", path.display(), report);
+        }
+    }
+
+    match reports.iter().filter_map(|(_, report)| report.severity()).max() {
+        None => 0,
+        Some(Severity::Warning) => 1,
+        Some(Severity::Error) => 2
+    }
+}
+
+/// Repairs a damaged PNG file and prints every change made
+pub fn repair(args: &RepairArguments) -> Result<(), String> {
+    let mut bytes: Vec<u8> = fs::read(&args.path)
+        .map_err(|source| PngError::Io { path: args.path.clone(), source })?;
+    let mut corruption: Option<TransferCorruption> = None;
+
+    if args.undo_text_transfer {
+        (bytes, corruption) = undo_transfer_corruption(&bytes)?;
+    }
+
+    let options = RepairOptions {
+        recompute_critical_crcs: args.recompute_critical_crcs,
+        recompute_ancillary_crcs: args.recompute_ancillary_crcs,
+        keep_unsafe_to_copy: args.keep_unsafe_to_copy
+    };
+    let (png, changes): (Png, Vec<RepairChange>) = Png::repair(&bytes, options).map_err(|error| match error {
+        PngError::CrcMismatch { .. } => format!("{} Use --recompute-critical-crcs to keep the chunk anyway.", error),
+        error => error.to_string()
+    })?;
+
+    if let Some(corruption) = corruption {
+        println!("Undid a text-mode transfer that {}.", corruption);
+    }
+
+    if changes.is_empty() && corruption.is_none() {
+        println!("No changes were needed.");
+        return Ok(());
+    }
+
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+
+    // the damaged file is only overwritten on request, as the repair may drop data
+    let outfile: PathBuf = match &args.destination {
+        Some(p) => p.clone(),
+        None if args.in_place => args.path.clone(),
+        None => args.path.with_extension("repaired.png")
+    };
+
+    save(&png, &outfile)?;
+    println!("Saved the repaired image to {}.", outfile.display());
+
+    Ok(())
+}
+
+/// Prints the parameters an AI generated PNG file was created with
+pub fn ai_show(args: &AiShowArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+    let parameters: GenerationParameters = GenerationParameters::from_png(&png)
+        .ok_or_else(|| "PNG file does not contain generation parameters.".to_string())??;
+
+    match args.json {
+        true => println!("{:#}", parameters.to_json()),
+        false => print!("{}", parameters)
+    }
+
+    Ok(())
+}
+
+/// Removes the generation parameters from a PNG file, keeping any other text
+pub fn ai_strip(args: &AiStripArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+    let was_valid: bool = has_valid_provenance(&png);
+
+    if strip_generation_parameters(&mut png) == 0 {
+        return Err("PNG file does not contain generation parameters.".into());
+    }
+    warn_if_provenance_broken(was_valid, &png);
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Writes the data stored after the IEND chunk of a PNG file to a file
+pub fn trailing_extract(args: &TrailingExtractArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+
+    if png.trailing_data().is_empty() {
+        return Err("PNG file does not contain data after the IEND chunk.".into());
+    }
+
+    match fs::write(&args.destination, png.trailing_data()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Could not save to file '{:#?}'", &args.destination))
+    }
+}
+
+/// Removes the data stored after the IEND chunk of a PNG file
+pub fn trailing_strip(args: &TrailingStripArguments) -> Result<(), String> {
+    let mut png: Png = Png::from_file(&args.path)?;
+    let was_valid: bool = has_valid_provenance(&png);
+
+    if png.remove_trailing_data().is_empty() {
+        return Err("PNG file does not contain data after the IEND chunk.".into());
+    }
+    warn_if_provenance_broken(was_valid, &png);
+
+    let outfile = match &args.destination {
+        Some(p) => p,
+        None => &args.path
+    };
+
+    save(&png, outfile)
+}
+
+/// Converts an HDR PNG file into an 8-bit sRGB preview and saves the result
+pub fn tonemap(args: &TonemapArguments) -> Result<(), String> {
+    let png: Png = Png::from_file(&args.path)?;
+    let operator: ToneMapOperator = ToneMapOperator::from_str(&args.operator)?;
+
+    let sdr: Png = png::tone_map_to_srgb(&png, operator, args.peak)?;
+
+    save(&sdr, &args.destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Executable, Subcommands};
+    use clap::Parser;
+
+    /// Writes a PNG file holding an unknown chunk that is not safe to copy
+    /// and returns its path
+    fn testing_file(name: &str) -> PathBuf {
+        let chunk = |chunk_type: &str| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), vec![]);
+        let png: Png = Png::from_chunks(
+            ["IHDR", "prVT", "prVt", "IDAT", "RuSt", "IEND"].iter().map(|c| chunk(c)).collect()
+        );
+
+        let path: PathBuf = std::env::temp_dir().join(format!("png-commands-{}-{}.png", name, std::process::id()));
+        save(&png, &path).unwrap();
+        path
+    }
+
+    /// Parses and runs an encode or remove command line, then returns the
+    /// chunk types of the edited file
+    fn run(path: &Path, command: &[&str]) -> Vec<String> {
+        let path_arg: &str = path.to_str().unwrap();
+        let mut argv: Vec<&str> = vec!["png", command[0], path_arg];
+        argv.extend(&command[1..]);
+
+        match Executable::try_parse_from(argv).unwrap().command {
+            Subcommands::Encode(args) => encode(&args).unwrap(),
+            Subcommands::Remove(args) => remove(&args).unwrap(),
+            _ => unreachable!()
+        }
+
+        let png: Png = Png::from_file(path).unwrap();
+        fs::remove_file(path).unwrap();
+        png.chunks().iter().map(|c| c.chunk_type().to_string()).collect()
+    }
+
+    #[test]
+    fn test_encode_ancillary_keeps_unsafe_to_copy() {
+        let chunk_types = run(&testing_file("encode-ancillary"), &["encode", "ruSt", "message"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVT", "prVt", "IDAT", "RuSt", "ruSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_encode_critical_drops_unsafe_to_copy() {
+        let chunk_types = run(&testing_file("encode-critical"), &["encode", "RuSt", "message"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVt", "IDAT", "RuSt", "RuSt", "IEND"]);
+
+        let chunk_types = run(&testing_file("encode-keep"), &["encode", "--keep-unsafe-to-copy", "RuSt", "message"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVT", "prVt", "IDAT", "RuSt", "RuSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_remove_critical_drops_unsafe_to_copy() {
+        let chunk_types = run(&testing_file("remove-ancillary"), &["remove", "prVt"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVT", "IDAT", "RuSt", "IEND"]);
+
+        let chunk_types = run(&testing_file("remove-missing"), &["remove", "PLTE"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVT", "prVt", "IDAT", "RuSt", "IEND"]);
+
+        let chunk_types = run(&testing_file("remove-critical"), &["remove", "RuSt"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVt", "IDAT", "IEND"]);
+
+        let chunk_types = run(&testing_file("remove-keep"), &["remove", "--keep-unsafe-to-copy", "RuSt"]);
+        assert_eq!(chunk_types, vec!["IHDR", "prVT", "prVt", "IDAT", "IEND"]);
+    }
+}
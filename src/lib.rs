@@ -0,0 +1,3 @@
+mod png;
+
+pub use crate::png::*;
@@ -1,14 +1,11 @@
 mod commands;
 mod args;
-mod png;
 
-use args::{Executable, Subcommands};
+use args::{AiSubcommands, Executable, IccSubcommands, Subcommands, TextSubcommands, TrailingSubcommands, XmpSubcommands};
 use clap::Parser;
+use std::process;
 
-use std::convert::TryFrom;
-use png::chunk_specs::IHDR;
-
-fn main() -> Result<(), String> {    
+fn main() -> Result<(), String> {
     let args = Executable::parse();
 
     match &args.command {
@@ -16,16 +13,36 @@ fn main() -> Result<(), String> {
         Subcommands::Decode(args) => commands::decode(args)?,
         Subcommands::Remove(args) => commands::remove(args)?,
         Subcommands::Print(args) => commands::print_chunks(args)?,
+        Subcommands::Check(args) => process::exit(commands::check(args)),
+        Subcommands::Repair(args) => commands::repair(args)?,
+        Subcommands::Text(args) => match &args.command {
+            TextSubcommands::Get(args) => commands::text_get(args)?,
+            TextSubcommands::Set(args) => commands::text_set(args)?,
+            TextSubcommands::Delete(args) => commands::text_delete(args)?,
+            TextSubcommands::List(args) => commands::text_list(args)?,
+        },
+        Subcommands::Dpi(args) => commands::dpi(args)?,
+        Subcommands::Icc(args) => match &args.command {
+            IccSubcommands::Extract(args) => commands::icc_extract(args)?,
+            IccSubcommands::Embed(args) => commands::icc_embed(args)?,
+        },
+        Subcommands::Xmp(args) => match &args.command {
+            XmpSubcommands::Get(args) => commands::xmp_get(args)?,
+            XmpSubcommands::Set(args) => commands::xmp_set(args)?,
+            XmpSubcommands::Delete(args) => commands::xmp_delete(args)?,
+            XmpSubcommands::List(args) => commands::xmp_list(args)?,
+        },
+        Subcommands::Ai(args) => match &args.command {
+            AiSubcommands::Show(args) => commands::ai_show(args)?,
+            AiSubcommands::Strip(args) => commands::ai_strip(args)?,
+        },
+        Subcommands::Trailing(args) => match &args.command {
+            TrailingSubcommands::Extract(args) => commands::trailing_extract(args)?,
+            TrailingSubcommands::Strip(args) => commands::trailing_strip(args)?,
+        },
+        Subcommands::Tonemap(args) => commands::tonemap(args)?,
     }
 
     Ok(())
 }
 
-// fn main() -> Result<(), String> {
-//     let image = png::Png::from_file("images/dice.png")?;
-
-//     let ihdr = IHDR::try_from(image.chunks()[0].clone())?;
-//     println!("{}", ihdr);
-
-//     Ok(())
-// }
@@ -0,0 +1,265 @@
+use std::convert::TryInto;
+
+/// The deepest nesting of arrays, maps, and tags that will be decoded.
+const MAX_DEPTH: usize = 64;
+
+/// A decoded CBOR data item. See RFC 8949 for more details.
+/// https://www.rfc-editor.org/rfc/rfc8949.html
+#[derive(Debug, Clone, PartialEq)]
+pub enum CborValue {
+    Unsigned(u64),
+    /// The negative integer `-1 - n`.
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(CborValue, CborValue)>),
+    Tag(u64, Box<CborValue>),
+    Bool(bool),
+    Null,
+    Undefined,
+    Float(f64)
+}
+
+impl CborValue {
+    /// Looks up a text key in a map.
+    pub fn get(&self, key: &str) -> Option<&CborValue> {
+        match self.untagged() {
+            CborValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| matches!(k, CborValue::Text(t) if t == key))
+                .map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.untagged() {
+            CborValue::Unsigned(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self.untagged() {
+            CborValue::Bytes(b) => Some(b),
+            _ => None
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self.untagged() {
+            CborValue::Text(t) => Some(t),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[CborValue]> {
+        match self.untagged() {
+            CborValue::Array(items) => Some(items),
+            _ => None
+        }
+    }
+
+    /// The value with any semantic tags removed.
+    fn untagged(&self) -> &CborValue {
+        match self {
+            CborValue::Tag(_, value) => value.untagged(),
+            value => value
+        }
+    }
+}
+
+/// Decodes a single CBOR data item that spans all of `data`.
+pub fn decode(data: &[u8]) -> Result<CborValue, String> {
+    let mut decoder = Decoder { data, position: 0 };
+    let value: CborValue = decoder.item(0)?;
+
+    if decoder.position != data.len() {
+        return Err(format!("CBOR item is followed by {} unexpected bytes.", data.len() - decoder.position));
+    }
+
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end: usize = self.position
+            .checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "CBOR item is truncated.".to_string())?;
+
+        let bytes: &'a [u8] = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Reads the argument following an initial byte. Returns `None` for the
+    /// indefinite length marker.
+    fn argument(&mut self, additional: u8) -> Result<Option<u64>, String> {
+        match additional {
+            0..=23 => Ok(Some(additional as u64)),
+            24 => Ok(Some(self.take(1)?[0] as u64)),
+            25 => Ok(Some(u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64)),
+            26 => Ok(Some(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64)),
+            27 => Ok(Some(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))),
+            31 => Ok(None),
+            _ => Err(format!("Invalid CBOR additional information {}.", additional))
+        }
+    }
+
+    fn length(&mut self, additional: u8) -> Result<Option<usize>, String> {
+        match self.argument(additional)? {
+            Some(length) if length > self.data.len() as u64 => Err("CBOR length exceeds the available data.".into()),
+            length => Ok(length.map(|l| l as usize))
+        }
+    }
+
+    /// Returns true and consumes the break marker if it is next.
+    fn at_break(&mut self) -> Result<bool, String> {
+        match self.data.get(self.position) {
+            Some(0xff) => {
+                self.position += 1;
+                Ok(true)
+            },
+            Some(_) => Ok(false),
+            None => Err("CBOR item is truncated.".into())
+        }
+    }
+
+    fn string(&mut self, major: u8, additional: u8) -> Result<Vec<u8>, String> {
+        match self.length(additional)? {
+            Some(length) => Ok(self.take(length)?.to_vec()),
+            None => {
+                // indefinite length strings are a series of definite length chunks
+                let mut bytes: Vec<u8> = Vec::new();
+                while !self.at_break()? {
+                    let initial: u8 = self.take(1)?[0];
+                    match (initial >> 5 == major, self.length(initial & 0x1f)?) {
+                        (true, Some(length)) => bytes.extend_from_slice(self.take(length)?),
+                        _ => return Err("Invalid chunk in indefinite length CBOR string.".into())
+                    }
+                }
+                Ok(bytes)
+            }
+        }
+    }
+
+    fn item(&mut self, depth: usize) -> Result<CborValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("CBOR items are nested too deeply.".into());
+        }
+
+        let initial: u8 = self.take(1)?[0];
+        let (major, additional): (u8, u8) = (initial >> 5, initial & 0x1f);
+
+        match major {
+            0 | 1 | 6 if additional == 31 => Err("Invalid indefinite length CBOR item.".into()),
+            0 => Ok(CborValue::Unsigned(self.argument(additional)?.unwrap())),
+            1 => Ok(CborValue::Negative(self.argument(additional)?.unwrap())),
+            2 => Ok(CborValue::Bytes(self.string(major, additional)?)),
+            3 => String::from_utf8(self.string(major, additional)?)
+                .map(CborValue::Text)
+                .map_err(|_| "CBOR text string is not valid UTF-8.".to_string()),
+            4 => {
+                let mut items: Vec<CborValue> = Vec::new();
+                match self.length(additional)? {
+                    Some(length) => for _ in 0..length {
+                        items.push(self.item(depth + 1)?);
+                    },
+                    None => while !self.at_break()? {
+                        items.push(self.item(depth + 1)?);
+                    }
+                }
+                Ok(CborValue::Array(items))
+            },
+            5 => {
+                let mut entries: Vec<(CborValue, CborValue)> = Vec::new();
+                match self.length(additional)? {
+                    Some(length) => for _ in 0..length {
+                        entries.push((self.item(depth + 1)?, self.item(depth + 1)?));
+                    },
+                    None => while !self.at_break()? {
+                        entries.push((self.item(depth + 1)?, self.item(depth + 1)?));
+                    }
+                }
+                Ok(CborValue::Map(entries))
+            },
+            6 => {
+                let tag: u64 = self.argument(additional)?.unwrap();
+                Ok(CborValue::Tag(tag, Box::new(self.item(depth + 1)?)))
+            },
+            _ => match additional {
+                20 => Ok(CborValue::Bool(false)),
+                21 => Ok(CborValue::Bool(true)),
+                22 => Ok(CborValue::Null),
+                23 => Ok(CborValue::Undefined),
+                25 => Ok(CborValue::Float(half_to_f64(u16::from_be_bytes(self.take(2)?.try_into().unwrap())))),
+                26 => Ok(CborValue::Float(f32::from_be_bytes(self.take(4)?.try_into().unwrap()) as f64)),
+                27 => Ok(CborValue::Float(f64::from_be_bytes(self.take(8)?.try_into().unwrap()))),
+                _ => Err(format!("Unsupported CBOR simple value {}.", additional))
+            }
+        }
+    }
+}
+
+/// Converts an IEEE 754 half-precision float.
+fn half_to_f64(half: u16) -> f64 {
+    let exponent: i32 = ((half >> 10) & 0x1f) as i32;
+    let mantissa: f64 = (half & 0x3ff) as f64;
+    let magnitude: f64 = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15)
+    };
+
+    match half >> 15 {
+        1 => -magnitude,
+        _ => magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_decode() {
+        // {"alg": "sha256", "n": [1, -2, h'0102'], "f": 1.5}
+        let data: Vec<u8> = vec![
+            0xa3, 0x63, b'a', b'l', b'g', 0x66, b's', b'h', b'a', b'2', b'5', b'6',
+            0x61, b'n', 0x83, 0x01, 0x21, 0x42, 0x01, 0x02,
+            0x61, b'f', 0xf9, 0x3e, 0x00
+        ];
+        let value = decode(&data).unwrap();
+
+        assert_eq!(value.get("alg").unwrap().as_text(), Some("sha256"));
+        assert_eq!(value.get("n").unwrap().as_array().unwrap(), &[
+            CborValue::Unsigned(1), CborValue::Negative(1), CborValue::Bytes(vec![1, 2])
+        ]);
+        assert_eq!(value.get("f"), Some(&CborValue::Float(1.5)));
+    }
+
+    #[test]
+    fn test_cbor_indefinite_and_tags() {
+        // tag 24 wrapping an indefinite array holding an indefinite text string
+        let data: Vec<u8> = vec![0xd8, 0x18, 0x9f, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xff];
+        let value = decode(&data).unwrap();
+
+        assert_eq!(value.as_array().unwrap(), &[CborValue::Text("ab".to_string())]);
+    }
+
+    #[test]
+    fn test_cbor_invalid() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[0x5a, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(decode(&[0x01, 0x02]).is_err());
+        assert!(decode(&[0x81; 100]).is_err());
+    }
+}
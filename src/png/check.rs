@@ -0,0 +1,359 @@
+use crate::png::chunk_specs::{ChunkRegistry, IHDR};
+use crate::png::{structure, zlib, Chunk, ChunkType, Png, PngError};
+use serde_json::{json, Value};
+use std::convert::{TryFrom, TryInto};
+use std::path::Path;
+use std::{fmt, fs};
+
+/// The critical chunk types defined by the PNG spec. A decoder cannot show
+/// an image correctly if it holds any other critical chunk.
+pub const KNOWN_CRITICAL_CHUNKS: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+/// How serious a problem found by a conformance check is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The file breaks a recommendation, or holds data most decoders ignore.
+    Warning,
+    /// The file breaks a requirement of the PNG spec.
+    Error
+}
+
+/// A single problem found by a conformance check.
+#[derive(Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub error: PngError
+}
+
+/// The result of checking a PNG file against the PNG spec, in the manner of
+/// `pngcheck`. Unlike parsing, checking carries on past damaged chunks
+/// whenever their length can be trusted, so that every problem is reported.
+pub struct CheckReport {
+    findings: Vec<Finding>,
+    chunk_count: usize,
+    header: Option<IHDR>
+}
+
+impl CheckReport {
+    /// Checks the signature and the CRC of every chunk, the IHDR fields, the
+    /// chunk ordering, the zlib integrity and size of the image data, and
+    /// reports unknown critical chunks and data trailing the IEND chunk.
+    pub fn check(bytes: &[u8]) -> CheckReport {
+        let mut report = CheckReport { findings: Vec::new(), chunk_count: 0, header: None };
+
+        if let Err(error) = Png::check_signature(bytes) {
+            report.error(error);
+            return report;
+        }
+
+        let (chunks, offsets): (Vec<Chunk>, Vec<usize>) = report.read_chunks(bytes);
+        let png: Png = Png::from_chunks(chunks);
+        report.chunk_count = png.chunks().len();
+
+        for violation in structure::check_structure_at(png.chunks(), &offsets) {
+            report.error(violation);
+        }
+
+        if let Some(chunk) = png.chunks().first().filter(|c| c.chunk_type().bytes() == *b"IHDR") {
+            match IHDR::try_from(chunk.clone()) {
+                Ok(header) => report.header = Some(header),
+                Err(error) => report.error(error)
+            }
+        }
+
+        let registry: &ChunkRegistry = ChunkRegistry::shared();
+        for (idx, chunk) in png.chunks().iter().enumerate() {
+            let chunk_type: &ChunkType = chunk.chunk_type();
+            let invalid = |reason: String| PngError::InvalidChunk {
+                offset: offsets[idx],
+                chunk_index: idx,
+                chunk_type: chunk_type.clone(),
+                reason
+            };
+
+            if chunk_type.is_critical() && !structure::is_defined(chunk_type) {
+                report.error(invalid("Unknown critical chunk. The image cannot be shown correctly without it.".into()));
+            } else if !chunk_type.is_critical() {
+                match registry.parse(chunk) {
+                    Some(Ok(parsed)) => if let Err(reason) = parsed.validate_against(&png) {
+                        report.warning(invalid(reason));
+                    },
+                    // a decompression bomb is refused outright, not merely ignored
+                    Some(Err(reason)) if reason.starts_with(zlib::LIMIT_EXCEEDED) => report.error(invalid(reason)),
+                    Some(Err(reason)) => report.warning(invalid(reason)),
+                    None => ()
+                }
+            }
+        }
+
+        if let Some(error) = report.header.as_ref().and_then(|header| check_image_data(&png, &offsets, header)) {
+            report.error(error);
+        }
+
+        report.findings.sort_by_key(|f| f.error.offset());
+        report
+    }
+
+    /// Checks the PNG file at the given path. A file that cannot be read is
+    /// reported as an error.
+    pub fn check_file<P: AsRef<Path>>(path: P) -> CheckReport {
+        match fs::read(&path) {
+            Ok(bytes) => CheckReport::check(&bytes),
+            Err(source) => {
+                let mut report = CheckReport { findings: Vec::new(), chunk_count: 0, header: None };
+                report.error(PngError::Io { path: path.as_ref().to_path_buf(), source });
+                report
+            }
+        }
+    }
+
+    /// Every problem found, in the order they appear in the file.
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// The most serious problem found, or `None` if the file conforms.
+    pub fn severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    /// The number of chunks that could be read.
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count
+    }
+
+    /// The image header, if it could be read and is valid.
+    pub fn header(&self) -> Option<&IHDR> {
+        self.header.as_ref()
+    }
+
+    /// The report as a JSON object, for use by other tools.
+    pub fn to_json(&self) -> Value {
+        let findings = |severity: Severity| -> Vec<Value> {
+            self.findings
+                .iter()
+                .filter(|f| f.severity == severity)
+                .map(|f| json!({
+                    "offset": f.error.offset(),
+                    "chunk_index": f.error.chunk_index(),
+                    "message": f.error.to_string()
+                }))
+                .collect()
+        };
+
+        json!({
+            "status": match self.severity() {
+                None => "ok",
+                Some(Severity::Warning) => "warnings",
+                Some(Severity::Error) => "errors"
+            },
+            "chunks": self.chunk_count,
+            "header": self.header.as_ref().map(|h| json!({
+                "width": h.width(),
+                "height": h.height(),
+                "bit_depth": h.bit_depth(),
+                "color_type": h.color_type(),
+                "interlace_method": h.interlace_method()
+            })),
+            "errors": findings(Severity::Error),
+            "warnings": findings(Severity::Warning)
+        })
+    }
+
+    fn error(&mut self, error: PngError) {
+        self.findings.push(Finding { severity: Severity::Error, error });
+    }
+
+    fn warning(&mut self, error: PngError) {
+        self.findings.push(Finding { severity: Severity::Warning, error });
+    }
+
+    /// Reads chunks until the IEND chunk or the first chunk whose length
+    /// cannot be trusted. Chunks with a bad CRC are reported and kept.
+    fn read_chunks(&mut self, bytes: &[u8]) -> (Vec<Chunk>, Vec<usize>) {
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut offsets: Vec<usize> = Vec::new();
+        let mut start: usize = 8;
+
+        while start < bytes.len() {
+            let remaining: &[u8] = &bytes[start..];
+            let chunk_length: usize = match remaining.get(..4) {
+                Some(length) => (u32::from_be_bytes(length.try_into().unwrap()) as usize).saturating_add(12),
+                None => 12
+            };
+
+            let chunk: Chunk = match remaining.get(..chunk_length).map(Chunk::try_from) {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(error @ PngError::CrcMismatch { .. })) => {
+                    self.error(error.relocate(start, chunks.len()));
+                    let chunk_type: [u8; 4] = remaining[4..8].try_into().unwrap();
+                    Chunk::new(ChunkType::try_from(chunk_type).unwrap(), remaining[8..chunk_length - 4].to_vec())
+                },
+                Some(Err(error)) => {
+                    self.error(error.relocate(start, chunks.len()));
+                    break;
+                },
+                None => {
+                    self.error(PngError::Truncated {
+                        offset: start,
+                        chunk_index: chunks.len(),
+                        expected: chunk_length,
+                        available: remaining.len()
+                    });
+                    break;
+                }
+            };
+
+            let is_iend: bool = chunk.chunk_type().bytes() == *b"IEND";
+            chunks.push(chunk);
+            offsets.push(start);
+            start += chunk_length;
+
+            if is_iend && start < bytes.len() {
+                self.warning(PngError::TrailingData { offset: start, chunk_index: chunks.len(), length: bytes.len() - start });
+                break;
+            }
+        }
+
+        (chunks, offsets)
+    }
+}
+
+/// Inflates the concatenated IDAT chunks, checking that the datastream is
+/// intact and holds exactly as many bytes as the header requires.
+fn check_image_data(png: &Png, offsets: &[usize], header: &IHDR) -> Option<PngError> {
+    let first_idat: usize = png.chunks().iter().position(|c| c.chunk_type().bytes() == *b"IDAT")?;
+    let compressed: Vec<u8> = png.chunks()
+        .iter()
+        .filter(|c| c.chunk_type().bytes() == *b"IDAT")
+        .flat_map(|c| c.data().iter().copied())
+        .collect();
+
+    let expected: usize = usize::try_from(header.image_data_size()).unwrap_or(usize::MAX);
+    let reason: String = match zlib::inflated_size(&compressed, expected) {
+        Ok(size) if size == expected => return None,
+        Ok(size) => format!("Image data holds {} bytes, but the header requires {}.", size, expected),
+        Err(reason) => reason
+    };
+
+    Some(PngError::InvalidChunk {
+        offset: offsets[first_idat],
+        chunk_index: first_idat,
+        chunk_type: png.chunks()[first_idat].chunk_type().clone(),
+        reason
+    })
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |severity: Severity| self.findings.iter().filter(|f| f.severity == severity).count();
+
+        match (self.severity(), &self.header) {
+            (None, Some(header)) => writeln!(f, "OK ({} chunks, {}x{}, {}-bit, color type {}{})",
+                self.chunk_count, header.width(), header.height(), header.bit_depth(), header.color_type(),
+                if header.interlace_method() == 1 { ", interlaced" } else { "" })?,
+            (None, None) => writeln!(f, "OK ({} chunks)", self.chunk_count)?,
+            _ => writeln!(f, "{} errors, {} warnings", count(Severity::Error), count(Severity::Warning))?
+        }
+
+        for finding in &self.findings {
+            match finding.severity {
+                Severity::Error => writeln!(f, "  ERROR: {}", finding.error)?,
+                Severity::Warning => writeln!(f, "  WARNING: {}", finding.error)?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::chunk_specs::TEXT;
+    use crate::png::Image;
+    use std::str::FromStr;
+
+    fn valid_png() -> Png {
+        let mut png: Png = Image::new(2, 2, 8, 0, vec![0, 64, 128, 255]).unwrap().encode().unwrap();
+        png.insert_chunk_before(TEXT::new("Title", "Check").unwrap().to_chunk(), &["IDAT"]);
+        png
+    }
+
+    #[test]
+    fn test_check_valid() {
+        let report = CheckReport::check(&valid_png().as_bytes());
+
+        assert!(report.findings().is_empty(), "{}", report);
+        assert_eq!(report.severity(), None);
+        assert_eq!(report.chunk_count(), 4);
+        assert_eq!(report.to_json()["status"], "ok");
+    }
+
+    #[test]
+    fn test_check_continues_past_bad_crc() {
+        let mut bytes: Vec<u8> = valid_png().as_bytes();
+        bytes[8 + 25 + 8] ^= 0x20; // lowercase the first letter of the tEXt keyword
+        bytes.extend_from_slice(b"trailing");
+
+        let report = CheckReport::check(&bytes);
+        let messages: Vec<(Severity, Option<usize>)> = report.findings()
+            .iter()
+            .map(|f| (f.severity, f.error.chunk_index()))
+            .collect();
+
+        assert_eq!(messages, vec![(Severity::Error, Some(1)), (Severity::Warning, Some(4))]);
+        assert_eq!(report.chunk_count(), 4);
+        assert_eq!(report.to_json()["errors"][0]["offset"], 8 + 25 + 8 + 11);
+    }
+
+    #[test]
+    fn test_check_decompression_bomb() {
+        let mut data: Vec<u8> = b"Comment\0\0".to_vec();
+        data.extend(zlib::deflate(&vec![b'a'; zlib::MAX_METADATA_SIZE + 1]));
+
+        let mut png: Png = valid_png();
+        png.insert_chunk_before(Chunk::new(ChunkType::from_str("zTXt").unwrap(), data), &["IDAT"]);
+
+        let report = CheckReport::check(&png.as_bytes());
+
+        assert_eq!(report.severity(), Some(Severity::Error));
+        assert_eq!(report.findings()[0].error.chunk_index(), Some(2));
+        assert!(report.findings()[0].error.to_string().contains("exceeds the expected"));
+    }
+
+    #[test]
+    fn test_check_image_data_size() {
+        let with_image_data = |data: Vec<u8>| {
+            let mut png: Png = valid_png();
+            png.retain_chunks(|c| c.chunk_type().bytes() != *b"IDAT");
+            png.insert_chunk_before(Chunk::new(ChunkType::from_str("IDAT").unwrap(), data), &["IEND"]);
+            CheckReport::check(&png.as_bytes()).findings().iter().map(|f| f.error.to_string()).collect::<Vec<String>>()
+        };
+
+        let compressed: Vec<u8> = zlib::deflate(&[0; 6]);
+
+        assert!(with_image_data(compressed.clone()).is_empty());
+        assert!(with_image_data(compressed[..compressed.len() - 4].to_vec())[0].contains("Could not decompress"));
+        assert!(with_image_data(zlib::deflate(&vec![0; 100_000]))[0].contains("exceeds the expected 6 bytes"));
+    }
+
+    #[test]
+    fn test_check_image_data_and_critical_chunks() {
+        let mut png: Png = valid_png();
+        png.retain_chunks(|c| c.chunk_type().bytes() != *b"IDAT");
+        png.insert_chunk_before(Chunk::new(ChunkType::from_str("IDAT").unwrap(), zlib::deflate(&[0; 5])), &["IEND"]);
+        png.insert_chunk_before(Chunk::new(ChunkType::from_str("ABCD").unwrap(), Vec::new()), &["IEND"]);
+
+        let report = CheckReport::check(&png.as_bytes());
+        let errors: Vec<String> = report.findings().iter().map(|f| f.error.to_string()).collect();
+
+        assert_eq!(report.severity(), Some(Severity::Error));
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("holds 5 bytes, but the header requires 6"));
+        assert!(errors[1].contains("Unknown critical chunk"));
+
+        assert!(CheckReport::check(b"\x89PNG").findings()[0].error.to_string().contains("signature"));
+        assert!(matches!(CheckReport::check_file("does/not/exist.png").findings()[0].error, PngError::Io { .. }));
+    }
+}
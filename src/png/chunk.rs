@@ -1,4 +1,5 @@
 use crate::png::chunk_type::ChunkType;
+use crate::png::error::PngError;
 use std::convert::{TryFrom, TryInto};
 use std::string::FromUtf8Error;
 use crc::Crc;
@@ -78,10 +79,31 @@ impl Chunk {
 }
 
 impl TryFrom<&[u8]> for Chunk {
-    type Error = String;
+    type Error = PngError;
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {        
+    /// Parses a single chunk that spans all of `value`. Arbitrary input never
+    /// causes a panic.
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        // length, chunk type, and crc fields compose 12 bytes in a chunk
+        if value.len() < 12 {
+            return Err(PngError::Truncated { offset: 0, chunk_index: 0, expected: 12, available: value.len() });
+        }
+
+        let length: u32 = u32::from_be_bytes(value[..4].try_into().unwrap());
         let chunk_type_bytes: [u8; 4] = value[4..8].try_into().unwrap();
+        let chunk_type: ChunkType = ChunkType::try_from(chunk_type_bytes).map_err(|e| e.relocate(4, 0))?;
+
+        let expected: usize = (length as usize).saturating_add(12);
+        if expected > value.len() {
+            return Err(PngError::Truncated { offset: 0, chunk_index: 0, expected, available: value.len() });
+        } else if expected < value.len() {
+            return Err(PngError::InvalidChunk {
+                offset: 0,
+                chunk_index: 0,
+                chunk_type,
+                reason: format!("Chunk length {} does not match the {} data bytes given.", length, value.len() - 12)
+            });
+        }
 
         let crc_bytes: [u8; 4] = value[value.len() - 4..].try_into().unwrap();
         let given_crc: u32 = u32::from_be_bytes(crc_bytes);
@@ -89,12 +111,18 @@ impl TryFrom<&[u8]> for Chunk {
         let crc: u32 = crc_obj.checksum(&value[4..value.len() - 4]);
 
         if crc != given_crc {
-            return Err("Computed CRC does not match given CRC.".into());
+            return Err(PngError::CrcMismatch {
+                offset: value.len() - 4,
+                chunk_index: 0,
+                chunk_type,
+                stored: given_crc,
+                computed: crc
+            });
         }
 
         Ok(Chunk {
-            length: u32::from_be_bytes(value[..4].try_into().unwrap()),
-            chunk_type: ChunkType::try_from(chunk_type_bytes)?,
+            length,
+            chunk_type,
             data: value[8..value.len() - 4].into(),
             crc
         })
@@ -210,6 +238,30 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_errors() {
+        let mut bytes: Vec<u8> = testing_chunk().as_bytes();
+        let last: usize = bytes.len() - 1;
+        bytes[last] ^= 1;
+
+        match Chunk::try_from(bytes.as_ref()) {
+            Err(PngError::CrcMismatch { offset, stored, computed, .. }) => {
+                assert_eq!(offset, 50);
+                assert_eq!(stored ^ 1, computed);
+            },
+            _ => panic!("Expected a CRC mismatch.")
+        }
+
+        assert!(matches!(
+            Chunk::try_from(&bytes[..30]),
+            Err(PngError::Truncated { expected: 54, available: 30, .. })
+        ));
+        assert!(matches!(
+            Chunk::try_from(&[0, 0, 0, 0, b'R', b'u', b'5', b't', 0, 0, 0, 0][..]),
+            Err(PngError::InvalidChunkType { offset: 4, .. })
+        ));
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -0,0 +1,119 @@
+use crate::png::chunk_specs::IHDR;
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// A default background color, whose layout depends on the color type of the
+/// image. See the PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.bKGD
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BKGD {
+    /// An index into the palette, for color type 3.
+    PaletteIndex(u8),
+    /// A gray level, for color types 0 and 4.
+    Gray(u16),
+    /// Red, green, and blue samples, for color types 2 and 6.
+    Rgb(u16, u16, u16)
+}
+
+impl BKGD {
+    /// Checks that the layout matches the color type of the image and that
+    /// samples fit in its bit depth.
+    pub fn validate(&self, ihdr: &IHDR) -> Result<(), String> {
+        let max_sample: u32 = (1u32 << ihdr.bit_depth()) - 1;
+        let fits = |sample: u16| sample as u32 <= max_sample;
+
+        let valid: bool = match (self, ihdr.color_type()) {
+            (BKGD::PaletteIndex(_), 3) => true,
+            (BKGD::Gray(gray), 0) | (BKGD::Gray(gray), 4) => fits(*gray),
+            (BKGD::Rgb(r, g, b), 2) | (BKGD::Rgb(r, g, b), 6) => fits(*r) && fits(*g) && fits(*b),
+            _ => false
+        };
+
+        if !valid {
+            return Err(format!("bKGD color does not fit color type {} at bit depth {}.",
+                ihdr.color_type(), ihdr.bit_depth()));
+        }
+
+        Ok(())
+    }
+
+    /// Serializes this into a `bKGD` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = match *self {
+            BKGD::PaletteIndex(index) => vec![index],
+            BKGD::Gray(gray) => gray.to_be_bytes().to_vec(),
+            BKGD::Rgb(r, g, b) => [r, g, b].iter().flat_map(|s| s.to_be_bytes()).collect()
+        };
+
+        Chunk::new(ChunkType::from_str("bKGD").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for BKGD {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("bKGD")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'bKGD'".into());
+        }
+
+        let sample = |idx: usize| u16::from_be_bytes([value.data()[idx], value.data()[idx + 1]]);
+
+        match value.length() {
+            1 => Ok(BKGD::PaletteIndex(value.data()[0])),
+            2 => Ok(BKGD::Gray(sample(0))),
+            6 => Ok(BKGD::Rgb(sample(0), sample(2), sample(4))),
+            length => Err(format!("Chunk length {} does not match expected length 1, 2, or 6.", length))
+        }
+    }
+}
+
+chunk_spec!(BKGD, "bKGD", fn validate_context(&self, png: &Png) {
+    match png.chunk_by_type("IHDR") {
+        Some(chunk) => BKGD::validate(self, &IHDR::try_from(chunk.clone())?),
+        None => Err("bKGD chunk requires an IHDR chunk.".into())
+    }
+});
+
+impl fmt::Display for BKGD {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "bKGD Background color {{",)?;
+        match self {
+            BKGD::PaletteIndex(index) => writeln!(f, "  Palette Index: {}", index)?,
+            BKGD::Gray(gray) => writeln!(f, "  Gray: {}", gray)?,
+            BKGD::Rgb(r, g, b) => writeln!(f, "  RGB: {}, {}, {}", r, g, b)?
+        }
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bkgd_round_trip() {
+        for bkgd in [BKGD::PaletteIndex(3), BKGD::Gray(512), BKGD::Rgb(1, 2, 3)].iter() {
+            assert_eq!(&BKGD::try_from(bkgd.to_chunk()).unwrap(), bkgd);
+        }
+
+        let chunk = Chunk::new(ChunkType::from_str("bKGD").unwrap(), vec![0; 3]);
+        assert!(BKGD::try_from(chunk).is_err());
+    }
+
+    #[test]
+    fn test_bkgd_validate() {
+        let ihdr = IHDR::new(1, 1, 8, 2, 0).unwrap();
+
+        assert!(BKGD::Rgb(255, 0, 0).validate(&ihdr).is_ok());
+        assert!(BKGD::Rgb(256, 0, 0).validate(&ihdr).is_err());
+        assert!(BKGD::Gray(0).validate(&ihdr).is_err());
+        assert!(BKGD::PaletteIndex(0).validate(&IHDR::new(1, 1, 4, 3, 0).unwrap()).is_ok());
+    }
+}
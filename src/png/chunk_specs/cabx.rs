@@ -0,0 +1,566 @@
+use crate::png::cbor::{self, CborValue};
+use crate::png::{Chunk, ChunkType, Png};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The deepest nesting of JUMBF superboxes that will be parsed.
+const MAX_DEPTH: usize = 32;
+
+/// A JUMBF superbox (`jumb`), holding a description and a list of content
+/// boxes. See ISO/IEC 19566-5 for more details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumbfSuperBox {
+    content_type: [u8; 16],
+    label: Option<String>,
+    /// The whole `jumd` box, written back as it was read so that the ID,
+    /// hash, and private fields of the description are kept.
+    description: Vec<u8>,
+    size: BoxSize,
+    boxes: Vec<JumbfBox>
+}
+
+/// A box within a JUMBF superbox.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JumbfBox {
+    SuperBox(JumbfSuperBox),
+    Content { box_type: [u8; 4], data: Vec<u8>, size: BoxSize }
+}
+
+/// How the size of a box is written in its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxSize {
+    /// A 32-bit size.
+    Compact,
+    /// A size of 1 followed by a 64-bit size.
+    Extended,
+    /// A size of 0, meaning the box extends to the end of its parent.
+    ToEnd
+}
+
+impl JumbfSuperBox {
+    /// Creates a requestable superbox, labeled if `label` is given.
+    pub fn new(content_type: [u8; 16], label: Option<&str>, boxes: Vec<JumbfBox>) -> JumbfSuperBox {
+        let mut description: Vec<u8> = content_type.to_vec();
+        match label {
+            Some(label) => {
+                // requestable, with a label
+                description.push(0x03);
+                description.extend_from_slice(label.as_bytes());
+                description.push(0);
+            },
+            None => description.push(0x01)
+        }
+
+        JumbfSuperBox {
+            content_type,
+            label: label.map(String::from),
+            description: write_box(b"jumd", &description),
+            size: BoxSize::Compact,
+            boxes
+        }
+    }
+
+    /// Parses a superbox from the `jumb` box spanning all of `data`.
+    pub fn parse(data: &[u8]) -> Result<JumbfSuperBox, String> {
+        match read_boxes(data)?.as_slice() {
+            [([b'j', b'u', b'm', b'b'], content, size)] => JumbfSuperBox::parse_content(content, *size, 0),
+            _ => Err("JUMBF data must hold a single 'jumb' superbox.".into())
+        }
+    }
+
+    fn parse_content(data: &[u8], size: BoxSize, depth: usize) -> Result<JumbfSuperBox, String> {
+        if depth > MAX_DEPTH {
+            return Err("JUMBF superboxes are nested too deeply.".into());
+        }
+
+        let mut boxes = read_boxes(data)?.into_iter();
+        let (description, description_size): (&[u8], BoxSize) = match boxes.next() {
+            Some((box_type, content, size)) if &box_type == b"jumd" => (content, size),
+            _ => return Err("JUMBF superbox does not start with a 'jumd' description box.".into())
+        };
+
+        if description.len() < 17 {
+            return Err("JUMBF description box is truncated.".into());
+        }
+
+        let content_type: [u8; 16] = description[..16].try_into().unwrap();
+        let toggles: u8 = description[16];
+
+        // the label is present when bit 1 is set, and is null-terminated
+        let label: Option<String> = match toggles & 0x02 {
+            0 => None,
+            _ => {
+                let text: &[u8] = &description[17..];
+                let end: usize = text.iter().position(|&b| b == 0)
+                    .ok_or_else(|| "JUMBF label is missing its null terminator.".to_string())?;
+                Some(String::from_utf8(text[..end].to_vec())
+                    .map_err(|_| "JUMBF label is not valid UTF-8.".to_string())?)
+            }
+        };
+
+        let boxes: Vec<JumbfBox> = boxes
+            .map(|(box_type, content, size)| match &box_type {
+                b"jumb" => JumbfSuperBox::parse_content(content, size, depth + 1).map(JumbfBox::SuperBox),
+                _ => Ok(JumbfBox::Content { box_type, data: content.to_vec(), size })
+            })
+            .collect::<Result<Vec<JumbfBox>, String>>()?;
+
+        Ok(JumbfSuperBox {
+            content_type,
+            label,
+            description: write_sized_box(b"jumd", description, description_size),
+            size,
+            boxes
+        })
+    }
+
+    /// The UUID identifying the kind of content in this superbox.
+    pub fn content_type(&self) -> [u8; 16] {
+        self.content_type
+    }
+
+    /// The label of this superbox, such as `c2pa.claim`.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The boxes following the description box.
+    pub fn boxes(&self) -> &[JumbfBox] {
+        &self.boxes
+    }
+
+    /// The superboxes nested directly within this superbox.
+    pub fn superboxes(&self) -> impl Iterator<Item = &JumbfSuperBox> {
+        self.boxes.iter().filter_map(|b| match b {
+            JumbfBox::SuperBox(superbox) => Some(superbox),
+            _ => None
+        })
+    }
+
+    /// The data of the first content box, such as the CBOR of an assertion.
+    pub fn content(&self) -> Option<(&[u8; 4], &[u8])> {
+        self.boxes.iter().find_map(|b| match b {
+            JumbfBox::Content { box_type, data, .. } => Some((box_type, data.as_slice())),
+            _ => None
+        })
+    }
+
+    /// The C2PA role of this superbox, taken from the first four bytes of
+    /// its content type.
+    fn role(&self) -> &'static str {
+        match &self.content_type[..4] {
+            b"c2pa" => "Manifest Store",
+            b"c2ma" => "Manifest",
+            b"c2um" => "Update Manifest",
+            b"c2as" => "Assertion Store",
+            b"c2cl" => "Claim",
+            b"c2cs" => "Signature",
+            b"c2vc" => "Credential Store",
+            _ => "Assertion"
+        }
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}: {}", "", self.role(), self.label().unwrap_or("(unlabeled)"), indent = indent)?;
+        if let Some((box_type, data)) = self.content() {
+            write!(f, " ({}, {} bytes)", String::from_utf8_lossy(box_type), data.len())?;
+        }
+        writeln!(f)?;
+
+        for superbox in self.superboxes() {
+            superbox.write_tree(f, indent + 2)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The type, content, and size encoding of a box.
+type RawBox<'a> = ([u8; 4], &'a [u8], BoxSize);
+
+/// Splits data into ISO BMFF style boxes.
+fn read_boxes(mut data: &[u8]) -> Result<Vec<RawBox<'_>>, String> {
+    let mut boxes: Vec<RawBox> = Vec::new();
+
+    while !data.is_empty() {
+        if data.len() < 8 {
+            return Err("JUMBF box header is truncated.".into());
+        }
+
+        let length: u32 = u32::from_be_bytes(data[0..4].try_into().unwrap());
+        let box_type: [u8; 4] = data[4..8].try_into().unwrap();
+
+        // a length of 1 is followed by a 64-bit length, 0 extends to the end
+        let (header, size, encoding): (usize, u64, BoxSize) = match length {
+            0 => (8, data.len() as u64, BoxSize::ToEnd),
+            1 if data.len() >= 16 => (16, u64::from_be_bytes(data[8..16].try_into().unwrap()), BoxSize::Extended),
+            1 => return Err("JUMBF box header is truncated.".into()),
+            length => (8, length as u64, BoxSize::Compact)
+        };
+
+        if size < header as u64 || size > data.len() as u64 {
+            return Err(format!("JUMBF box '{}' has invalid length {}.", String::from_utf8_lossy(&box_type), size));
+        }
+
+        boxes.push((box_type, &data[header..size as usize], encoding));
+        data = &data[size as usize..];
+    }
+
+    Ok(boxes)
+}
+
+/// A range of file bytes left out of a hard-binding hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashExclusion {
+    pub start: u64,
+    pub length: u64
+}
+
+/// The result of recomputing the `c2pa.hash.data` hard binding of the
+/// active manifest against the current bytes of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardBinding {
+    algorithm: String,
+    exclusions: Vec<HashExclusion>,
+    expected: Vec<u8>,
+    actual: Vec<u8>
+}
+
+impl HardBinding {
+    /// The hash algorithm, such as `sha256`.
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    /// The byte ranges left out of the hash.
+    pub fn exclusions(&self) -> &[HashExclusion] {
+        &self.exclusions
+    }
+
+    /// Whether the hash recorded in the manifest matches the file, meaning
+    /// the file has not been modified outside of the excluded ranges.
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// A C2PA manifest store (Content Credentials) embedded in a `caBX` chunk.
+/// The chunk holds a JUMBF superbox containing one or more manifests, each
+/// holding assertions, a claim, and a signature. Signatures are not verified.
+/// See the C2PA specification for more details.
+/// https://c2pa.org/specifications/specifications/2.1/specs/C2PA_Specification.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct CABX {
+    store: JumbfSuperBox
+}
+
+impl CABX {
+    /// Creates a new `caBX` from a manifest store superbox.
+    pub fn new(store: JumbfSuperBox) -> Result<CABX, String> {
+        if &store.content_type[..4] != b"c2pa" {
+            return Err("caBX superbox is not a C2PA manifest store.".into());
+        }
+
+        Ok(CABX { store })
+    }
+
+    /// The manifest store superbox.
+    pub fn store(&self) -> &JumbfSuperBox {
+        &self.store
+    }
+
+    /// The manifests in the store. The last manifest is the active manifest.
+    pub fn manifests(&self) -> Vec<&JumbfSuperBox> {
+        self.store
+            .superboxes()
+            .filter(|m| matches!(&m.content_type[..4], b"c2ma" | b"c2um"))
+            .collect()
+    }
+
+    /// The `claim_generator` recorded in the claim of the active manifest.
+    pub fn claim_generator(&self) -> Option<String> {
+        let claim: &JumbfSuperBox = self.manifests()
+            .last()?
+            .superboxes()
+            .find(|b| &b.content_type[..4] == b"c2cl")?;
+        let value: CborValue = cbor::decode(claim.content()?.1).ok()?;
+
+        value.get("claim_generator")?.as_text().map(String::from)
+    }
+
+    /// Recomputes the `c2pa.hash.data` hard binding of the active manifest
+    /// over the current bytes of `png`. Returns `None` if the manifest has
+    /// no data hash assertion.
+    pub fn hard_binding(&self, png: &Png) -> Option<Result<HardBinding, String>> {
+        let assertion: &JumbfSuperBox = self.manifests()
+            .last()?
+            .superboxes()
+            .filter(|b| &b.content_type[..4] == b"c2as")
+            .flat_map(|store| store.superboxes())
+            .find(|a| a.label().is_some_and(|l| l.starts_with("c2pa.hash.data")))?;
+
+        Some(assertion
+            .content()
+            .ok_or_else(|| "c2pa.hash.data assertion is empty.".to_string())
+            .and_then(|(_, data)| cbor::decode(data))
+            .and_then(|value| recompute_hash(&value, &png.as_bytes())))
+    }
+
+    /// Serializes this into a `caBX` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("caBX").unwrap(), write_superbox(&self.store))
+    }
+}
+
+/// Recomputes a data hash assertion over `bytes`, skipping its exclusions.
+fn recompute_hash(assertion: &CborValue, bytes: &[u8]) -> Result<HardBinding, String> {
+    let algorithm: String = assertion.get("alg")
+        .and_then(|a| a.as_text())
+        .unwrap_or("sha256")
+        .to_string();
+    let expected: Vec<u8> = assertion.get("hash")
+        .and_then(|h| h.as_bytes())
+        .ok_or_else(|| "c2pa.hash.data assertion is missing its hash.".to_string())?
+        .to_vec();
+
+    let mut exclusions: Vec<HashExclusion> = Vec::new();
+    for exclusion in assertion.get("exclusions").and_then(|e| e.as_array()).unwrap_or_default() {
+        match (exclusion.get("start").and_then(|s| s.as_u64()), exclusion.get("length").and_then(|l| l.as_u64())) {
+            (Some(start), Some(length)) => exclusions.push(HashExclusion { start, length }),
+            _ => return Err("c2pa.hash.data exclusion is missing its start or length.".into())
+        }
+    }
+    exclusions.sort_by_key(|e| e.start);
+
+    // hash the bytes between the excluded ranges
+    let mut included: Vec<&[u8]> = Vec::new();
+    let mut position: u64 = 0;
+    for exclusion in &exclusions {
+        let end: u64 = exclusion.start.saturating_add(exclusion.length);
+        if exclusion.start < position || end > bytes.len() as u64 {
+            return Err("c2pa.hash.data exclusions overlap or extend past the end of the file.".into());
+        }
+
+        included.push(&bytes[position as usize..exclusion.start as usize]);
+        position = end;
+    }
+    included.push(&bytes[position as usize..]);
+
+    let actual: Vec<u8> = match algorithm.as_str() {
+        "sha256" => hash_parts::<Sha256>(&included),
+        "sha384" => hash_parts::<Sha384>(&included),
+        "sha512" => hash_parts::<Sha512>(&included),
+        _ => return Err(format!("Unsupported hard-binding hash algorithm '{}'.", algorithm))
+    };
+
+    Ok(HardBinding { algorithm, exclusions, expected, actual })
+}
+
+fn hash_parts<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+
+    hasher.finalize().to_vec()
+}
+
+/// Serializes a superbox back into a `jumb` box.
+fn write_superbox(superbox: &JumbfSuperBox) -> Vec<u8> {
+    let mut content: Vec<u8> = superbox.description.clone();
+    for b in &superbox.boxes {
+        match b {
+            JumbfBox::SuperBox(nested) => content.extend(write_superbox(nested)),
+            JumbfBox::Content { box_type, data, size } => content.extend(write_sized_box(box_type, data, *size))
+        }
+    }
+
+    write_sized_box(b"jumb", &content, superbox.size)
+}
+
+fn write_box(box_type: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    write_sized_box(box_type, content, BoxSize::Compact)
+}
+
+fn write_sized_box(box_type: &[u8; 4], content: &[u8], size: BoxSize) -> Vec<u8> {
+    let mut bytes: Vec<u8> = match size {
+        BoxSize::Compact => ((content.len() + 8) as u32).to_be_bytes().to_vec(),
+        BoxSize::Extended => 1u32.to_be_bytes().to_vec(),
+        BoxSize::ToEnd => 0u32.to_be_bytes().to_vec()
+    };
+    bytes.extend_from_slice(box_type);
+    if size == BoxSize::Extended {
+        bytes.extend_from_slice(&((content.len() + 16) as u64).to_be_bytes());
+    }
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+impl TryFrom<Chunk> for CABX {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("caBX")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'caBX'".into());
+        }
+
+        CABX::new(JumbfSuperBox::parse(value.data())?)
+    }
+}
+
+chunk_spec!(CABX, "caBX", fn validate_context(&self, png: &Png) {
+    match self.hard_binding(png) {
+        Some(Ok(binding)) if !binding.is_valid() =>
+            Err("C2PA hard binding does not match the file; it was modified after signing.".into()),
+        Some(Err(e)) => Err(e),
+        _ => Ok(())
+    }
+});
+
+impl fmt::Display for CABX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "caBX Content Credentials (C2PA) {{",)?;
+        if let Some(generator) = self.claim_generator() {
+            writeln!(f, "  Claim Generator: {}", generator)?;
+        }
+        self.store.write_tree(f, 2)?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::chunk_specs::{ChunkSpec, IHDR};
+
+    fn superbox(role: &[u8; 4], label: &str, boxes: Vec<JumbfBox>) -> JumbfBox {
+        let mut content_type: [u8; 16] = [0; 16];
+        content_type[..4].copy_from_slice(role);
+
+        JumbfBox::SuperBox(JumbfSuperBox::new(content_type, Some(label), boxes))
+    }
+
+    fn cbor(data: Vec<u8>) -> Vec<JumbfBox> {
+        vec![JumbfBox::Content { box_type: *b"cbor", data, size: BoxSize::Compact }]
+    }
+
+    /// Builds a manifest store whose data hash excludes `exclusion` and
+    /// records `hash`. Integers are always encoded in 4 bytes so that the
+    /// size of the store does not depend on them.
+    fn testing_store(exclusion: (u32, u32), hash: [u8; 32]) -> JumbfSuperBox {
+        let mut hash_data: Vec<u8> = vec![0xa3];
+        hash_data.extend_from_slice(b"\x63alg\x66sha256");
+        hash_data.extend_from_slice(b"\x64hash\x58\x20");
+        hash_data.extend_from_slice(&hash);
+        hash_data.extend_from_slice(b"\x6aexclusions\x81\xa2\x65start\x1a");
+        hash_data.extend_from_slice(&exclusion.0.to_be_bytes());
+        hash_data.extend_from_slice(b"\x66length\x1a");
+        hash_data.extend_from_slice(&exclusion.1.to_be_bytes());
+
+        let claim: Vec<u8> = b"\xa1\x6fclaim_generator\x64test".to_vec();
+        let manifest = superbox(b"c2ma", "urn:uuid:0001", vec![
+            superbox(b"c2as", "c2pa.assertions", vec![
+                superbox(b"cbor", "c2pa.actions", cbor(vec![0xa0])),
+                superbox(b"cbor", "c2pa.hash.data", cbor(hash_data))
+            ]),
+            superbox(b"c2cl", "c2pa.claim", cbor(claim)),
+            superbox(b"c2cs", "c2pa.signature", cbor(vec![0xf6]))
+        ]);
+
+        match superbox(b"c2pa", "c2pa", vec![manifest]) {
+            JumbfBox::SuperBox(store) => store,
+            _ => unreachable!()
+        }
+    }
+
+    /// Builds a PNG holding a signed manifest store after its IHDR chunk.
+    fn testing_png() -> Png {
+        let ihdr: Chunk = IHDR::new(1, 1, 8, 0, 0).unwrap().to_chunk();
+        let idat: Chunk = Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]);
+        let iend: Chunk = Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]);
+
+        let start: u32 = 8 + ihdr.as_bytes().len() as u32;
+        let length: u32 = CABX::new(testing_store((0, 0), [0; 32])).unwrap().to_chunk().as_bytes().len() as u32;
+
+        let mut unsigned: Vec<u8> = Png::from_chunks(vec![ihdr.clone(), idat.clone(), iend.clone()]).as_bytes();
+        unsigned.splice(start as usize..start as usize, std::iter::repeat_n(0, length as usize));
+        let mut hash: [u8; 32] = [0; 32];
+        hash.copy_from_slice(&hash_parts::<Sha256>(&[&unsigned[..start as usize], &unsigned[(start + length) as usize..]]));
+
+        let cabx: CABX = CABX::new(testing_store((start, length), hash)).unwrap();
+        Png::from_chunks(vec![ihdr, cabx.to_chunk(), idat, iend])
+    }
+
+    #[test]
+    fn test_cabx_round_trip() {
+        let png = testing_png();
+        let chunk = png.chunk_by_type("caBX").unwrap().clone();
+        let cabx = CABX::try_from(chunk.clone()).unwrap();
+
+        assert_eq!(cabx.to_chunk().data(), chunk.data());
+        assert_eq!(cabx.manifests().len(), 1);
+        assert_eq!(cabx.claim_generator().as_deref(), Some("test"));
+
+        let description = cabx.to_string();
+        assert!(description.contains("  Manifest Store: c2pa\n"));
+        assert!(description.contains("      Assertion: c2pa.hash.data (cbor, "));
+        assert!(description.contains("    Signature: c2pa.signature (cbor, 1 bytes)"));
+    }
+
+    #[test]
+    fn test_jumbf_keeps_description() {
+        // requestable, labeled, with an ID, a hash, and a private box
+        let mut description: Vec<u8> = b"c2pa\0\x11\0\x10\x80\0\0\xaa\0\x38\x9b\x71".to_vec();
+        description.push(0x1f);
+        description.extend_from_slice(b"c2pa\0");
+        description.extend_from_slice(&7u32.to_be_bytes());
+        description.extend_from_slice(&[0x42; 32]);
+        description.extend_from_slice(&write_box(b"free", b"private"));
+
+        let content: Vec<u8> = [
+            write_box(b"jumd", &description),
+            write_sized_box(b"json", b"{}", BoxSize::Extended),
+            write_sized_box(b"cbor", &[0xa0], BoxSize::ToEnd)
+        ].concat();
+        let data: Vec<u8> = write_sized_box(b"jumb", &content, BoxSize::Extended);
+
+        let store = JumbfSuperBox::parse(&data).unwrap();
+        assert_eq!(store.label(), Some("c2pa"));
+        assert_eq!(store.content(), Some((b"json", &b"{}"[..])));
+        assert_eq!(CABX::new(store).unwrap().to_chunk().data(), data.as_slice());
+    }
+
+    #[test]
+    fn test_cabx_hard_binding() {
+        let mut png = testing_png();
+        let cabx = png.typed_chunk::<CABX>().unwrap().unwrap();
+
+        let binding = cabx.hard_binding(&png).unwrap().unwrap();
+        assert_eq!(binding.algorithm(), "sha256");
+        assert!(binding.is_valid());
+        assert!(cabx.validate_context(&png).is_ok());
+
+        png.append_chunk(Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()));
+        assert!(!cabx.hard_binding(&png).unwrap().unwrap().is_valid());
+        assert!(cabx.validate_context(&png).is_err());
+    }
+
+    #[test]
+    fn test_jumbf_invalid() {
+        assert!(JumbfSuperBox::parse(&[0, 0, 0, 9, b'j', b'u', b'm', b'b']).is_err());
+        assert!(JumbfSuperBox::parse(&write_box(b"jumb", &write_box(b"free", &[]))).is_err());
+        assert!(JumbfSuperBox::parse(&write_box(b"jumb", &write_box(b"jumd", &[0; 4]))).is_err());
+
+        let mut nested: Vec<u8> = write_box(b"jumb", &write_box(b"jumd", &[0; 17]));
+        for _ in 0..40 {
+            nested = write_box(b"jumb", &[write_box(b"jumd", &[0; 17]), nested].concat());
+        }
+        assert!(JumbfSuperBox::parse(&nested).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The CIE 1931 chromaticities of the white point and the red, green, and
+/// blue primaries, each stored as x and y times 100000. See the PNG spec for
+/// more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.cHRM
+#[derive(Debug, PartialEq, Eq)]
+pub struct CHRM {
+    white_point: (u32, u32),
+    red: (u32, u32),
+    green: (u32, u32),
+    blue: (u32, u32)
+}
+
+impl CHRM {
+    /// Creates a new `cHRM` from chromaticities times 100000.
+    pub fn new(white_point: (u32, u32), red: (u32, u32), green: (u32, u32), blue: (u32, u32)) -> CHRM {
+        CHRM { white_point, red, green, blue }
+    }
+
+    /// The white point chromaticity times 100000.
+    pub fn white_point(&self) -> (u32, u32) {
+        self.white_point
+    }
+
+    /// The red primary chromaticity times 100000.
+    pub fn red(&self) -> (u32, u32) {
+        self.red
+    }
+
+    /// The green primary chromaticity times 100000.
+    pub fn green(&self) -> (u32, u32) {
+        self.green
+    }
+
+    /// The blue primary chromaticity times 100000.
+    pub fn blue(&self) -> (u32, u32) {
+        self.blue
+    }
+
+    /// Serializes this into a `cHRM` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = [self.white_point, self.red, self.green, self.blue]
+            .iter()
+            .flat_map(|&(x, y)| [x, y])
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+
+        Chunk::new(ChunkType::from_str("cHRM").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for CHRM {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("cHRM")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'cHRM'".into());
+        }
+
+        if value.length() != 32 {
+            return Err(format!("Chunk length {} does not match expected length 32.", value.length()));
+        }
+
+        let values: Vec<u32> = value.data()
+            .chunks_exact(4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+            .collect();
+
+        Ok(CHRM::new(
+            (values[0], values[1]),
+            (values[2], values[3]),
+            (values[4], values[5]),
+            (values[6], values[7])
+        ))
+    }
+}
+
+chunk_spec!(CHRM, "cHRM");
+
+impl fmt::Display for CHRM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |(x, y): (u32, u32)| format!("({:.5}, {:.5})", x as f64 / 100_000.0, y as f64 / 100_000.0);
+
+        writeln!(f, "cHRM Primary chromaticities {{",)?;
+        writeln!(f, "  White Point: {}", format(self.white_point()))?;
+        writeln!(f, "  Red: {}", format(self.red()))?;
+        writeln!(f, "  Green: {}", format(self.green()))?;
+        writeln!(f, "  Blue: {}", format(self.blue()))?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrm_round_trip() {
+        // the sRGB chromaticities recommended by the PNG spec
+        let chrm = CHRM::new((31270, 32900), (64000, 33000), (30000, 60000), (15000, 6000));
+        let chunk = chrm.to_chunk();
+
+        assert_eq!(chunk.length(), 32);
+        assert_eq!(&chunk.data()[0..4], &31270u32.to_be_bytes());
+        assert_eq!(CHRM::try_from(chunk).unwrap(), chrm);
+    }
+}
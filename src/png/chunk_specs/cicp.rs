@@ -0,0 +1,190 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// Coding-independent code points, which identify the color space of the
+/// image using the code points of ITU-T H.273. This is the only way to tag
+/// HDR content such as BT.2100 PQ or HLG. See the PNG Third Edition spec
+/// https://www.w3.org/TR/png-3/#cICP-chunk
+#[derive(Debug, PartialEq, Eq)]
+pub struct CICP {
+    colour_primaries: u8,
+    transfer_function: u8,
+    matrix_coefficients: u8,
+    video_full_range: bool
+}
+
+impl CICP {
+    /// The BT.709 colour primaries, shared by sRGB.
+    pub const PRIMARIES_BT709: u8 = 1;
+
+    /// The BT.2020 and BT.2100 colour primaries.
+    pub const PRIMARIES_BT2020: u8 = 9;
+
+    /// The Display P3 colour primaries.
+    pub const PRIMARIES_DISPLAY_P3: u8 = 12;
+
+    /// The sRGB transfer function.
+    pub const TRANSFER_SRGB: u8 = 13;
+
+    /// The BT.2100 perceptual quantization (PQ) transfer function.
+    pub const TRANSFER_PQ: u8 = 16;
+
+    /// The BT.2100 hybrid log-gamma (HLG) transfer function.
+    pub const TRANSFER_HLG: u8 = 18;
+
+    /// Creates a new `cICP`. PNG only stores RGB samples, so the matrix
+    /// coefficients must be 0 (identity).
+    pub fn new(colour_primaries: u8, transfer_function: u8, matrix_coefficients: u8,
+            video_full_range: bool) -> Result<CICP, String> {
+        if matrix_coefficients != 0 {
+            return Err(format!("cICP matrix coefficients {} must be 0, as PNG only supports RGB.",
+                matrix_coefficients));
+        }
+
+        Ok(CICP { colour_primaries, transfer_function, matrix_coefficients, video_full_range })
+    }
+
+    /// The colour primaries code point from ITU-T H.273 Table 2.
+    pub fn colour_primaries(&self) -> u8 {
+        self.colour_primaries
+    }
+
+    /// The transfer characteristics code point from ITU-T H.273 Table 3.
+    pub fn transfer_function(&self) -> u8 {
+        self.transfer_function
+    }
+
+    /// The matrix coefficients code point from ITU-T H.273 Table 4.
+    pub fn matrix_coefficients(&self) -> u8 {
+        self.matrix_coefficients
+    }
+
+    /// Whether samples use the full range rather than the narrow range.
+    pub fn is_video_full_range(&self) -> bool {
+        self.video_full_range
+    }
+
+    /// Whether the image uses the BT.2100 PQ transfer function.
+    pub fn is_pq(&self) -> bool {
+        self.transfer_function == CICP::TRANSFER_PQ
+    }
+
+    /// Whether the image uses the BT.2100 HLG transfer function.
+    pub fn is_hlg(&self) -> bool {
+        self.transfer_function == CICP::TRANSFER_HLG
+    }
+
+    /// Serializes this into a `cICP` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = vec![
+            self.colour_primaries,
+            self.transfer_function,
+            self.matrix_coefficients,
+            self.video_full_range as u8
+        ];
+
+        Chunk::new(ChunkType::from_str("cICP").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for CICP {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("cICP")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'cICP'".into());
+        }
+
+        if value.length() != 4 {
+            return Err(format!("Chunk length {} does not match expected length 4.", value.length()));
+        }
+
+        let video_full_range: bool = match value.data()[3] {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("Invalid cICP video full range flag {}.", flag))
+        };
+
+        CICP::new(value.data()[0], value.data()[1], value.data()[2], video_full_range)
+    }
+}
+
+chunk_spec!(CICP, "cICP");
+
+impl fmt::Display for CICP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cICP Coding-independent code points {{",)?;
+        writeln!(f, "  Colour Primaries: {} ({})", self.colour_primaries(),
+            primaries_name(self.colour_primaries()))?;
+        writeln!(f, "  Transfer Function: {} ({})", self.transfer_function(),
+            transfer_name(self.transfer_function()))?;
+        writeln!(f, "  Matrix Coefficients: {}", self.matrix_coefficients())?;
+        writeln!(f, "  Video Full Range: {}", self.is_video_full_range())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+fn primaries_name(code_point: u8) -> &'static str {
+    match code_point {
+        1 => "BT.709",
+        4 => "BT.470 M",
+        5 => "BT.601 625",
+        6 | 7 => "BT.601 525",
+        8 => "Generic film",
+        9 => "BT.2020",
+        10 => "CIE 1931 XYZ",
+        11 => "SMPTE RP 431-2",
+        12 => "Display P3",
+        22 => "EBU Tech 3213-E",
+        _ => "Unknown"
+    }
+}
+
+fn transfer_name(code_point: u8) -> &'static str {
+    match code_point {
+        1 | 6 | 14 | 15 => "BT.709",
+        4 => "Gamma 2.2",
+        5 => "Gamma 2.8",
+        8 => "Linear",
+        13 => "sRGB",
+        16 => "PQ",
+        17 => "SMPTE ST 428-1",
+        18 => "HLG",
+        _ => "Unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cicp_round_trip() {
+        let cicp = CICP::new(CICP::PRIMARIES_BT2020, CICP::TRANSFER_PQ, 0, true).unwrap();
+        let chunk = cicp.to_chunk();
+
+        assert_eq!(chunk.data(), &[9, 16, 0, 1]);
+
+        let parsed = CICP::try_from(chunk).unwrap();
+        assert!(parsed.is_pq());
+        assert!(!parsed.is_hlg());
+        assert_eq!(parsed, cicp);
+    }
+
+    #[test]
+    fn test_cicp_invalid() {
+        assert!(CICP::new(1, 13, 1, true).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("cICP").unwrap(), vec![9, 18, 0, 2]);
+        assert!(CICP::try_from(chunk).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("cICP").unwrap(), vec![9, 18, 0]);
+        assert!(CICP::try_from(chunk).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The scale of luminance values, stored in units of 0.0001 cd/m².
+const LUMINANCE_SCALE: f64 = 10000.0;
+
+/// The content light level of the image, as described by CTA-861.3. See the
+/// PNG Third Edition spec for more details.
+/// https://www.w3.org/TR/png-3/#cLLI-chunk
+#[derive(Debug, PartialEq, Eq)]
+pub struct CLLI {
+    max_content_light_level: u32,
+    max_frame_average_light_level: u32
+}
+
+impl CLLI {
+    /// Creates a new `cLLI` from raw light levels in units of 0.0001 cd/m².
+    pub fn new(max_content_light_level: u32, max_frame_average_light_level: u32)
+            -> Result<CLLI, String> {
+        if max_frame_average_light_level > max_content_light_level {
+            return Err("cLLI MaxFALL may not exceed MaxCLL.".into());
+        }
+
+        Ok(CLLI { max_content_light_level, max_frame_average_light_level })
+    }
+
+    /// The raw maximum content light level (MaxCLL), in units of 0.0001 cd/m².
+    pub fn max_content_light_level(&self) -> u32 {
+        self.max_content_light_level
+    }
+
+    /// The raw maximum frame-average light level (MaxFALL), in units of
+    /// 0.0001 cd/m².
+    pub fn max_frame_average_light_level(&self) -> u32 {
+        self.max_frame_average_light_level
+    }
+
+    /// The maximum content light level (MaxCLL) in cd/m².
+    pub fn max_content_light_level_nits(&self) -> f64 {
+        self.max_content_light_level as f64 / LUMINANCE_SCALE
+    }
+
+    /// The maximum frame-average light level (MaxFALL) in cd/m².
+    pub fn max_frame_average_light_level_nits(&self) -> f64 {
+        self.max_frame_average_light_level as f64 / LUMINANCE_SCALE
+    }
+
+    /// Serializes this into a `cLLI` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.max_content_light_level
+            .to_be_bytes()
+            .iter()
+            .chain(self.max_frame_average_light_level.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("cLLI").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for CLLI {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("cLLI")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'cLLI'".into());
+        }
+
+        if value.length() != 8 {
+            return Err(format!("Chunk length {} does not match expected length 8.", value.length()));
+        }
+
+        CLLI::new(
+            u32::from_be_bytes(value.data()[0..4].try_into().unwrap()),
+            u32::from_be_bytes(value.data()[4..8].try_into().unwrap())
+        )
+    }
+}
+
+chunk_spec!(CLLI, "cLLI");
+
+impl fmt::Display for CLLI {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cLLI Content light level information {{",)?;
+        writeln!(f, "  MaxCLL: {} cd/m2", self.max_content_light_level_nits())?;
+        writeln!(f, "  MaxFALL: {} cd/m2", self.max_frame_average_light_level_nits())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clli_round_trip() {
+        let clli = CLLI::new(10_000_000, 4_000_000).unwrap();
+        let chunk = clli.to_chunk();
+
+        assert_eq!(chunk.data(), &[0, 152, 150, 128, 0, 61, 9, 0]);
+        assert_eq!(CLLI::try_from(chunk).unwrap(), clli);
+        assert_eq!(clli.max_content_light_level_nits(), 1000.0);
+        assert_eq!(clli.max_frame_average_light_level_nits(), 400.0);
+    }
+
+    #[test]
+    fn test_clli_invalid() {
+        assert!(CLLI::new(100, 200).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("cLLI").unwrap(), vec![0; 4]);
+        assert!(CLLI::try_from(chunk).is_err());
+    }
+}
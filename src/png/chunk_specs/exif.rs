@@ -0,0 +1,487 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The tag of the IFD0 entry pointing to the EXIF sub-IFD.
+const EXIF_IFD_POINTER: u16 = 0x8769;
+
+/// The tag of the IFD0 entry pointing to the GPS IFD.
+const GPS_IFD_POINTER: u16 = 0x8825;
+
+/// The maximum number of values shown for a single tag when displayed.
+const MAX_DISPLAYED_VALUES: usize = 16;
+
+/// Exchangeable image file (EXIF) profile data. An `eXIf` chunk holds a
+/// TIFF-structured payload starting with a byte-order mark. The tags of
+/// IFD0, the EXIF sub-IFD, and the GPS IFD are parsed when the chunk is
+/// parsed. See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.eXIf
+pub struct EXIF {
+    big_endian: bool,
+    tags: Vec<ExifTag>,
+    data: Vec<u8>
+}
+
+/// The image file directory (IFD) an EXIF tag was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExifDirectory {
+    /// The primary image directory (IFD0).
+    Primary,
+    /// The EXIF sub-IFD, which holds camera and capture settings.
+    Exif,
+    /// The GPS IFD, which holds location information.
+    Gps
+}
+
+/// A typed EXIF tag value, as described by the TIFF 6.0 field types.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExifValue {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>)
+}
+
+/// A single tag read from an EXIF image file directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifTag {
+    directory: ExifDirectory,
+    tag: u16,
+    value: ExifValue
+}
+
+impl EXIF {
+    /// Creates a new `eXIf` by parsing a TIFF-structured EXIF payload.
+    pub fn new(data: Vec<u8>) -> Result<EXIF, String> {
+        let big_endian: bool = match data.get(0..4) {
+            Some(b"MM\0*") => true,
+            Some(b"II*\0") => false,
+            _ => return Err("eXIf data does not begin with a valid TIFF byte-order mark.".into())
+        };
+
+        let reader = TiffReader { data: &data, big_endian };
+        let mut tags: Vec<ExifTag> = Vec::new();
+        let mut visited: Vec<usize> = Vec::new();
+
+        let ifd0: usize = reader.u32_at(4)? as usize;
+        reader.read_ifd(ifd0, ExifDirectory::Primary, &mut tags, &mut visited)?;
+
+        let pointers: Vec<(ExifDirectory, usize)> = tags
+            .iter()
+            .filter_map(|t| match (t.tag, &t.value) {
+                (EXIF_IFD_POINTER, ExifValue::Long(v)) => v.first().map(|&o| (ExifDirectory::Exif, o as usize)),
+                (GPS_IFD_POINTER, ExifValue::Long(v)) => v.first().map(|&o| (ExifDirectory::Gps, o as usize)),
+                _ => None
+            })
+            .collect();
+
+        for (directory, offset) in pointers {
+            reader.read_ifd(offset, directory, &mut tags, &mut visited)?;
+        }
+
+        Ok(EXIF { big_endian, tags, data })
+    }
+
+    /// Whether the payload is stored big-endian (`MM`) rather than
+    /// little-endian (`II`).
+    pub fn is_big_endian(&self) -> bool {
+        self.big_endian
+    }
+
+    /// Every tag found in IFD0, the EXIF sub-IFD, and the GPS IFD.
+    pub fn tags(&self) -> &[ExifTag] {
+        &self.tags
+    }
+
+    /// Searches for the first tag with the given tag number in a directory.
+    pub fn tag(&self, directory: ExifDirectory, tag: u16) -> Option<&ExifTag> {
+        self.tags.iter().find(|t| t.directory == directory && t.tag == tag)
+    }
+
+    /// The raw TIFF-structured payload.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Serializes this profile into an `eXIf` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("eXIf").unwrap(), self.data.clone())
+    }
+}
+
+impl ExifTag {
+    /// The directory this tag was found in.
+    pub fn directory(&self) -> ExifDirectory {
+        self.directory
+    }
+
+    /// The numeric tag identifier.
+    pub fn tag(&self) -> u16 {
+        self.tag
+    }
+
+    /// The typed value of this tag.
+    pub fn value(&self) -> &ExifValue {
+        &self.value
+    }
+
+    /// The name of this tag as given by the EXIF spec, if it is known.
+    pub fn name(&self) -> Option<&'static str> {
+        tag_name(self.directory, self.tag)
+    }
+}
+
+impl TryFrom<Chunk> for EXIF {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("eXIf")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'eXIf'".into());
+        }
+
+        EXIF::new(value.data().to_vec())
+    }
+}
+
+chunk_spec!(EXIF, "eXIf");
+
+impl fmt::Display for EXIF {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "eXIf Exchangeable image file profile {{",)?;
+        writeln!(f, "  Byte Order: {}", if self.is_big_endian() { "big-endian" } else { "little-endian" })?;
+        for tag in self.tags() {
+            writeln!(f, "  {}", tag)?;
+        }
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExifDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExifDirectory::Primary => write!(f, "IFD0"),
+            ExifDirectory::Exif => write!(f, "EXIF"),
+            ExifDirectory::Gps => write!(f, "GPS")
+        }
+    }
+}
+
+impl fmt::Display for ExifTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} (0x{:04x}): {}",
+            self.directory(), self.name().unwrap_or("Unknown"), self.tag(), self.value())
+    }
+}
+
+impl fmt::Display for ExifValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExifValue::Ascii(s) => write!(f, "{:?}", s),
+            ExifValue::Byte(v) | ExifValue::Undefined(v) if v.len() > MAX_DISPLAYED_VALUES => {
+                write!(f, "{} bytes", v.len())
+            },
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => write_list(f, v),
+            ExifValue::Short(v) => write_list(f, v),
+            ExifValue::Long(v) => write_list(f, v),
+            ExifValue::SByte(v) => write_list(f, v),
+            ExifValue::SShort(v) => write_list(f, v),
+            ExifValue::SLong(v) => write_list(f, v),
+            ExifValue::Float(v) => write_list(f, v),
+            ExifValue::Double(v) => write_list(f, v),
+            ExifValue::Rational(v) => {
+                let v: Vec<String> = v.iter().map(|(n, d)| format!("{}/{}", n, d)).collect();
+                write_list(f, &v)
+            },
+            ExifValue::SRational(v) => {
+                let v: Vec<String> = v.iter().map(|(n, d)| format!("{}/{}", n, d)).collect();
+                write_list(f, &v)
+            }
+        }
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, values: &[T]) -> fmt::Result {
+    let shown: Vec<String> = values
+        .iter()
+        .take(MAX_DISPLAYED_VALUES)
+        .map(|v| v.to_string())
+        .collect();
+
+    write!(f, "{}", shown.join(", "))?;
+    if values.len() > MAX_DISPLAYED_VALUES {
+        write!(f, ", ... ({} values)", values.len())?;
+    }
+
+    Ok(())
+}
+
+/// Reads values from a TIFF-structured payload in its declared byte order.
+struct TiffReader<'a> {
+    data: &'a [u8],
+    big_endian: bool
+}
+
+impl<'a> TiffReader<'a> {
+    fn bytes_at(&self, offset: usize, length: usize) -> Result<&'a [u8], String> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or_else(|| format!("eXIf data is truncated reading {} bytes at offset {}.", length, offset))
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, String> {
+        let bytes: [u8; 2] = self.bytes_at(offset, 2)?.try_into().unwrap();
+        Ok(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, String> {
+        let bytes: [u8; 4] = self.bytes_at(offset, 4)?.try_into().unwrap();
+        Ok(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    fn u64_at(&self, offset: usize) -> Result<u64, String> {
+        let bytes: [u8; 8] = self.bytes_at(offset, 8)?.try_into().unwrap();
+        Ok(if self.big_endian { u64::from_be_bytes(bytes) } else { u64::from_le_bytes(bytes) })
+    }
+
+    /// Reads every entry of the IFD at `offset` into `tags`. Offsets that were
+    /// already visited are skipped so that cyclic directories terminate.
+    fn read_ifd(&self, offset: usize, directory: ExifDirectory, tags: &mut Vec<ExifTag>,
+            visited: &mut Vec<usize>) -> Result<(), String> {
+        if visited.contains(&offset) {
+            return Ok(());
+        }
+        visited.push(offset);
+
+        let count: usize = self.u16_at(offset)? as usize;
+        for idx in 0..count {
+            let entry: usize = offset + 2 + idx * 12;
+            let tag: u16 = self.u16_at(entry)?;
+            let field_type: u16 = self.u16_at(entry + 2)?;
+            let value_count: usize = self.u32_at(entry + 4)? as usize;
+
+            // unknown field types must be skipped as described by TIFF 6.0
+            let size: usize = match field_type_size(field_type) {
+                Some(size) => size,
+                None => continue
+            };
+
+            let length: usize = match size.checked_mul(value_count) {
+                Some(length) => length,
+                None => return Err(format!("eXIf tag 0x{:04x} has an invalid count {}.", tag, value_count))
+            };
+
+            // values of 4 bytes or less are stored inline in the entry
+            let value_offset: usize = match length <= 4 {
+                true => entry + 8,
+                false => self.u32_at(entry + 8)? as usize
+            };
+
+            let value: ExifValue = self.read_value(field_type, value_offset, value_count)?;
+            tags.push(ExifTag { directory, tag, value });
+        }
+
+        Ok(())
+    }
+
+    fn read_value(&self, field_type: u16, offset: usize, count: usize) -> Result<ExifValue, String> {
+        let size: usize = field_type_size(field_type).unwrap_or(1);
+        let raw: &[u8] = self.bytes_at(offset, size * count)?;
+        let at = |idx: usize| offset + idx * size;
+
+        let value: ExifValue = match field_type {
+            1 => ExifValue::Byte(raw.to_vec()),
+            2 => {
+                let end: usize = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                ExifValue::Ascii(String::from_utf8_lossy(&raw[..end]).into_owned())
+            },
+            3 => ExifValue::Short((0..count).map(|i| self.u16_at(at(i))).collect::<Result<_, _>>()?),
+            4 => ExifValue::Long((0..count).map(|i| self.u32_at(at(i))).collect::<Result<_, _>>()?),
+            5 => ExifValue::Rational((0..count)
+                .map(|i| Ok((self.u32_at(at(i))?, self.u32_at(at(i) + 4)?)))
+                .collect::<Result<_, String>>()?),
+            6 => ExifValue::SByte(raw.iter().map(|&b| b as i8).collect()),
+            8 => ExifValue::SShort((0..count).map(|i| self.u16_at(at(i)).map(|v| v as i16)).collect::<Result<_, _>>()?),
+            9 => ExifValue::SLong((0..count).map(|i| self.u32_at(at(i)).map(|v| v as i32)).collect::<Result<_, _>>()?),
+            10 => ExifValue::SRational((0..count)
+                .map(|i| Ok((self.u32_at(at(i))? as i32, self.u32_at(at(i) + 4)? as i32)))
+                .collect::<Result<_, String>>()?),
+            11 => ExifValue::Float((0..count).map(|i| self.u32_at(at(i)).map(f32::from_bits)).collect::<Result<_, _>>()?),
+            12 => ExifValue::Double((0..count).map(|i| self.u64_at(at(i)).map(f64::from_bits)).collect::<Result<_, _>>()?),
+            _ => ExifValue::Undefined(raw.to_vec())
+        };
+
+        Ok(value)
+    }
+}
+
+/// The size in bytes of a single value of a TIFF field type.
+fn field_type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None
+    }
+}
+
+/// The names of commonly used tags as given by the EXIF 2.32 spec.
+fn tag_name(directory: ExifDirectory, tag: u16) -> Option<&'static str> {
+    let name: &'static str = match (directory, tag) {
+        (ExifDirectory::Gps, 0x0000) => "GPSVersionID",
+        (ExifDirectory::Gps, 0x0001) => "GPSLatitudeRef",
+        (ExifDirectory::Gps, 0x0002) => "GPSLatitude",
+        (ExifDirectory::Gps, 0x0003) => "GPSLongitudeRef",
+        (ExifDirectory::Gps, 0x0004) => "GPSLongitude",
+        (ExifDirectory::Gps, 0x0005) => "GPSAltitudeRef",
+        (ExifDirectory::Gps, 0x0006) => "GPSAltitude",
+        (ExifDirectory::Gps, 0x0007) => "GPSTimeStamp",
+        (ExifDirectory::Gps, 0x0012) => "GPSMapDatum",
+        (ExifDirectory::Gps, 0x001d) => "GPSDateStamp",
+        (ExifDirectory::Gps, _) => return None,
+        (_, 0x010e) => "ImageDescription",
+        (_, 0x010f) => "Make",
+        (_, 0x0110) => "Model",
+        (_, 0x0112) => "Orientation",
+        (_, 0x011a) => "XResolution",
+        (_, 0x011b) => "YResolution",
+        (_, 0x0128) => "ResolutionUnit",
+        (_, 0x0131) => "Software",
+        (_, 0x0132) => "DateTime",
+        (_, 0x013b) => "Artist",
+        (_, 0x0213) => "YCbCrPositioning",
+        (_, 0x8298) => "Copyright",
+        (_, EXIF_IFD_POINTER) => "ExifIFDPointer",
+        (_, GPS_IFD_POINTER) => "GPSInfoIFDPointer",
+        (_, 0x829a) => "ExposureTime",
+        (_, 0x829d) => "FNumber",
+        (_, 0x8822) => "ExposureProgram",
+        (_, 0x8827) => "PhotographicSensitivity",
+        (_, 0x9000) => "ExifVersion",
+        (_, 0x9003) => "DateTimeOriginal",
+        (_, 0x9004) => "DateTimeDigitized",
+        (_, 0x9010) => "OffsetTime",
+        (_, 0x9011) => "OffsetTimeOriginal",
+        (_, 0x9101) => "ComponentsConfiguration",
+        (_, 0x9201) => "ShutterSpeedValue",
+        (_, 0x9202) => "ApertureValue",
+        (_, 0x9204) => "ExposureBiasValue",
+        (_, 0x9207) => "MeteringMode",
+        (_, 0x9209) => "Flash",
+        (_, 0x920a) => "FocalLength",
+        (_, 0x927c) => "MakerNote",
+        (_, 0x9286) => "UserComment",
+        (_, 0xa000) => "FlashpixVersion",
+        (_, 0xa001) => "ColorSpace",
+        (_, 0xa002) => "PixelXDimension",
+        (_, 0xa003) => "PixelYDimension",
+        (_, 0xa402) => "ExposureMode",
+        (_, 0xa403) => "WhiteBalance",
+        (_, 0xa405) => "FocalLengthIn35mmFilm",
+        (_, 0xa406) => "SceneCaptureType",
+        (_, 0xa420) => "ImageUniqueID",
+        (_, 0xa430) => "CameraOwnerName",
+        (_, 0xa431) => "BodySerialNumber",
+        (_, 0xa433) => "LensMake",
+        (_, 0xa434) => "LensModel",
+        _ => return None
+    };
+
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A little-endian payload with IFD0 (Make, Orientation, EXIF pointer,
+    /// GPS pointer), an EXIF sub-IFD (FNumber), and a GPS IFD (GPSLatitudeRef).
+    fn testing_payload() -> Vec<u8> {
+        let mut data: Vec<u8> = b"II*\0".to_vec();
+        data.extend_from_slice(&8u32.to_le_bytes());
+
+        // IFD0 at offset 8 with 4 entries, ending at 8 + 2 + 48 + 4 = 62
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&[0x0f, 0x01, 2, 0, 6, 0, 0, 0, 62, 0, 0, 0]);
+        data.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        data.extend_from_slice(&[0x69, 0x87, 4, 0, 1, 0, 0, 0, 68, 0, 0, 0]);
+        data.extend_from_slice(&[0x25, 0x88, 4, 0, 1, 0, 0, 0, 94, 0, 0, 0]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(b"Canon\0");
+
+        // EXIF IFD at offset 68 with 1 entry, followed by its rational value at 86
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&[0x9d, 0x82, 5, 0, 1, 0, 0, 0, 86, 0, 0, 0]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&28u32.to_le_bytes());
+        data.extend_from_slice(&10u32.to_le_bytes());
+
+        // GPS IFD at offset 94 with 1 entry
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&[0x01, 0x00, 2, 0, 2, 0, 0, 0, b'N', 0, 0, 0]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_exif_tags() {
+        let exif = EXIF::new(testing_payload()).unwrap();
+
+        assert!(!exif.is_big_endian());
+        assert_eq!(exif.tags().len(), 6);
+
+        let make = exif.tag(ExifDirectory::Primary, 0x010f).unwrap();
+        assert_eq!(make.name(), Some("Make"));
+        assert_eq!(make.value(), &ExifValue::Ascii("Canon".into()));
+
+        let orientation = exif.tag(ExifDirectory::Primary, 0x0112).unwrap();
+        assert_eq!(orientation.value(), &ExifValue::Short(vec![1]));
+
+        let f_number = exif.tag(ExifDirectory::Exif, 0x829d).unwrap();
+        assert_eq!(f_number.name(), Some("FNumber"));
+        assert_eq!(f_number.value(), &ExifValue::Rational(vec![(28, 10)]));
+
+        let latitude_ref = exif.tag(ExifDirectory::Gps, 0x0001).unwrap();
+        assert_eq!(latitude_ref.name(), Some("GPSLatitudeRef"));
+        assert_eq!(latitude_ref.value(), &ExifValue::Ascii("N".into()));
+    }
+
+    #[test]
+    fn test_exif_chunk_round_trip() {
+        let exif = EXIF::new(testing_payload()).unwrap();
+        let parsed = EXIF::try_from(exif.to_chunk()).unwrap();
+
+        assert_eq!(parsed.data(), &testing_payload()[..]);
+        assert_eq!(parsed.tags(), exif.tags());
+    }
+
+    #[test]
+    fn test_exif_invalid_payloads() {
+        assert!(EXIF::new(b"XX*\0\x08\0\0\0".to_vec()).is_err());
+        assert!(EXIF::new(b"MM\0*\0\0\0\x08\0\x05".to_vec()).is_err());
+        assert!(EXIF::new(b"II*\0\xff\0\0\0".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_exif_cyclic_sub_ifd() {
+        let mut data = testing_payload();
+        // point the EXIF sub-IFD back at IFD0
+        data[10 + 24 + 8] = 8;
+        assert!(EXIF::new(data).is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The gamma of the image, stored as the gamma times 100000. See the PNG spec
+/// for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.gAMA
+#[derive(Debug, PartialEq, Eq)]
+pub struct GAMA {
+    gamma: u32
+}
+
+impl GAMA {
+    /// Creates a new `gAMA` from the gamma times 100000, which must not be zero.
+    pub fn new(gamma: u32) -> Result<GAMA, String> {
+        if gamma == 0 {
+            return Err("gAMA gamma must not be zero.".into());
+        }
+
+        Ok(GAMA { gamma })
+    }
+
+    /// The stored gamma times 100000, such as 45455 for a gamma of 1/2.2.
+    pub fn gamma(&self) -> u32 {
+        self.gamma
+    }
+
+    /// The gamma as a floating-point value.
+    pub fn value(&self) -> f64 {
+        self.gamma as f64 / 100_000.0
+    }
+
+    /// Serializes this into a `gAMA` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("gAMA").unwrap(), self.gamma.to_be_bytes().to_vec())
+    }
+}
+
+impl TryFrom<Chunk> for GAMA {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("gAMA")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'gAMA'".into());
+        }
+
+        if value.length() != 4 {
+            return Err(format!("Chunk length {} does not match expected length 4.", value.length()));
+        }
+
+        GAMA::new(u32::from_be_bytes(value.data()[0..4].try_into().unwrap()))
+    }
+}
+
+chunk_spec!(GAMA, "gAMA");
+
+impl fmt::Display for GAMA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "gAMA Image gamma {{",)?;
+        writeln!(f, "  Gamma: {:.5}", self.value())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gama_round_trip() {
+        let gama = GAMA::new(45455).unwrap();
+        let chunk = gama.to_chunk();
+
+        assert_eq!(chunk.data(), &[0, 0, 177, 143]);
+        assert_eq!(GAMA::try_from(chunk).unwrap(), gama);
+        assert!(GAMA::new(0).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The GIF Graphic Control Extension of an image converted from GIF. See the
+/// PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.gIFg
+#[derive(Debug, PartialEq, Eq)]
+pub struct GIFG {
+    disposal_method: u8,
+    user_input: bool,
+    delay_time: u16
+}
+
+impl GIFG {
+    /// Creates a new `gIFg`. GIF defines disposal methods 0-7.
+    pub fn new(disposal_method: u8, user_input: bool, delay_time: u16) -> Result<GIFG, String> {
+        if disposal_method > 7 {
+            return Err(format!("Invalid gIFg disposal method {}.", disposal_method));
+        }
+
+        Ok(GIFG { disposal_method, user_input, delay_time })
+    }
+
+    /// The GIF disposal method, describing what happens after the image is shown.
+    pub fn disposal_method(&self) -> u8 {
+        self.disposal_method
+    }
+
+    /// Whether the viewer should wait for user input before continuing.
+    pub fn user_input(&self) -> bool {
+        self.user_input
+    }
+
+    /// The delay before continuing, in hundredths of a second.
+    pub fn delay_time(&self) -> u16 {
+        self.delay_time
+    }
+
+    /// Serializes this into a `gIFg` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = [self.disposal_method, self.user_input as u8]
+            .iter()
+            .chain(self.delay_time.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("gIFg").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for GIFG {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("gIFg")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'gIFg'".into());
+        }
+
+        if value.length() != 4 {
+            return Err(format!("Chunk length {} does not match expected length 4.", value.length()));
+        }
+
+        let user_input: bool = match value.data()[1] {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("Invalid gIFg user input flag {}.", flag))
+        };
+
+        GIFG::new(
+            value.data()[0],
+            user_input,
+            u16::from_be_bytes(value.data()[2..4].try_into().unwrap())
+        )
+    }
+}
+
+chunk_spec!(GIFG, "gIFg");
+
+impl fmt::Display for GIFG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "gIFg GIF Graphic Control Extension {{",)?;
+        writeln!(f, "  Disposal Method: {}", self.disposal_method())?;
+        writeln!(f, "  User Input: {}", self.user_input())?;
+        writeln!(f, "  Delay Time: {}/100 s", self.delay_time())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gifg_round_trip() {
+        let gifg = GIFG::new(2, true, 150).unwrap();
+        let chunk = gifg.to_chunk();
+
+        assert_eq!(chunk.data(), &[2, 1, 0, 150]);
+        assert_eq!(GIFG::try_from(chunk).unwrap(), gifg);
+    }
+
+    #[test]
+    fn test_gifg_invalid() {
+        assert!(GIFG::new(8, false, 0).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("gIFg").unwrap(), vec![0, 2, 0, 0]);
+        assert!(GIFG::try_from(chunk).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::png::chunk_specs::decode_latin1;
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// A GIF Application Extension of an image converted from GIF, such as the
+/// `NETSCAPE2.0` looping extension. See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.gIFx
+#[derive(Debug, PartialEq, Eq)]
+pub struct GIFX {
+    application_identifier: [u8; 8],
+    authentication_code: [u8; 3],
+    application_data: Vec<u8>
+}
+
+impl GIFX {
+    /// Creates a new `gIFx`.
+    pub fn new(application_identifier: [u8; 8], authentication_code: [u8; 3],
+            application_data: Vec<u8>) -> GIFX {
+        GIFX { application_identifier, authentication_code, application_data }
+    }
+
+    /// The 8-byte identifier of the application that wrote the extension.
+    pub fn application_identifier(&self) -> [u8; 8] {
+        self.application_identifier
+    }
+
+    /// The 3-byte code used to authenticate the application identifier.
+    pub fn authentication_code(&self) -> [u8; 3] {
+        self.authentication_code
+    }
+
+    /// The application-specific data.
+    pub fn application_data(&self) -> &[u8] {
+        &self.application_data
+    }
+
+    /// Serializes this into a `gIFx` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.application_identifier
+            .iter()
+            .chain(self.authentication_code.iter())
+            .chain(self.application_data.iter())
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("gIFx").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for GIFX {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("gIFx")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'gIFx'".into());
+        }
+
+        if value.length() < 11 {
+            return Err(format!("Chunk length {} is shorter than the minimum length 11.", value.length()));
+        }
+
+        let mut application_identifier: [u8; 8] = Default::default();
+        let mut authentication_code: [u8; 3] = Default::default();
+        application_identifier.copy_from_slice(&value.data()[0..8]);
+        authentication_code.copy_from_slice(&value.data()[8..11]);
+
+        Ok(GIFX::new(application_identifier, authentication_code, value.data()[11..].to_vec()))
+    }
+}
+
+chunk_spec!(GIFX, "gIFx");
+
+impl fmt::Display for GIFX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "gIFx GIF Application Extension {{",)?;
+        writeln!(f, "  Application Identifier: {}", decode_latin1(&self.application_identifier))?;
+        writeln!(f, "  Authentication Code: {}", decode_latin1(&self.authentication_code))?;
+        writeln!(f, "  Application Data: {} bytes", self.application_data().len())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gifx_round_trip() {
+        let gifx = GIFX::new(*b"NETSCAPE", *b"2.0", vec![1, 0, 0]);
+        let chunk = gifx.to_chunk();
+
+        assert_eq!(chunk.data(), b"NETSCAPE2.0\x01\x00\x00");
+        assert_eq!(GIFX::try_from(chunk).unwrap(), gifx);
+    }
+
+    #[test]
+    fn test_gifx_too_short() {
+        let chunk = Chunk::new(ChunkType::from_str("gIFx").unwrap(), b"NETSCAPE".to_vec());
+        assert!(GIFX::try_from(chunk).is_err());
+    }
+}
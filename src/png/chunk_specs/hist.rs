@@ -0,0 +1,126 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The approximate usage frequency of each color in the palette. A `hIST`
+/// chunk holds exactly one entry for each entry in the `PLTE` chunk. See the
+/// PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.hIST
+#[derive(Debug, PartialEq, Eq)]
+pub struct HIST {
+    frequencies: Vec<u16>
+}
+
+impl HIST {
+    /// Creates a new `hIST` holding one frequency per palette entry.
+    pub fn new(frequencies: Vec<u16>) -> Result<HIST, String> {
+        if frequencies.is_empty() || frequencies.len() > 256 {
+            return Err(format!("hIST must hold 1-256 entries, but holds {}.", frequencies.len()));
+        }
+
+        Ok(HIST { frequencies })
+    }
+
+    /// The usage frequency of each palette entry, in palette order.
+    pub fn frequencies(&self) -> &[u16] {
+        &self.frequencies
+    }
+
+    /// Checks that the number of entries matches the number of entries in
+    /// the `PLTE` chunk of the image.
+    pub fn validate(&self, palette: &Chunk) -> Result<(), String> {
+        let palette_entries: usize = palette.data().len() / 3;
+
+        if self.frequencies.len() != palette_entries {
+            return Err(format!("hIST holds {} entries, but PLTE holds {}.",
+                self.frequencies.len(), palette_entries));
+        }
+
+        Ok(())
+    }
+
+    /// Serializes this into a `hIST` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.frequencies
+            .iter()
+            .flat_map(|f| f.to_be_bytes())
+            .collect();
+
+        Chunk::new(ChunkType::from_str("hIST").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for HIST {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("hIST")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'hIST'".into());
+        }
+
+        if !value.length().is_multiple_of(2) {
+            return Err(format!("Chunk length {} is not a multiple of 2.", value.length()));
+        }
+
+        HIST::new(value.data()
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes(b.try_into().unwrap()))
+            .collect())
+    }
+}
+
+chunk_spec!(HIST, "hIST", fn validate_context(&self, png: &Png) {
+    match png.chunk_by_type("PLTE") {
+        Some(palette) => HIST::validate(self, palette),
+        None => Err("hIST chunk requires a PLTE chunk.".into())
+    }
+});
+
+impl fmt::Display for HIST {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequencies: Vec<String> = self.frequencies.iter().map(|f| f.to_string()).collect();
+
+        writeln!(f, "hIST Image histogram {{",)?;
+        writeln!(f, "  Entries: {}", self.frequencies.len())?;
+        writeln!(f, "  Frequencies: {}", frequencies.join(", "))?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hist_round_trip() {
+        let hist = HIST::new(vec![0, 1, 65535]).unwrap();
+        let chunk = hist.to_chunk();
+
+        assert_eq!(chunk.data(), &[0, 0, 0, 1, 255, 255]);
+        assert_eq!(HIST::try_from(chunk).unwrap(), hist);
+    }
+
+    #[test]
+    fn test_hist_validate() {
+        let hist = HIST::new(vec![10, 20]).unwrap();
+        let palette = Chunk::new(ChunkType::from_str("PLTE").unwrap(), vec![0; 6]);
+        assert!(hist.validate(&palette).is_ok());
+
+        let palette = Chunk::new(ChunkType::from_str("PLTE").unwrap(), vec![0; 9]);
+        assert!(hist.validate(&palette).is_err());
+    }
+
+    #[test]
+    fn test_hist_invalid() {
+        assert!(HIST::new(vec![]).is_err());
+        assert!(HIST::new(vec![0; 257]).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("hIST").unwrap(), vec![0, 1, 2]);
+        assert!(HIST::try_from(chunk).is_err());
+    }
+}
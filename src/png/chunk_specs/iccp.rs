@@ -0,0 +1,187 @@
+use crate::png::chunk_specs::{IHDR, decode_latin1, encode_latin1, validate_keyword};
+use crate::png::{zlib, Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The length of the fixed header at the start of every ICC profile.
+const ICC_HEADER_LENGTH: usize = 128;
+
+/// An embedded ICC profile. An `iCCP` chunk holds a Latin-1 profile name
+/// followed by the zlib-compressed profile. The profile is decompressed when
+/// the chunk is parsed. See the PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.iCCP
+pub struct ICCP {
+    profile_name: String,
+    profile: Vec<u8>
+}
+
+impl ICCP {
+    /// The only compression method defined by the PNG spec (zlib deflate).
+    pub const COMPRESSION_METHOD: u8 = 0;
+
+    /// Creates a new `iCCP` after validating the profile name and checking
+    /// that the profile has a well-formed ICC header.
+    pub fn new(profile_name: &str, profile: Vec<u8>) -> Result<ICCP, String> {
+        validate_keyword(profile_name)?;
+
+        if profile.len() < ICC_HEADER_LENGTH {
+            return Err(format!("ICC profile of {} bytes is shorter than the {} byte ICC header.",
+                profile.len(), ICC_HEADER_LENGTH));
+        }
+
+        if &profile[36..40] != b"acsp" {
+            return Err("ICC profile is missing the 'acsp' file signature.".into());
+        }
+
+        let declared_size: usize = u32::from_be_bytes(profile[0..4].try_into().unwrap()) as usize;
+        if declared_size != profile.len() {
+            return Err(format!("ICC profile size {} does not match its declared size {}.",
+                profile.len(), declared_size));
+        }
+
+        Ok(ICCP {
+            profile_name: profile_name.to_string(),
+            profile
+        })
+    }
+
+    /// The name used to refer to the profile.
+    pub fn profile_name(&self) -> &str {
+        &self.profile_name
+    }
+
+    /// The raw (decompressed) ICC profile, as it would be stored in an
+    /// `.icc` file.
+    pub fn profile(&self) -> &[u8] {
+        &self.profile
+    }
+
+    /// The color space signature from the ICC profile header, such as
+    /// `RGB ` or `GRAY`.
+    pub fn color_space(&self) -> String {
+        decode_latin1(&self.profile[16..20])
+    }
+
+    /// Serializes this profile into an `iCCP` chunk, compressing the profile.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = encode_latin1(&self.profile_name)
+            .into_iter()
+            .chain([0, ICCP::COMPRESSION_METHOD])
+            .chain(zlib::deflate(&self.profile))
+            .collect();
+
+        Chunk::new(ChunkType::from_str("iCCP").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for ICCP {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("iCCP")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'iCCP'".into());
+        }
+
+        let data: &[u8] = value.data();
+        let separator: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("iCCP chunk is missing the null separator after the profile name.".into())
+        };
+
+        match data.get(separator + 1) {
+            Some(&ICCP::COMPRESSION_METHOD) => (),
+            Some(method) => return Err(format!("Unknown iCCP compression method {}.", method)),
+            None => return Err("iCCP chunk is missing the compression method.".into())
+        }
+
+        let profile: Vec<u8> = zlib::inflate_with_limit(&data[separator + 2..], zlib::MAX_METADATA_SIZE)?;
+        ICCP::new(&decode_latin1(&data[..separator]), profile)
+    }
+}
+
+chunk_spec!(ICCP, "iCCP", fn validate_context(&self, png: &Png) {
+    if png.chunk_by_type("sRGB").is_some() {
+        return Err("iCCP and sRGB chunks must not both be present.".into());
+    }
+
+    let ihdr: IHDR = match png.chunk_by_type("IHDR") {
+        Some(chunk) => IHDR::try_from(chunk.clone())?,
+        None => return Err("iCCP chunk requires an IHDR chunk.".into())
+    };
+
+    // grayscale images need a GRAY profile, color images an RGB profile
+    let expected: &str = match ihdr.color_type() {
+        0 | 4 => "GRAY",
+        _ => "RGB "
+    };
+
+    if self.color_space() != expected {
+        return Err(format!("iCCP color space '{}' does not match color type {}.",
+            self.color_space().trim_end(), ihdr.color_type()));
+    }
+
+    Ok(())
+});
+
+impl fmt::Display for ICCP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "iCCP Embedded ICC profile {{",)?;
+        writeln!(f, "  Profile Name: {}", self.profile_name())?;
+        writeln!(f, "  Color Space: {}", self.color_space().trim_end())?;
+        writeln!(f, "  Profile: {} bytes", self.profile().len())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_profile() -> Vec<u8> {
+        let mut profile: Vec<u8> = vec![0; 200];
+        profile[0..4].copy_from_slice(&200u32.to_be_bytes());
+        profile[16..20].copy_from_slice(b"RGB ");
+        profile[36..40].copy_from_slice(b"acsp");
+        profile
+    }
+
+    #[test]
+    fn test_iccp_round_trip() {
+        let iccp = ICCP::new("Display P3", testing_profile()).unwrap();
+        let parsed = ICCP::try_from(iccp.to_chunk()).unwrap();
+
+        assert_eq!(parsed.profile_name(), "Display P3");
+        assert_eq!(parsed.profile(), &testing_profile()[..]);
+        assert_eq!(parsed.color_space(), "RGB ");
+    }
+
+    #[test]
+    fn test_iccp_invalid_profiles() {
+        assert!(ICCP::new("Profile", vec![0; 16]).is_err());
+
+        let mut profile = testing_profile();
+        profile[36] = b'x';
+        assert!(ICCP::new("Profile", profile).is_err());
+
+        let mut profile = testing_profile();
+        profile.push(0);
+        assert!(ICCP::new("Profile", profile).is_err());
+    }
+
+    #[test]
+    fn test_iccp_unknown_compression_method() {
+        let data: Vec<u8> = b"Profile\0\x08"
+            .iter()
+            .copied()
+            .chain(zlib::deflate(&testing_profile()))
+            .collect();
+        let chunk = Chunk::new(ChunkType::from_str("iCCP").unwrap(), data);
+
+        assert!(ICCP::try_from(chunk).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::png::{Chunk, ChunkType};
+use crate::png::{Chunk, ChunkType, PngError};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use std::fmt;
@@ -14,47 +14,161 @@ pub struct IHDR {
 }
 
 impl IHDR {
-    fn width(&self) -> u32 {
+    /// Creates a new `IHDR` using the only compression and filter methods
+    /// defined by the PNG spec, after validating the bit depth and color type.
+    pub fn new(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace_method: u8)
+            -> Result<IHDR, String> {
+        let ihdr = IHDR {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method
+        };
+
+        ihdr.validate()?;
+        Ok(ihdr)
+    }
+
+    pub fn width(&self) -> u32 {
         self.width
     }
 
-    fn height(&self) -> u32 {
+    pub fn height(&self) -> u32 {
         self.height
     }
 
-    fn bit_depth(&self) -> u8 {
+    pub fn bit_depth(&self) -> u8 {
         self.bit_depth
     }
 
-    fn color_type(&self) -> u8 {
+    pub fn color_type(&self) -> u8 {
         self.color_type
     } 
 
-    fn compression_method(&self) -> u8 {
+    pub fn compression_method(&self) -> u8 {
         self.compression_method
     }
     
-    fn filter_method(&self) -> u8 {
+    pub fn filter_method(&self) -> u8 {
         self.filter_method
     }
 
-    fn interlace_method(&self) -> u8 {
+    pub fn interlace_method(&self) -> u8 {
         self.interlace_method
     }
+
+    /// The number of samples in each pixel. Palette indices count as a 
+    /// single sample.
+    pub fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1
+        }
+    }
+
+    /// Serializes this into an `IHDR` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.width
+            .to_be_bytes()
+            .iter()
+            .chain(self.height.to_be_bytes().iter())
+            .chain([
+                self.bit_depth,
+                self.color_type,
+                self.compression_method,
+                self.filter_method,
+                self.interlace_method
+            ].iter())
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("IHDR").unwrap(), data)
+    }
+
+    /// The number of bytes in the decompressed image data, which holds each
+    /// scanline of each interlace pass preceded by its filter type byte.
+    /// Saturates at `u64::MAX` for images too large to hold in memory.
+    pub fn image_data_size(&self) -> u64 {
+        let bits_per_pixel: u64 = self.channels() as u64 * self.bit_depth as u64;
+        let pass_size = |width: u64, height: u64| match width {
+            0 => 0,
+            _ => ((width * bits_per_pixel).div_ceil(8) + 1).saturating_mul(height)
+        };
+        let (width, height): (u64, u64) = (self.width as u64, self.height as u64);
+
+        match self.interlace_method {
+            0 => pass_size(width, height),
+            // the starting column and row and the spacing of each Adam7 pass
+            _ => [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)]
+                .iter()
+                .map(|&(x, y, dx, dy)| pass_size(
+                    width.saturating_sub(x).div_ceil(dx),
+                    height.saturating_sub(y).div_ceil(dy)
+                ))
+                .fold(0, u64::saturating_add)
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let valid_combination = match self.color_type {
+            0 => matches!(self.bit_depth, 1 | 2 | 4 | 8 | 16),
+            2 => matches!(self.bit_depth, 8 | 16),
+            3 => matches!(self.bit_depth, 1 | 2 | 4 | 8),
+            4 => matches!(self.bit_depth, 8 | 16),
+            6 => matches!(self.bit_depth, 8 | 16),
+            _ => false
+        };
+
+        if !valid_combination {
+            return Err("Invalid color type and bit depth combination.".into());
+        }
+
+        if self.width == 0 || self.height == 0 || self.width > i32::MAX as u32 || self.height > i32::MAX as u32 {
+            return Err(format!("Image dimensions {}x{} must be between 1 and 2^31 - 1.", self.width, self.height));
+        }
+
+        if self.compression_method != 0 {
+            return Err(format!("Unknown compression method {}.", self.compression_method));
+        }
+
+        if self.filter_method != 0 {
+            return Err(format!("Unknown filter method {}.", self.filter_method));
+        }
+
+        if self.interlace_method > 1 {
+            return Err(format!("Unknown interlace method {}.", self.interlace_method));
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<Chunk> for IHDR {
-    type Error = String;
+    type Error = PngError;
 
+    /// Errors are reported at offset 8 as chunk 0, which is where the spec
+    /// requires the IHDR chunk to be.
     fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let invalid = |reason: String| PngError::InvalidChunk {
+            offset: 8,
+            chunk_index: 0,
+            chunk_type: value.chunk_type().clone(),
+            reason
+        };
+
         let expected_type: ChunkType = ChunkType::from_str("IHDR")?;
 
         if &expected_type != value.chunk_type() {
-            return Err("Chunk type does not match expected chunk type 'IHDR'".into());
+            return Err(invalid("Chunk type does not match expected chunk type 'IHDR'".into()));
         }
 
-        if value.length() != 13 {
-            return Err(format!("Chunk length {} does not match expected length 13.", value.length()));
+        if value.data().len() != 13 {
+            return Err(invalid(format!("Chunk length {} does not match expected length 13.", value.length())));
         }
 
         let ihdr = IHDR {
@@ -67,23 +181,14 @@ impl TryFrom<Chunk> for IHDR {
             interlace_method: value.data()[12]
         };
 
-        let valid_combination = match ihdr.color_type {
-            0 => matches!(ihdr.bit_depth, 1 | 2 | 4 | 8 | 16),
-            2 => matches!(ihdr.bit_depth, 8 | 16),
-            3 => matches!(ihdr.bit_depth, 1 | 2 | 4 | 8),
-            4 => matches!(ihdr.bit_depth, 8 | 16),
-            6 => matches!(ihdr.bit_depth, 8 | 16),
-            _ => false
-        };
-
-        if !valid_combination {
-            return Err("Invalid color type and bit depth combination.".into());
-        }
+        ihdr.validate().map_err(invalid)?;
 
         Ok(ihdr)
     }
 }
 
+chunk_spec!(IHDR, "IHDR");
+
 impl fmt::Display for IHDR {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "IHDR Image header {{",)?;
@@ -99,3 +204,33 @@ impl fmt::Display for IHDR {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ihdr_validation() {
+        assert!(IHDR::new(1, 1, 16, 6, 1).is_ok());
+        assert!(IHDR::new(1, 1, 16, 3, 0).is_err());
+        assert!(IHDR::new(0, 1, 8, 0, 0).is_err());
+        assert!(IHDR::new(1, 1 << 31, 8, 0, 0).is_err());
+        assert!(IHDR::new(1, 1, 8, 0, 2).is_err());
+
+        let mut chunk: Chunk = IHDR::new(1, 1, 8, 0, 0).unwrap().to_chunk();
+        let mut data: Vec<u8> = chunk.data().to_vec();
+        data[11] = 1;
+        chunk = Chunk::new(chunk.chunk_type().clone(), data);
+
+        assert!(matches!(IHDR::try_from(chunk), Err(PngError::InvalidChunk { offset: 8, chunk_index: 0, .. })));
+    }
+
+    #[test]
+    fn test_image_data_size() {
+        assert_eq!(IHDR::new(10, 3, 8, 2, 0).unwrap().image_data_size(), 3 * (1 + 30));
+        assert_eq!(IHDR::new(10, 3, 1, 0, 0).unwrap().image_data_size(), 3 * (1 + 2));
+        // the Adam7 passes of a 3x3 image are 1x1, empty, empty, 1x1, 2x1, 1x2, and 3x1 pixels
+        assert_eq!(IHDR::new(3, 3, 8, 0, 1).unwrap().image_data_size(), 2 + 2 + 3 + 2 * 2 + 4);
+        assert_eq!(IHDR::new(i32::MAX as u32, i32::MAX as u32, 16, 6, 0).unwrap().image_data_size(), u64::MAX);
+    }
+}
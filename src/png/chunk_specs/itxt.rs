@@ -0,0 +1,238 @@
+use crate::png::chunk_specs::{decode_latin1, encode_latin1, validate_keyword};
+use crate::png::{zlib, Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// An international textual data chunk. An `iTXt` chunk holds a Latin-1
+/// keyword, an optional language tag, the keyword translated into that
+/// language, and UTF-8 text which may be zlib-compressed. See the PNG spec
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.iTXt
+pub struct ITXT {
+    keyword: String,
+    compressed: bool,
+    language_tag: String,
+    translated_keyword: String,
+    text: String
+}
+
+impl ITXT {
+    /// The only compression method defined by the PNG spec (zlib deflate).
+    pub const COMPRESSION_METHOD: u8 = 0;
+
+    /// Creates a new `iTXt` entry after validating each of its fields. An
+    /// empty `language_tag` indicates that the language is unspecified.
+    pub fn new(keyword: &str, language_tag: &str, translated_keyword: &str,
+            text: &str, compressed: bool) -> Result<ITXT, String> {
+        validate_keyword(keyword)?;
+        validate_language_tag(language_tag)?;
+
+        if translated_keyword.contains('\0') {
+            return Err("Translated keywords may not contain null characters.".into());
+        }
+
+        if text.contains('\0') {
+            return Err("Text may not contain null characters.".into());
+        }
+
+        Ok(ITXT {
+            keyword: keyword.to_string(),
+            compressed,
+            language_tag: language_tag.to_string(),
+            translated_keyword: translated_keyword.to_string(),
+            text: text.to_string()
+        })
+    }
+
+    /// The keyword that indicates what this text represents.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// Whether the text is stored compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// The language the text is written in, such as `en-US`. An empty
+    /// string indicates that the language is unspecified.
+    pub fn language_tag(&self) -> &str {
+        &self.language_tag
+    }
+
+    /// The keyword translated into the language of the text.
+    pub fn translated_keyword(&self) -> &str {
+        &self.translated_keyword
+    }
+
+    /// The (decompressed) text associated with the keyword.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Serializes this entry into an `iTXt` chunk, compressing the text if
+    /// the entry is marked as compressed.
+    pub fn to_chunk(&self) -> Chunk {
+        let text: Vec<u8> = match self.compressed {
+            true => zlib::deflate(self.text.as_bytes()),
+            false => self.text.as_bytes().to_vec()
+        };
+
+        let data: Vec<u8> = encode_latin1(&self.keyword)
+            .into_iter()
+            .chain([0, self.compressed as u8, ITXT::COMPRESSION_METHOD])
+            .chain(self.language_tag.bytes())
+            .chain(std::iter::once(0))
+            .chain(self.translated_keyword.bytes())
+            .chain(std::iter::once(0))
+            .chain(text)
+            .collect();
+
+        Chunk::new(ChunkType::from_str("iTXt").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for ITXT {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("iTXt")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'iTXt'".into());
+        }
+
+        let data: &[u8] = value.data();
+        let keyword_end: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("iTXt chunk is missing the null separator after the keyword.".into())
+        };
+
+        let (compressed, method): (u8, u8) = match data.get(keyword_end + 1..keyword_end + 3) {
+            Some(flags) => (flags[0], flags[1]),
+            None => return Err("iTXt chunk is missing the compression flag and method.".into())
+        };
+
+        let compressed: bool = match compressed {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("Invalid iTXt compression flag {}.", flag))
+        };
+
+        if compressed && method != ITXT::COMPRESSION_METHOD {
+            return Err(format!("Unknown iTXt compression method {}.", method));
+        }
+
+        let mut fields = data[keyword_end + 3..].splitn(3, |&b| b == 0);
+        let (language_tag, translated_keyword, text) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(l), Some(k), Some(t)) => (l, k, t),
+            _ => return Err("iTXt chunk is missing the null separators after the language tag or translated keyword.".into())
+        };
+
+        let text: Vec<u8> = match compressed {
+            true => zlib::inflate_with_limit(text, zlib::MAX_METADATA_SIZE)?,
+            false => text.to_vec()
+        };
+
+        ITXT::new(
+            &decode_latin1(&data[..keyword_end]),
+            &decode_utf8(language_tag, "language tag")?,
+            &decode_utf8(translated_keyword, "translated keyword")?,
+            &decode_utf8(&text, "text")?,
+            compressed
+        )
+    }
+}
+
+chunk_spec!(ITXT, "iTXt");
+
+impl fmt::Display for ITXT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "iTXt International textual data {{",)?;
+        writeln!(f, "  Keyword: {}", self.keyword())?;
+        writeln!(f, "  Compressed: {}", self.is_compressed())?;
+        writeln!(f, "  Language Tag: {}", self.language_tag())?;
+        writeln!(f, "  Translated Keyword: {}", self.translated_keyword())?;
+        writeln!(f, "  Text: {}", self.text())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+/// Checks that a language tag is empty or consists of hyphen-separated words
+/// of 1-8 ASCII alphanumeric characters, as described by RFC 3066.
+pub fn validate_language_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty() {
+        return Ok(());
+    }
+
+    let valid: bool = tag
+        .split('-')
+        .all(|word| (1..=8).contains(&word.len()) && word.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    if !valid {
+        return Err(format!("Language tag '{}' is not a valid RFC 3066 language tag.", tag));
+    }
+
+    Ok(())
+}
+
+fn decode_utf8(bytes: &[u8], field: &str) -> Result<String, String> {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(format!("iTXt {} is not valid UTF-8.", field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_itxt_round_trip() {
+        let itxt = ITXT::new("Description", "ja", "\u{8aac}\u{660e}", "\u{30b5}\u{30a4}\u{30b3}\u{30ed}", false).unwrap();
+        let parsed = ITXT::try_from(itxt.to_chunk()).unwrap();
+
+        assert_eq!(parsed.keyword(), "Description");
+        assert_eq!(parsed.language_tag(), "ja");
+        assert_eq!(parsed.translated_keyword(), "\u{8aac}\u{660e}");
+        assert_eq!(parsed.text(), "\u{30b5}\u{30a4}\u{30b3}\u{30ed}");
+        assert!(!parsed.is_compressed());
+    }
+
+    #[test]
+    fn test_itxt_compressed_round_trip() {
+        let text: String = "Gr\u{fc}\u{df}e aus M\u{fc}nchen \u{1f3b2} ".repeat(10);
+        let itxt = ITXT::new("Comment", "de-DE", "Kommentar", &text, true).unwrap();
+        let parsed = ITXT::try_from(itxt.to_chunk()).unwrap();
+
+        assert_eq!(parsed.text(), text);
+        assert!(parsed.is_compressed());
+    }
+
+    #[test]
+    fn test_itxt_empty_language_and_translation() {
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), b"Title\0\0\0\0\0Dice".to_vec());
+        let parsed = ITXT::try_from(chunk).unwrap();
+
+        assert_eq!(parsed.language_tag(), "");
+        assert_eq!(parsed.translated_keyword(), "");
+        assert_eq!(parsed.text(), "Dice");
+    }
+
+    #[test]
+    fn test_invalid_language_tags() {
+        assert!(validate_language_tag("en-US").is_ok());
+        assert!(validate_language_tag("x-klingon").is_ok());
+        assert!(validate_language_tag("en_US").is_err());
+        assert!(validate_language_tag("-en").is_err());
+        assert!(validate_language_tag("toolongword").is_err());
+    }
+
+    #[test]
+    fn test_itxt_missing_separators() {
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), b"Title\0\0\0en".to_vec());
+        assert!(ITXT::try_from(chunk).is_err());
+    }
+}
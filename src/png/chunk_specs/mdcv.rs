@@ -0,0 +1,159 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The scale of chromaticity coordinates, stored in units of 0.00002.
+const CHROMATICITY_SCALE: f64 = 50000.0;
+
+/// The scale of luminance values, stored in units of 0.0001 cd/m².
+const LUMINANCE_SCALE: f64 = 10000.0;
+
+/// The color volume of the display used to master the content, as described
+/// by SMPTE ST 2086. See the PNG Third Edition spec for more details.
+/// https://www.w3.org/TR/png-3/#mDCV-chunk
+#[derive(Debug, PartialEq, Eq)]
+pub struct MDCV {
+    primaries: [(u16, u16); 3],
+    white_point: (u16, u16),
+    max_luminance: u32,
+    min_luminance: u32
+}
+
+impl MDCV {
+    /// Creates a new `mDCV` from raw chromaticity coordinates (in units of
+    /// 0.00002) for the red, green, and blue primaries and the white point,
+    /// and raw luminance values (in units of 0.0001 cd/m²).
+    pub fn new(primaries: [(u16, u16); 3], white_point: (u16, u16), max_luminance: u32,
+            min_luminance: u32) -> Result<MDCV, String> {
+        if min_luminance >= max_luminance {
+            return Err("mDCV minimum luminance must be less than the maximum luminance.".into());
+        }
+
+        Ok(MDCV { primaries, white_point, max_luminance, min_luminance })
+    }
+
+    /// The raw chromaticity coordinates of the red, green, and blue primaries.
+    pub fn primaries(&self) -> [(u16, u16); 3] {
+        self.primaries
+    }
+
+    /// The raw chromaticity coordinates of the white point.
+    pub fn white_point(&self) -> (u16, u16) {
+        self.white_point
+    }
+
+    /// The raw maximum luminance, in units of 0.0001 cd/m².
+    pub fn max_luminance(&self) -> u32 {
+        self.max_luminance
+    }
+
+    /// The raw minimum luminance, in units of 0.0001 cd/m².
+    pub fn min_luminance(&self) -> u32 {
+        self.min_luminance
+    }
+
+    /// The maximum luminance of the mastering display in cd/m².
+    pub fn max_luminance_nits(&self) -> f64 {
+        self.max_luminance as f64 / LUMINANCE_SCALE
+    }
+
+    /// The minimum luminance of the mastering display in cd/m².
+    pub fn min_luminance_nits(&self) -> f64 {
+        self.min_luminance as f64 / LUMINANCE_SCALE
+    }
+
+    /// Serializes this into an `mDCV` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.primaries
+            .iter()
+            .chain(std::iter::once(&self.white_point))
+            .flat_map(|(x, y)| IntoIterator::into_iter(x.to_be_bytes()).chain(y.to_be_bytes()))
+            .chain(self.max_luminance.to_be_bytes())
+            .chain(self.min_luminance.to_be_bytes())
+            .collect();
+
+        Chunk::new(ChunkType::from_str("mDCV").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for MDCV {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("mDCV")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'mDCV'".into());
+        }
+
+        if value.length() != 24 {
+            return Err(format!("Chunk length {} does not match expected length 24.", value.length()));
+        }
+
+        let data: &[u8] = value.data();
+        let u16_at = |idx: usize| u16::from_be_bytes(data[idx..idx + 2].try_into().unwrap());
+        let coordinate_at = |idx: usize| (u16_at(idx), u16_at(idx + 2));
+
+        MDCV::new(
+            [coordinate_at(0), coordinate_at(4), coordinate_at(8)],
+            coordinate_at(12),
+            u32::from_be_bytes(data[16..20].try_into().unwrap()),
+            u32::from_be_bytes(data[20..24].try_into().unwrap())
+        )
+    }
+}
+
+chunk_spec!(MDCV, "mDCV");
+
+impl fmt::Display for MDCV {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coordinate = |(x, y): (u16, u16)| {
+            format!("({:.5}, {:.5})", x as f64 / CHROMATICITY_SCALE, y as f64 / CHROMATICITY_SCALE)
+        };
+
+        writeln!(f, "mDCV Mastering display color volume {{",)?;
+        writeln!(f, "  Red: {}", coordinate(self.primaries[0]))?;
+        writeln!(f, "  Green: {}", coordinate(self.primaries[1]))?;
+        writeln!(f, "  Blue: {}", coordinate(self.primaries[2]))?;
+        writeln!(f, "  White Point: {}", coordinate(self.white_point))?;
+        writeln!(f, "  Max Luminance: {} cd/m2", self.max_luminance_nits())?;
+        writeln!(f, "  Min Luminance: {} cd/m2", self.min_luminance_nits())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bt2020_mdcv() -> MDCV {
+        MDCV::new([(35400, 14600), (8500, 39850), (6550, 2300)], (15635, 16450), 10_000_000, 1).unwrap()
+    }
+
+    #[test]
+    fn test_mdcv_round_trip() {
+        let mdcv = bt2020_mdcv();
+        let chunk = mdcv.to_chunk();
+
+        assert_eq!(chunk.length(), 24);
+        assert_eq!(MDCV::try_from(chunk).unwrap(), mdcv);
+    }
+
+    #[test]
+    fn test_mdcv_luminance() {
+        let mdcv = bt2020_mdcv();
+        assert_eq!(mdcv.max_luminance_nits(), 1000.0);
+        assert_eq!(mdcv.min_luminance_nits(), 0.0001);
+    }
+
+    #[test]
+    fn test_mdcv_invalid() {
+        assert!(MDCV::new([(0, 0); 3], (0, 0), 100, 100).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("mDCV").unwrap(), vec![0; 20]);
+        assert!(MDCV::try_from(chunk).is_err());
+    }
+}
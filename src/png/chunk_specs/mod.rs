@@ -1,3 +1,77 @@
+/// Implements `ChunkSpec` for a spec that is parsed with `TryFrom<Chunk>`
+/// and serialized with an inherent `to_chunk` method. Specs with context
+/// requirements pass the body of `validate_context` after the chunk type.
+macro_rules! chunk_spec {
+    ($spec:ident, $chunk_type:literal) => {
+        chunk_spec!($spec, $chunk_type, fn validate_context(&self, _png: &Png) { Ok(()) });
+    };
+    ($spec:ident, $chunk_type:literal, fn validate_context(&$self:ident, $png:ident: &Png) $body:block) => {
+        impl $crate::png::chunk_specs::ChunkSpec for $spec {
+            const CHUNK_TYPE: &'static str = $chunk_type;
+
+            fn parse(chunk: &$crate::png::Chunk) -> Result<$spec, String> {
+                <$spec as std::convert::TryFrom<$crate::png::Chunk>>::try_from(chunk.clone()).map_err(Into::into)
+            }
+
+            fn to_chunk(&self) -> $crate::png::Chunk {
+                $spec::to_chunk(self)
+            }
+
+            fn validate_context(&$self, $png: &$crate::png::Png) -> Result<(), String> $body
+        }
+    };
+}
+
+mod bkgd;
+mod cabx;
+mod chrm;
+mod cicp;
+mod clli;
+mod exif;
+mod gama;
+mod gifg;
+mod gifx;
+mod hist;
+mod iccp;
 mod ihdr;
+mod itxt;
+mod mdcv;
+mod offs;
+mod pcal;
+mod phys;
+mod sbit;
+mod scal;
+mod spec;
+mod splt;
+mod srgb;
+mod ster;
+mod text;
+mod time;
+mod ztxt;
 
-pub use ihdr::*;
\ No newline at end of file
+pub use bkgd::*;
+pub use cabx::*;
+pub use chrm::*;
+pub use cicp::*;
+pub use clli::*;
+pub use exif::*;
+pub use gama::*;
+pub use gifg::*;
+pub use gifx::*;
+pub use hist::*;
+pub use iccp::*;
+pub use ihdr::*;
+pub use itxt::*;
+pub use mdcv::*;
+pub use offs::*;
+pub use pcal::*;
+pub use phys::*;
+pub use sbit::*;
+pub use scal::*;
+pub use spec::*;
+pub use splt::*;
+pub use srgb::*;
+pub use ster::*;
+pub use text::*;
+pub use time::*;
+pub use ztxt::*;
@@ -0,0 +1,118 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The position of the image on a page, given as an offset from the left
+/// and top edges. See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.oFFs
+#[derive(Debug, PartialEq, Eq)]
+pub struct OFFS {
+    position_x: i32,
+    position_y: i32,
+    unit_specifier: u8
+}
+
+impl OFFS {
+    /// The offsets are measured in pixels.
+    pub const UNIT_PIXEL: u8 = 0;
+
+    /// The offsets are measured in micrometers.
+    pub const UNIT_MICROMETER: u8 = 1;
+
+    /// Creates a new `oFFs` after validating the unit specifier.
+    pub fn new(position_x: i32, position_y: i32, unit_specifier: u8) -> Result<OFFS, String> {
+        if unit_specifier > OFFS::UNIT_MICROMETER {
+            return Err(format!("Invalid oFFs unit specifier {}.", unit_specifier));
+        }
+
+        Ok(OFFS { position_x, position_y, unit_specifier })
+    }
+
+    /// The offset from the left edge of the page.
+    pub fn position_x(&self) -> i32 {
+        self.position_x
+    }
+
+    /// The offset from the top edge of the page.
+    pub fn position_y(&self) -> i32 {
+        self.position_y
+    }
+
+    /// The unit of the offsets, either `UNIT_PIXEL` or `UNIT_MICROMETER`.
+    pub fn unit_specifier(&self) -> u8 {
+        self.unit_specifier
+    }
+
+    /// Serializes this into an `oFFs` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.position_x
+            .to_be_bytes()
+            .iter()
+            .chain(self.position_y.to_be_bytes().iter())
+            .chain(std::iter::once(&self.unit_specifier))
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("oFFs").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for OFFS {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("oFFs")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'oFFs'".into());
+        }
+
+        if value.length() != 9 {
+            return Err(format!("Chunk length {} does not match expected length 9.", value.length()));
+        }
+
+        OFFS::new(
+            i32::from_be_bytes(value.data()[0..4].try_into().unwrap()),
+            i32::from_be_bytes(value.data()[4..8].try_into().unwrap()),
+            value.data()[8]
+        )
+    }
+}
+
+chunk_spec!(OFFS, "oFFs");
+
+impl fmt::Display for OFFS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit: &str = match self.unit_specifier() {
+            OFFS::UNIT_MICROMETER => "micrometers",
+            _ => "pixels"
+        };
+
+        writeln!(f, "oFFs Image offset {{",)?;
+        writeln!(f, "  Position X: {} {}", self.position_x(), unit)?;
+        writeln!(f, "  Position Y: {} {}", self.position_y(), unit)?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offs_round_trip() {
+        let offs = OFFS::new(-20, 300, OFFS::UNIT_MICROMETER).unwrap();
+        let chunk = offs.to_chunk();
+
+        assert_eq!(chunk.data(), &[255, 255, 255, 236, 0, 0, 1, 44, 1]);
+        assert_eq!(OFFS::try_from(chunk).unwrap(), offs);
+    }
+
+    #[test]
+    fn test_offs_invalid() {
+        assert!(OFFS::new(0, 0, 2).is_err());
+    }
+}
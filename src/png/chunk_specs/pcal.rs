@@ -0,0 +1,312 @@
+use crate::png::chunk_specs::{decode_latin1, encode_latin1, validate_keyword, validate_latin1_text};
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The calibration of stored sample values to physical values, such as
+/// temperatures or elevations. See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.pCAL
+#[derive(Debug, PartialEq)]
+pub struct PCAL {
+    calibration_name: String,
+    original_zero: i32,
+    original_max: i32,
+    equation_type: u8,
+    unit_name: String,
+    parameters: Vec<String>
+}
+
+impl PCAL {
+    /// `Y = p0 + p1 * X / (x1 - x0)`
+    pub const EQUATION_LINEAR: u8 = 0;
+
+    /// `Y = p0 + p1 * exp(p2 * X / (x1 - x0))`
+    pub const EQUATION_EXPONENTIAL: u8 = 1;
+
+    /// `Y = p0 + p1 * pow(p2, X / (x1 - x0))`
+    pub const EQUATION_ARBITRARY_EXPONENTIAL: u8 = 2;
+
+    /// `Y = p0 + p1 * sinh(p2 * (X - p3) / (x1 - x0))`
+    pub const EQUATION_HYPERBOLIC: u8 = 3;
+
+    /// Creates a new `pCAL` after validating the names, the original sample
+    /// range, and that the equation type has the right number of
+    /// floating-point parameters.
+    pub fn new(calibration_name: &str, original_zero: i32, original_max: i32, equation_type: u8,
+            unit_name: &str, parameters: Vec<String>) -> Result<PCAL, String> {
+        validate_keyword(calibration_name)?;
+        validate_latin1_text(unit_name)?;
+
+        if unit_name.contains('\0') {
+            return Err("pCAL unit name must not contain null characters.".into());
+        }
+
+        if original_zero == original_max {
+            return Err(format!("pCAL original sample range {}..{} is empty.", original_zero, original_max));
+        }
+
+        let expected_parameters: usize = match equation_type {
+            PCAL::EQUATION_LINEAR => 2,
+            PCAL::EQUATION_EXPONENTIAL => 3,
+            PCAL::EQUATION_ARBITRARY_EXPONENTIAL | PCAL::EQUATION_HYPERBOLIC => 4,
+            _ => return Err(format!("Invalid pCAL equation type {}.", equation_type))
+        };
+
+        if parameters.len() != expected_parameters {
+            return Err(format!("pCAL equation type {} requires {} parameters, but {} were given.",
+                equation_type, expected_parameters, parameters.len()));
+        }
+
+        for parameter in &parameters {
+            parse_ascii_float(parameter)?;
+        }
+
+        Ok(PCAL {
+            calibration_name: calibration_name.to_string(),
+            original_zero,
+            original_max,
+            equation_type,
+            unit_name: unit_name.to_string(),
+            parameters
+        })
+    }
+
+    /// The name of the calibration.
+    pub fn calibration_name(&self) -> &str {
+        &self.calibration_name
+    }
+
+    /// The original sample value that stored sample 0 maps to (`x0`).
+    pub fn original_zero(&self) -> i32 {
+        self.original_zero
+    }
+
+    /// The original sample value that the maximum stored sample maps to (`x1`).
+    pub fn original_max(&self) -> i32 {
+        self.original_max
+    }
+
+    /// The equation type, one of the `EQUATION_*` constants.
+    pub fn equation_type(&self) -> u8 {
+        self.equation_type
+    }
+
+    /// The unit of the physical values, such as `Celsius`.
+    pub fn unit_name(&self) -> &str {
+        &self.unit_name
+    }
+
+    /// The equation parameters as stored in the chunk.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Maps a stored sample value to the original sample value, where
+    /// `max_stored` is the largest sample value at the image bit depth.
+    pub fn original_sample(&self, stored: u32, max_stored: u32) -> i64 {
+        let range: i64 = self.original_max as i64 - self.original_zero as i64;
+        let max_stored: i64 = max_stored.max(1) as i64;
+        let scaled: i64 = stored as i64 * range;
+
+        // integer division rounding half away from zero, as in the spec
+        let rounded: i64 = if scaled >= 0 {
+            (scaled + max_stored / 2) / max_stored
+        } else {
+            (scaled - max_stored / 2) / max_stored
+        };
+
+        rounded + self.original_zero as i64
+    }
+
+    /// Maps a stored sample value to its physical value using the
+    /// calibration equation.
+    pub fn physical_value(&self, stored: u32, max_stored: u32) -> f64 {
+        let p: Vec<f64> = self.parameters
+            .iter()
+            .map(|s| parse_ascii_float(s).unwrap_or(0.0))
+            .collect();
+        let x: f64 = self.original_sample(stored, max_stored) as f64;
+        let range: f64 = self.original_max as f64 - self.original_zero as f64;
+
+        match self.equation_type {
+            PCAL::EQUATION_LINEAR => p[0] + p[1] * x / range,
+            PCAL::EQUATION_EXPONENTIAL => p[0] + p[1] * (p[2] * x / range).exp(),
+            PCAL::EQUATION_ARBITRARY_EXPONENTIAL => p[0] + p[1] * p[2].powf(x / range),
+            _ => p[0] + p[1] * (p[2] * (x - p[3]) / range).sinh()
+        }
+    }
+
+    /// Serializes this into a `pCAL` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data: Vec<u8> = encode_latin1(&self.calibration_name);
+        data.push(0);
+        data.extend_from_slice(&self.original_zero.to_be_bytes());
+        data.extend_from_slice(&self.original_max.to_be_bytes());
+        data.push(self.equation_type);
+        data.push(self.parameters.len() as u8);
+        data.extend(encode_latin1(&self.unit_name));
+
+        for parameter in &self.parameters {
+            data.push(0);
+            data.extend_from_slice(parameter.as_bytes());
+        }
+
+        Chunk::new(ChunkType::from_str("pCAL").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for PCAL {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("pCAL")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'pCAL'".into());
+        }
+
+        let data: &[u8] = value.data();
+        let separator: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("pCAL chunk is missing the null separator after the calibration name.".into())
+        };
+
+        let fields: &[u8] = &data[separator + 1..];
+        if fields.len() < 10 {
+            return Err("pCAL chunk is too short to hold the original sample range and equation.".into());
+        }
+
+        let original_zero: i32 = i32::from_be_bytes(fields[0..4].try_into().unwrap());
+        let original_max: i32 = i32::from_be_bytes(fields[4..8].try_into().unwrap());
+        let equation_type: u8 = fields[8];
+        let parameter_count: usize = fields[9] as usize;
+
+        let mut strings = fields[10..].split(|&b| b == 0);
+        let unit_name: String = decode_latin1(strings.next().unwrap_or_default());
+        let parameters: Vec<String> = strings
+            .map(|s| String::from_utf8(s.to_vec()).map_err(|_| "pCAL parameter is not ASCII.".to_string()))
+            .collect::<Result<Vec<String>, String>>()?;
+
+        if parameters.len() != parameter_count {
+            return Err(format!("pCAL chunk declares {} parameters, but holds {}.",
+                parameter_count, parameters.len()));
+        }
+
+        PCAL::new(&decode_latin1(&data[..separator]), original_zero, original_max,
+            equation_type, &unit_name, parameters)
+    }
+}
+
+chunk_spec!(PCAL, "pCAL");
+
+impl fmt::Display for PCAL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pCAL Pixel calibration {{",)?;
+        writeln!(f, "  Calibration Name: {}", self.calibration_name())?;
+        writeln!(f, "  Original Zero: {}", self.original_zero())?;
+        writeln!(f, "  Original Max: {}", self.original_max())?;
+        writeln!(f, "  Equation Type: {}", self.equation_type())?;
+        writeln!(f, "  Unit Name: {}", self.unit_name())?;
+        writeln!(f, "  Parameters: {}", self.parameters().join(", "))?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+/// Parses an ASCII floating-point string as defined by the PNG extensions
+/// spec: an optional sign, an integer and/or fractional part, and an
+/// optional exponent. Unlike `f64::from_str`, this rejects `inf`, `NaN`,
+/// and surrounding whitespace.
+pub fn parse_ascii_float(text: &str) -> Result<f64, String> {
+    let invalid = || format!("'{}' is not a valid floating-point string.", text);
+    let bytes: &[u8] = text.as_bytes();
+    let mut idx: usize = 0;
+
+    if matches!(bytes.first(), Some(b'+') | Some(b'-')) {
+        idx += 1;
+    }
+
+    let integer_digits: usize = bytes[idx..].iter().take_while(|b| b.is_ascii_digit()).count();
+    idx += integer_digits;
+
+    let mut fraction_digits: usize = 0;
+    if bytes.get(idx) == Some(&b'.') {
+        idx += 1;
+        fraction_digits = bytes[idx..].iter().take_while(|b| b.is_ascii_digit()).count();
+        idx += fraction_digits;
+    }
+
+    if integer_digits + fraction_digits == 0 {
+        return Err(invalid());
+    }
+
+    if matches!(bytes.get(idx), Some(b'e') | Some(b'E')) {
+        idx += 1;
+        if matches!(bytes.get(idx), Some(b'+') | Some(b'-')) {
+            idx += 1;
+        }
+
+        let exponent_digits: usize = bytes[idx..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if exponent_digits == 0 {
+            return Err(invalid());
+        }
+        idx += exponent_digits;
+    }
+
+    if idx != bytes.len() {
+        return Err(invalid());
+    }
+
+    f64::from_str(text).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_pcal() -> PCAL {
+        PCAL::new("Temperature", 0, 1000, PCAL::EQUATION_LINEAR, "Celsius",
+            vec!["-40".to_string(), "1.2e2".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_pcal_round_trip() {
+        let pcal = testing_pcal();
+        assert_eq!(PCAL::try_from(pcal.to_chunk()).unwrap(), pcal);
+    }
+
+    #[test]
+    fn test_pcal_physical_value() {
+        let pcal = testing_pcal();
+
+        assert_eq!(pcal.original_sample(255, 255), 1000);
+        assert_eq!(pcal.original_sample(128, 255), 502);
+        assert_eq!(pcal.physical_value(0, 255), -40.0);
+        assert_eq!(pcal.physical_value(255, 255), 80.0);
+    }
+
+    #[test]
+    fn test_pcal_invalid() {
+        let parameters = vec!["0".to_string(), "1".to_string()];
+
+        assert!(PCAL::new("Depth", 5, 5, 0, "m", parameters.clone()).is_err());
+        assert!(PCAL::new("Depth", 0, 5, 1, "m", parameters.clone()).is_err());
+        assert!(PCAL::new("Depth", 0, 5, 4, "m", parameters).is_err());
+        assert!(PCAL::new("Depth", 0, 5, 0, "m", vec!["0".to_string(), "inf".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_ascii_float() {
+        assert_eq!(parse_ascii_float("1.5").unwrap(), 1.5);
+        assert_eq!(parse_ascii_float("-.5E+1").unwrap(), -5.0);
+        assert_eq!(parse_ascii_float("7.").unwrap(), 7.0);
+
+        assert!(parse_ascii_float("").is_err());
+        assert!(parse_ascii_float(".").is_err());
+        assert!(parse_ascii_float("1e").is_err());
+        assert!(parse_ascii_float(" 1").is_err());
+        assert!(parse_ascii_float("NaN").is_err());
+    }
+}
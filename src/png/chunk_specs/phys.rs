@@ -0,0 +1,172 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// The number of meters in an inch, used to convert to and from DPI.
+const METERS_PER_INCH: f64 = 0.0254;
+
+/// The intended pixel size or aspect ratio for displaying the image. See the
+/// PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.pHYs
+#[derive(Debug, PartialEq, Eq)]
+pub struct PHYS {
+    pixels_per_unit_x: u32,
+    pixels_per_unit_y: u32,
+    unit_specifier: u8
+}
+
+impl PHYS {
+    /// The unit is unknown and the values only define the pixel aspect ratio.
+    pub const UNIT_UNKNOWN: u8 = 0;
+
+    /// The unit is the meter.
+    pub const UNIT_METER: u8 = 1;
+
+    /// Creates a new `pHYs` after validating the unit specifier.
+    pub fn new(pixels_per_unit_x: u32, pixels_per_unit_y: u32, unit_specifier: u8)
+            -> Result<PHYS, String> {
+        if unit_specifier > PHYS::UNIT_METER {
+            return Err(format!("Invalid pHYs unit specifier {}.", unit_specifier));
+        }
+
+        Ok(PHYS { pixels_per_unit_x, pixels_per_unit_y, unit_specifier })
+    }
+
+    /// Creates a `pHYs` measured in pixels per meter from a horizontal and
+    /// vertical resolution in dots per inch.
+    pub fn from_dpi(dpi_x: f64, dpi_y: f64) -> Result<PHYS, String> {
+        PHYS::new(dpi_to_ppm(dpi_x)?, dpi_to_ppm(dpi_y)?, PHYS::UNIT_METER)
+    }
+
+    /// The number of pixels per unit along the X axis.
+    pub fn pixels_per_unit_x(&self) -> u32 {
+        self.pixels_per_unit_x
+    }
+
+    /// The number of pixels per unit along the Y axis.
+    pub fn pixels_per_unit_y(&self) -> u32 {
+        self.pixels_per_unit_y
+    }
+
+    /// The unit of measurement, either `UNIT_UNKNOWN` or `UNIT_METER`.
+    pub fn unit_specifier(&self) -> u8 {
+        self.unit_specifier
+    }
+
+    /// The horizontal and vertical resolution in dots per inch. Returns
+    /// `None` if the unit is unknown, since only the aspect ratio is defined.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        match self.unit_specifier {
+            PHYS::UNIT_METER => Some((
+                self.pixels_per_unit_x as f64 * METERS_PER_INCH,
+                self.pixels_per_unit_y as f64 * METERS_PER_INCH
+            )),
+            _ => None
+        }
+    }
+
+    /// Serializes this into a `pHYs` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.pixels_per_unit_x
+            .to_be_bytes()
+            .iter()
+            .chain(self.pixels_per_unit_y.to_be_bytes().iter())
+            .chain(std::iter::once(&self.unit_specifier))
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("pHYs").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for PHYS {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("pHYs")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'pHYs'".into());
+        }
+
+        if value.length() != 9 {
+            return Err(format!("Chunk length {} does not match expected length 9.", value.length()));
+        }
+
+        PHYS::new(
+            u32::from_be_bytes(value.data()[0..4].try_into().unwrap()),
+            u32::from_be_bytes(value.data()[4..8].try_into().unwrap()),
+            value.data()[8]
+        )
+    }
+}
+
+chunk_spec!(PHYS, "pHYs");
+
+impl fmt::Display for PHYS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pHYs Physical pixel dimensions {{",)?;
+        writeln!(f, "  Pixels Per Unit X: {}", self.pixels_per_unit_x())?;
+        writeln!(f, "  Pixels Per Unit Y: {}", self.pixels_per_unit_y())?;
+        match self.unit_specifier() {
+            PHYS::UNIT_METER => writeln!(f, "  Unit: meter")?,
+            _ => writeln!(f, "  Unit: unknown")?
+        }
+        if let Some((x, y)) = self.dpi() {
+            writeln!(f, "  DPI: {:.2} x {:.2}", x, y)?;
+        }
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+/// Converts dots per inch into pixels per meter, rounding to the nearest pixel.
+fn dpi_to_ppm(dpi: f64) -> Result<u32, String> {
+    let ppm: f64 = (dpi / METERS_PER_INCH).round();
+
+    if !ppm.is_finite() || ppm < 1.0 || ppm > u32::MAX as f64 {
+        return Err(format!("{} DPI is not a valid resolution.", dpi));
+    }
+
+    Ok(ppm as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phys_round_trip() {
+        let phys = PHYS::new(3780, 3780, PHYS::UNIT_METER).unwrap();
+        let chunk = phys.to_chunk();
+
+        assert_eq!(chunk.data(), &[0, 0, 14, 196, 0, 0, 14, 196, 1]);
+        assert_eq!(PHYS::try_from(chunk).unwrap(), phys);
+    }
+
+    #[test]
+    fn test_phys_from_dpi() {
+        let phys = PHYS::from_dpi(300.0, 72.0).unwrap();
+        assert_eq!(phys.pixels_per_unit_x(), 11811);
+        assert_eq!(phys.pixels_per_unit_y(), 2835);
+
+        let (x, y) = phys.dpi().unwrap();
+        assert_eq!(x.round(), 300.0);
+        assert_eq!(y.round(), 72.0);
+    }
+
+    #[test]
+    fn test_phys_unknown_unit_has_no_dpi() {
+        let phys = PHYS::new(1, 2, PHYS::UNIT_UNKNOWN).unwrap();
+        assert!(phys.dpi().is_none());
+    }
+
+    #[test]
+    fn test_phys_invalid() {
+        assert!(PHYS::new(1, 1, 2).is_err());
+        assert!(PHYS::from_dpi(0.0, 300.0).is_err());
+        assert!(PHYS::from_dpi(f64::NAN, 300.0).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::png::chunk_specs::IHDR;
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// The number of significant bits in each channel of the original image,
+/// which may be lower than the sample depth stored in the PNG. See the PNG
+/// spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sBIT
+#[derive(Debug, PartialEq, Eq)]
+pub struct SBIT {
+    significant_bits: Vec<u8>
+}
+
+impl SBIT {
+    /// Creates a new `sBIT` holding one significant bit count per channel.
+    /// Depending on the color type, the channels are gray, RGB, gray and
+    /// alpha, or RGB and alpha.
+    pub fn new(significant_bits: Vec<u8>) -> Result<SBIT, String> {
+        if !(1..=4).contains(&significant_bits.len()) {
+            return Err(format!("sBIT must hold 1-4 channels, but holds {}.", significant_bits.len()));
+        }
+
+        if significant_bits.contains(&0) {
+            return Err("sBIT significant bits must be greater than zero.".into());
+        }
+
+        Ok(SBIT { significant_bits })
+    }
+
+    /// The number of significant bits in each channel.
+    pub fn significant_bits(&self) -> &[u8] {
+        &self.significant_bits
+    }
+
+    /// Checks that the number of channels matches the color type of the
+    /// image and that no channel exceeds the sample depth.
+    pub fn validate(&self, ihdr: &IHDR) -> Result<(), String> {
+        let (channels, sample_depth): (usize, u8) = match ihdr.color_type() {
+            0 => (1, ihdr.bit_depth()),
+            2 => (3, ihdr.bit_depth()),
+            3 => (3, 8),
+            4 => (2, ihdr.bit_depth()),
+            _ => (4, ihdr.bit_depth())
+        };
+
+        if self.significant_bits.len() != channels {
+            return Err(format!("sBIT holds {} channels, but color type {} requires {}.",
+                self.significant_bits.len(), ihdr.color_type(), channels));
+        }
+
+        if self.significant_bits.iter().any(|&bits| bits > sample_depth) {
+            return Err(format!("sBIT significant bits may not exceed the sample depth {}.", sample_depth));
+        }
+
+        Ok(())
+    }
+
+    /// Recovers the original sample of a channel from a sample scaled up to
+    /// `sample_depth` bits, by discarding the insignificant low-order bits.
+    pub fn original_sample(&self, channel: usize, sample: u16, sample_depth: u8) -> u16 {
+        let bits: u8 = self.significant_bits
+            .get(channel)
+            .copied()
+            .unwrap_or(sample_depth)
+            .min(sample_depth);
+
+        sample >> (sample_depth - bits)
+    }
+
+    /// Recovers the original samples of interleaved pixel data in place.
+    /// Samples are assumed to cycle through each channel of this `sBIT`.
+    pub fn original_samples(&self, samples: &mut [u16], sample_depth: u8) {
+        let channels: usize = self.significant_bits.len();
+
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            *sample = self.original_sample(idx % channels, *sample, sample_depth);
+        }
+    }
+
+    /// Serializes this into an `sBIT` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("sBIT").unwrap(), self.significant_bits.clone())
+    }
+}
+
+impl TryFrom<Chunk> for SBIT {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("sBIT")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'sBIT'".into());
+        }
+
+        SBIT::new(value.data().to_vec())
+    }
+}
+
+chunk_spec!(SBIT, "sBIT", fn validate_context(&self, png: &Png) {
+    match png.chunk_by_type("IHDR") {
+        Some(chunk) => SBIT::validate(self, &IHDR::try_from(chunk.clone())?),
+        None => Err("sBIT chunk requires an IHDR chunk.".into())
+    }
+});
+
+impl fmt::Display for SBIT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits: Vec<String> = self.significant_bits.iter().map(|b| b.to_string()).collect();
+
+        writeln!(f, "sBIT Significant bits {{",)?;
+        writeln!(f, "  Significant Bits: {}", bits.join(", "))?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_ihdr(bit_depth: u8, color_type: u8) -> IHDR {
+        let data: Vec<u8> = [0, 0, 0, 1, 0, 0, 0, 1, bit_depth, color_type, 0, 0, 0].to_vec();
+        IHDR::try_from(Chunk::new(ChunkType::from_str("IHDR").unwrap(), data)).unwrap()
+    }
+
+    #[test]
+    fn test_sbit_round_trip() {
+        let sbit = SBIT::new(vec![5, 6, 5]).unwrap();
+        assert_eq!(SBIT::try_from(sbit.to_chunk()).unwrap(), sbit);
+    }
+
+    #[test]
+    fn test_sbit_validate() {
+        let sbit = SBIT::new(vec![5, 6, 5]).unwrap();
+        assert!(sbit.validate(&testing_ihdr(8, 2)).is_ok());
+        assert!(sbit.validate(&testing_ihdr(8, 3)).is_ok());
+        assert!(sbit.validate(&testing_ihdr(8, 6)).is_err());
+
+        let sbit = SBIT::new(vec![12]).unwrap();
+        assert!(sbit.validate(&testing_ihdr(16, 0)).is_ok());
+        assert!(sbit.validate(&testing_ihdr(8, 0)).is_err());
+    }
+
+    #[test]
+    fn test_sbit_original_samples() {
+        let sbit = SBIT::new(vec![5, 6, 5]).unwrap();
+        let mut samples: Vec<u16> = vec![255, 255, 255, 0b1000_0100, 0b1000_0100, 0b1000_0100];
+        sbit.original_samples(&mut samples, 8);
+
+        assert_eq!(samples, vec![31, 63, 31, 0b10000, 0b100001, 0b10000]);
+    }
+
+    #[test]
+    fn test_sbit_invalid() {
+        assert!(SBIT::new(vec![]).is_err());
+        assert!(SBIT::new(vec![8, 8, 8, 8, 8]).is_err());
+        assert!(SBIT::new(vec![8, 0, 8]).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::png::chunk_specs::parse_ascii_float;
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// The physical size of the scene shown by each pixel, for images such as
+/// maps or scientific photographs. See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.sCAL
+#[derive(Debug, PartialEq, Eq)]
+pub struct SCAL {
+    unit_specifier: u8,
+    pixel_width: String,
+    pixel_height: String
+}
+
+impl SCAL {
+    /// The pixel size is measured in meters.
+    pub const UNIT_METER: u8 = 1;
+
+    /// The pixel size is measured in radians.
+    pub const UNIT_RADIAN: u8 = 2;
+
+    /// Creates a new `sCAL` after validating the unit specifier and checking
+    /// that the width and height are positive floating-point strings.
+    pub fn new(unit_specifier: u8, pixel_width: &str, pixel_height: &str) -> Result<SCAL, String> {
+        if unit_specifier != SCAL::UNIT_METER && unit_specifier != SCAL::UNIT_RADIAN {
+            return Err(format!("Invalid sCAL unit specifier {}.", unit_specifier));
+        }
+
+        for value in &[pixel_width, pixel_height] {
+            if parse_ascii_float(value)? <= 0.0 {
+                return Err(format!("sCAL pixel size '{}' must be positive.", value));
+            }
+        }
+
+        Ok(SCAL {
+            unit_specifier,
+            pixel_width: pixel_width.to_string(),
+            pixel_height: pixel_height.to_string()
+        })
+    }
+
+    /// The unit of the pixel size, either `UNIT_METER` or `UNIT_RADIAN`.
+    pub fn unit_specifier(&self) -> u8 {
+        self.unit_specifier
+    }
+
+    /// The width of each pixel as stored in the chunk.
+    pub fn pixel_width(&self) -> &str {
+        &self.pixel_width
+    }
+
+    /// The height of each pixel as stored in the chunk.
+    pub fn pixel_height(&self) -> &str {
+        &self.pixel_height
+    }
+
+    /// The width and height of each pixel.
+    pub fn pixel_size(&self) -> (f64, f64) {
+        (
+            parse_ascii_float(&self.pixel_width).unwrap_or(0.0),
+            parse_ascii_float(&self.pixel_height).unwrap_or(0.0)
+        )
+    }
+
+    /// Serializes this into an `sCAL` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data: Vec<u8> = vec![self.unit_specifier];
+        data.extend_from_slice(self.pixel_width.as_bytes());
+        data.push(0);
+        data.extend_from_slice(self.pixel_height.as_bytes());
+
+        Chunk::new(ChunkType::from_str("sCAL").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for SCAL {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("sCAL")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'sCAL'".into());
+        }
+
+        let data: &[u8] = value.data();
+        if data.is_empty() {
+            return Err("sCAL chunk is missing the unit specifier.".into());
+        }
+
+        let separator: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("sCAL chunk is missing the null separator after the pixel width.".into())
+        };
+
+        if separator == 0 {
+            return Err("sCAL chunk is missing the unit specifier.".into());
+        }
+
+        let pixel_width: &str = std::str::from_utf8(&data[1..separator])
+            .map_err(|_| "sCAL pixel width is not ASCII.".to_string())?;
+        let pixel_height: &str = std::str::from_utf8(&data[separator + 1..])
+            .map_err(|_| "sCAL pixel height is not ASCII.".to_string())?;
+
+        SCAL::new(data[0], pixel_width, pixel_height)
+    }
+}
+
+chunk_spec!(SCAL, "sCAL");
+
+impl fmt::Display for SCAL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit: &str = match self.unit_specifier() {
+            SCAL::UNIT_METER => "meters",
+            _ => "radians"
+        };
+
+        writeln!(f, "sCAL Physical scale {{",)?;
+        writeln!(f, "  Pixel Width: {} {}", self.pixel_width(), unit)?;
+        writeln!(f, "  Pixel Height: {} {}", self.pixel_height(), unit)?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scal_round_trip() {
+        let scal = SCAL::new(SCAL::UNIT_METER, "0.5", "2.5e-1").unwrap();
+        let chunk = scal.to_chunk();
+
+        assert_eq!(chunk.data(), b"\x010.5\x002.5e-1");
+        assert_eq!(scal.pixel_size(), (0.5, 0.25));
+        assert_eq!(SCAL::try_from(chunk).unwrap(), scal);
+    }
+
+    #[test]
+    fn test_scal_invalid() {
+        assert!(SCAL::new(0, "1", "1").is_err());
+        assert!(SCAL::new(SCAL::UNIT_RADIAN, "0", "1").is_err());
+        assert!(SCAL::new(SCAL::UNIT_RADIAN, "1", "-1").is_err());
+        assert!(SCAL::new(SCAL::UNIT_RADIAN, "1", "x").is_err());
+    }
+}
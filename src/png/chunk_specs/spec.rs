@@ -0,0 +1,222 @@
+use crate::png::chunk_specs::*;
+use crate::png::{Chunk, ChunkType, Png};
+use std::any::Any;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::fmt;
+
+/// A typed view of a chunk with a known layout. Implementing this for a
+/// private chunk format and registering it with a `ChunkRegistry` makes the
+/// chunk available through the same APIs as the chunks defined by the PNG spec.
+pub trait ChunkSpec: fmt::Display + Sized + 'static {
+    /// The four-letter type of the chunks described by this spec, such as `tIME`.
+    const CHUNK_TYPE: &'static str;
+
+    /// Parses the data of a chunk, checking everything that can be checked
+    /// without looking at the rest of the file.
+    fn parse(chunk: &Chunk) -> Result<Self, String>;
+
+    /// Serializes this back into a chunk.
+    fn to_chunk(&self) -> Chunk;
+
+    /// Checks this against the rest of the file, such as the `IHDR` or
+    /// `PLTE` chunks it depends on. Most chunks have no such requirements.
+    fn validate_context(&self, _png: &Png) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// An object-safe view of a chunk parsed by a `ChunkRegistry`. Every
+/// `ChunkSpec` is a `ParsedChunk`, and can be recovered with `downcast_ref`.
+pub trait ParsedChunk: fmt::Display {
+    /// The parsed chunk as `Any`, so that it can be downcast to its spec.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Serializes the parsed chunk back into a chunk.
+    fn serialize(&self) -> Chunk;
+
+    /// Checks the parsed chunk against the rest of the file.
+    fn validate_against(&self, png: &Png) -> Result<(), String>;
+}
+
+impl<T: ChunkSpec> ParsedChunk for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn serialize(&self) -> Chunk {
+        ChunkSpec::to_chunk(self)
+    }
+
+    fn validate_against(&self, png: &Png) -> Result<(), String> {
+        ChunkSpec::validate_context(self, png)
+    }
+}
+
+impl dyn ParsedChunk {
+    /// Returns the parsed chunk as `T` if it was parsed by that spec.
+    pub fn downcast_ref<T: ChunkSpec>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+}
+
+type Parser = fn(&Chunk) -> Result<Box<dyn ParsedChunk>, String>;
+
+fn parse_boxed<T: ChunkSpec>(chunk: &Chunk) -> Result<Box<dyn ParsedChunk>, String> {
+    Ok(Box::new(T::parse(chunk)?))
+}
+
+/// Maps chunk types to the specs used to parse them.
+pub struct ChunkRegistry {
+    parsers: HashMap<ChunkType, Parser>
+}
+
+impl ChunkRegistry {
+    /// Creates a registry without any specs.
+    pub fn new() -> ChunkRegistry {
+        ChunkRegistry { parsers: HashMap::new() }
+    }
+
+    /// Creates a registry holding every spec implemented by this crate.
+    pub fn standard() -> ChunkRegistry {
+        let mut registry = ChunkRegistry::new();
+
+        registry.register::<IHDR>();
+        registry.register::<BKGD>();
+        registry.register::<CABX>();
+        registry.register::<CHRM>();
+        registry.register::<CICP>();
+        registry.register::<CLLI>();
+        registry.register::<EXIF>();
+        registry.register::<GAMA>();
+        registry.register::<GIFG>();
+        registry.register::<GIFX>();
+        registry.register::<HIST>();
+        registry.register::<ICCP>();
+        registry.register::<ITXT>();
+        registry.register::<MDCV>();
+        registry.register::<OFFS>();
+        registry.register::<PCAL>();
+        registry.register::<PHYS>();
+        registry.register::<SBIT>();
+        registry.register::<SCAL>();
+        registry.register::<SPLT>();
+        registry.register::<SRGB>();
+        registry.register::<STER>();
+        registry.register::<TEXT>();
+        registry.register::<TIME>();
+        registry.register::<ZTXT>();
+
+        registry
+    }
+
+    /// The standard registry, built on first use and shared afterwards.
+    pub(crate) fn shared() -> &'static ChunkRegistry {
+        static STANDARD: OnceLock<ChunkRegistry> = OnceLock::new();
+        STANDARD.get_or_init(ChunkRegistry::standard)
+    }
+
+    /// Registers `T` as the spec for its chunk type, replacing any spec
+    /// previously registered for that type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T::CHUNK_TYPE` is not a valid chunk type.
+    pub fn register<T: ChunkSpec>(&mut self) {
+        let chunk_type: ChunkType = ChunkType::from_str(T::CHUNK_TYPE)
+            .unwrap_or_else(|e| panic!("Invalid chunk spec type '{}': {}", T::CHUNK_TYPE, e));
+
+        self.parsers.insert(chunk_type, parse_boxed::<T>);
+    }
+
+    /// Whether a spec is registered for `chunk_type`.
+    pub fn is_registered(&self, chunk_type: &ChunkType) -> bool {
+        self.parsers.contains_key(chunk_type)
+    }
+
+    /// Parses a chunk with the spec registered for its type. Returns `None`
+    /// if no spec is registered.
+    pub fn parse(&self, chunk: &Chunk) -> Option<Result<Box<dyn ParsedChunk>, String>> {
+        self.parsers
+            .get(chunk.chunk_type())
+            .map(|parser| parser(chunk))
+    }
+
+    /// Decodes a chunk into a human-readable description. Returns `None` for
+    /// unregistered or malformed chunks.
+    pub fn describe(&self, chunk: &Chunk) -> Option<String> {
+        self.parse(chunk)?.ok().map(|parsed| parsed.to_string())
+    }
+}
+
+impl Default for ChunkRegistry {
+    fn default() -> ChunkRegistry {
+        ChunkRegistry::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A private chunk holding a single counter, as a user might define.
+    struct Counter(u8);
+
+    impl ChunkSpec for Counter {
+        const CHUNK_TYPE: &'static str = "cnTr";
+
+        fn parse(chunk: &Chunk) -> Result<Counter, String> {
+            match chunk.data() {
+                [count] => Ok(Counter(*count)),
+                _ => Err("Counter chunks hold a single byte.".into())
+            }
+        }
+
+        fn to_chunk(&self) -> Chunk {
+            Chunk::new(ChunkType::from_str(Counter::CHUNK_TYPE).unwrap(), vec![self.0])
+        }
+
+        fn validate_context(&self, png: &Png) -> Result<(), String> {
+            match png.chunk_by_type("IHDR") {
+                Some(_) => Ok(()),
+                None => Err("Counter chunks require an IHDR chunk.".into())
+            }
+        }
+    }
+
+    impl fmt::Display for Counter {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Counter {}", self.0)
+        }
+    }
+
+    #[test]
+    fn test_registry_user_defined_spec() {
+        let chunk = Counter(7).to_chunk();
+        let mut registry = ChunkRegistry::standard();
+        assert!(registry.parse(&chunk).is_none());
+
+        registry.register::<Counter>();
+        let parsed = registry.parse(&chunk).unwrap().unwrap();
+
+        assert_eq!(parsed.downcast_ref::<Counter>().unwrap().0, 7);
+        assert!(parsed.downcast_ref::<TIME>().is_none());
+        assert_eq!(parsed.serialize().data(), &[7]);
+        assert_eq!(registry.describe(&chunk).unwrap(), "Counter 7");
+        assert!(parsed.validate_against(&Png::from_chunks(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_registry_standard_specs() {
+        let registry = ChunkRegistry::standard();
+        let chunk = TIME::new(2020, 1, 2, 3, 4, 5).unwrap().to_chunk();
+
+        assert!(registry.is_registered(chunk.chunk_type()));
+        assert!(registry.parse(&chunk).unwrap().unwrap().downcast_ref::<TIME>().is_some());
+        assert!(ChunkRegistry::new().describe(&chunk).is_none());
+
+        let malformed = Chunk::new(ChunkType::from_str("tIME").unwrap(), vec![0]);
+        assert!(registry.parse(&malformed).unwrap().is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::png::chunk_specs::{decode_latin1, encode_latin1, validate_keyword};
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use std::fmt;
+
+/// A named suggested palette, which viewers with limited colors may use to
+/// quantize the image. See the PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sPLT
+#[derive(Debug, PartialEq, Eq)]
+pub struct SPLT {
+    palette_name: String,
+    sample_depth: u8,
+    entries: Vec<SuggestedPaletteEntry>
+}
+
+/// A single color in a suggested palette. Each sample is stored at the
+/// sample depth of the palette (8 or 16 bits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuggestedPaletteEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16
+}
+
+impl SPLT {
+    /// Creates a new `sPLT` after validating the palette name and checking
+    /// that every sample fits within the sample depth.
+    pub fn new(palette_name: &str, sample_depth: u8, entries: Vec<SuggestedPaletteEntry>)
+            -> Result<SPLT, String> {
+        validate_keyword(palette_name)?;
+
+        let max_sample: u16 = match sample_depth {
+            8 => u8::MAX as u16,
+            16 => u16::MAX,
+            _ => return Err(format!("sPLT sample depth {} must be 8 or 16.", sample_depth))
+        };
+
+        let fits: bool = entries
+            .iter()
+            .all(|e| [e.red, e.green, e.blue, e.alpha].iter().all(|&s| s <= max_sample));
+
+        if !fits {
+            return Err(format!("sPLT samples must fit within the sample depth {}.", sample_depth));
+        }
+
+        Ok(SPLT {
+            palette_name: palette_name.to_string(),
+            sample_depth,
+            entries
+        })
+    }
+
+    /// The name used to refer to the palette.
+    pub fn palette_name(&self) -> &str {
+        &self.palette_name
+    }
+
+    /// The sample depth of the palette, either 8 or 16.
+    pub fn sample_depth(&self) -> u8 {
+        self.sample_depth
+    }
+
+    /// The colors of the palette.
+    pub fn entries(&self) -> &[SuggestedPaletteEntry] {
+        &self.entries
+    }
+
+    /// Serializes this palette into an `sPLT` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let entries: Vec<u8> = self.entries
+            .iter()
+            .flat_map(|e| {
+                let samples: Vec<u8> = [e.red, e.green, e.blue, e.alpha]
+                    .iter()
+                    .flat_map(|&s| match self.sample_depth {
+                        8 => vec![s as u8],
+                        _ => s.to_be_bytes().to_vec()
+                    })
+                    .collect();
+
+                samples.into_iter().chain(e.frequency.to_be_bytes())
+            })
+            .collect();
+
+        let data: Vec<u8> = encode_latin1(&self.palette_name)
+            .into_iter()
+            .chain([0, self.sample_depth])
+            .chain(entries)
+            .collect();
+
+        Chunk::new(ChunkType::from_str("sPLT").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for SPLT {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("sPLT")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'sPLT'".into());
+        }
+
+        let data: &[u8] = value.data();
+        let separator: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("sPLT chunk is missing the null separator after the palette name.".into())
+        };
+
+        let sample_depth: u8 = match data.get(separator + 1) {
+            Some(&depth) => depth,
+            None => return Err("sPLT chunk is missing the sample depth.".into())
+        };
+
+        let entry_size: usize = match sample_depth {
+            8 => 6,
+            16 => 10,
+            _ => return Err(format!("sPLT sample depth {} must be 8 or 16.", sample_depth))
+        };
+
+        let entries: &[u8] = &data[separator + 2..];
+        if !entries.len().is_multiple_of(entry_size) {
+            return Err(format!("sPLT entries of {} bytes are not a multiple of the {} byte entry size.",
+                entries.len(), entry_size));
+        }
+
+        let entries: Vec<SuggestedPaletteEntry> = entries
+            .chunks_exact(entry_size)
+            .map(|e| {
+                let sample = |idx: usize| match sample_depth {
+                    8 => e[idx] as u16,
+                    _ => u16::from_be_bytes(e[idx * 2..idx * 2 + 2].try_into().unwrap())
+                };
+
+                SuggestedPaletteEntry {
+                    red: sample(0),
+                    green: sample(1),
+                    blue: sample(2),
+                    alpha: sample(3),
+                    frequency: u16::from_be_bytes(e[entry_size - 2..].try_into().unwrap())
+                }
+            })
+            .collect();
+
+        SPLT::new(&decode_latin1(&data[..separator]), sample_depth, entries)
+    }
+}
+
+chunk_spec!(SPLT, "sPLT");
+
+impl fmt::Display for SPLT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "sPLT Suggested palette {{",)?;
+        writeln!(f, "  Palette Name: {}", self.palette_name())?;
+        writeln!(f, "  Sample Depth: {}", self.sample_depth())?;
+        writeln!(f, "  Entries: {}", self.entries().len())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(red: u16, green: u16, blue: u16, alpha: u16, frequency: u16) -> SuggestedPaletteEntry {
+        SuggestedPaletteEntry { red, green, blue, alpha, frequency }
+    }
+
+    #[test]
+    fn test_splt_8_bit_round_trip() {
+        let splt = SPLT::new("web safe", 8, vec![entry(255, 0, 51, 255, 10), entry(0, 0, 0, 0, 0)]).unwrap();
+        let chunk = splt.to_chunk();
+
+        assert_eq!(chunk.length(), 8 + 2 + 12);
+        assert_eq!(SPLT::try_from(chunk).unwrap(), splt);
+    }
+
+    #[test]
+    fn test_splt_16_bit_round_trip() {
+        let splt = SPLT::new("deep", 16, vec![entry(65535, 256, 1, 65535, 7)]).unwrap();
+        let chunk = splt.to_chunk();
+
+        assert_eq!(chunk.length(), 4 + 2 + 10);
+        assert_eq!(SPLT::try_from(chunk).unwrap(), splt);
+    }
+
+    #[test]
+    fn test_splt_invalid() {
+        assert!(SPLT::new("palette", 4, vec![]).is_err());
+        assert!(SPLT::new("palette", 8, vec![entry(256, 0, 0, 0, 0)]).is_err());
+
+        let chunk = Chunk::new(ChunkType::from_str("sPLT").unwrap(), b"palette\0\x08\0\0\0".to_vec());
+        assert!(SPLT::try_from(chunk).is_err());
+    }
+}
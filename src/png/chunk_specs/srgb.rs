@@ -0,0 +1,100 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// Marks the image as being in the sRGB color space, with the rendering
+/// intent to use when mapping it to other color spaces. See the PNG spec for
+/// more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.sRGB
+#[derive(Debug, PartialEq, Eq)]
+pub struct SRGB {
+    rendering_intent: u8
+}
+
+impl SRGB {
+    /// Used for images preferring good adaptation to the output device gamut.
+    pub const INTENT_PERCEPTUAL: u8 = 0;
+
+    /// Used for images requiring color appearance matching.
+    pub const INTENT_RELATIVE_COLORIMETRIC: u8 = 1;
+
+    /// Used for images preferring preservation of saturation.
+    pub const INTENT_SATURATION: u8 = 2;
+
+    /// Used for images requiring preservation of absolute colorimetry.
+    pub const INTENT_ABSOLUTE_COLORIMETRIC: u8 = 3;
+
+    /// Creates a new `sRGB` after validating the rendering intent.
+    pub fn new(rendering_intent: u8) -> Result<SRGB, String> {
+        if rendering_intent > SRGB::INTENT_ABSOLUTE_COLORIMETRIC {
+            return Err(format!("Invalid sRGB rendering intent {}.", rendering_intent));
+        }
+
+        Ok(SRGB { rendering_intent })
+    }
+
+    /// The rendering intent, one of the `INTENT_*` constants.
+    pub fn rendering_intent(&self) -> u8 {
+        self.rendering_intent
+    }
+
+    /// Serializes this into an `sRGB` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("sRGB").unwrap(), vec![self.rendering_intent])
+    }
+}
+
+impl TryFrom<Chunk> for SRGB {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("sRGB")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'sRGB'".into());
+        }
+
+        if value.length() != 1 {
+            return Err(format!("Chunk length {} does not match expected length 1.", value.length()));
+        }
+
+        SRGB::new(value.data()[0])
+    }
+}
+
+chunk_spec!(SRGB, "sRGB", fn validate_context(&self, png: &Png) {
+    match png.chunk_by_type("iCCP") {
+        Some(_) => Err("sRGB and iCCP chunks must not both be present.".into()),
+        None => Ok(())
+    }
+});
+
+impl fmt::Display for SRGB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let intent: &str = match self.rendering_intent() {
+            SRGB::INTENT_PERCEPTUAL => "perceptual",
+            SRGB::INTENT_RELATIVE_COLORIMETRIC => "relative colorimetric",
+            SRGB::INTENT_SATURATION => "saturation",
+            _ => "absolute colorimetric"
+        };
+
+        writeln!(f, "sRGB Standard RGB color space {{",)?;
+        writeln!(f, "  Rendering Intent: {}", intent)?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_round_trip() {
+        let srgb = SRGB::new(SRGB::INTENT_PERCEPTUAL).unwrap();
+        assert_eq!(SRGB::try_from(srgb.to_chunk()).unwrap(), srgb);
+        assert!(SRGB::new(4).is_err());
+    }
+}
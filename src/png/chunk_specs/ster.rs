@@ -0,0 +1,115 @@
+use crate::png::chunk_specs::IHDR;
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// Indicates that the image holds a stereo pair of subimages side by side.
+/// See the PNG extensions spec for more details.
+/// http://ftp-osl.osuosl.org/pub/libpng/documents/pngext-1.5.0.html#C.sTER
+#[derive(Debug, PartialEq, Eq)]
+pub struct STER {
+    mode: u8
+}
+
+impl STER {
+    /// The right-eye image is on the left (cross-fuse layout).
+    pub const MODE_CROSS_FUSE: u8 = 0;
+
+    /// The left-eye image is on the left (diverging-fuse layout).
+    pub const MODE_DIVERGING_FUSE: u8 = 1;
+
+    /// Creates a new `sTER` after validating the layout mode.
+    pub fn new(mode: u8) -> Result<STER, String> {
+        if mode > STER::MODE_DIVERGING_FUSE {
+            return Err(format!("Invalid sTER mode {}.", mode));
+        }
+
+        Ok(STER { mode })
+    }
+
+    /// The layout mode, either `MODE_CROSS_FUSE` or `MODE_DIVERGING_FUSE`.
+    pub fn mode(&self) -> u8 {
+        self.mode
+    }
+
+    /// The width of each subimage given the width of the whole image. The
+    /// right subimage starts on a column divisible by eight, so 0-7 padding
+    /// columns separate the subimages. Returns `None` if no subimage width
+    /// produces the given image width.
+    pub fn subimage_width(image_width: u32) -> Option<u32> {
+        let min_width: u32 = (image_width.saturating_sub(7) / 2).max(1);
+
+        (min_width..=image_width / 2).find(|&w| 2 * w + 7 - (w - 1) % 8 == image_width)
+    }
+
+    /// Serializes this into an `sTER` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("sTER").unwrap(), vec![self.mode])
+    }
+}
+
+impl TryFrom<Chunk> for STER {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("sTER")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'sTER'".into());
+        }
+
+        if value.length() != 1 {
+            return Err(format!("Chunk length {} does not match expected length 1.", value.length()));
+        }
+
+        STER::new(value.data()[0])
+    }
+}
+
+chunk_spec!(STER, "sTER", fn validate_context(&self, png: &Png) {
+    let ihdr: IHDR = match png.chunk_by_type("IHDR") {
+        Some(chunk) => IHDR::try_from(chunk.clone())?,
+        None => return Err("sTER chunk requires an IHDR chunk.".into())
+    };
+
+    match STER::subimage_width(ihdr.width()) {
+        Some(_) => Ok(()),
+        None => Err(format!("Image width {} cannot hold a stereo pair.", ihdr.width()))
+    }
+});
+
+impl fmt::Display for STER {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode: &str = match self.mode() {
+            STER::MODE_CROSS_FUSE => "cross-fuse",
+            _ => "diverging-fuse"
+        };
+
+        writeln!(f, "sTER Stereo image indicator {{",)?;
+        writeln!(f, "  Mode: {} ({})", self.mode(), mode)?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ster_round_trip() {
+        let ster = STER::new(STER::MODE_DIVERGING_FUSE).unwrap();
+        assert_eq!(STER::try_from(ster.to_chunk()).unwrap(), ster);
+        assert!(STER::new(2).is_err());
+    }
+
+    #[test]
+    fn test_ster_subimage_width() {
+        assert_eq!(STER::subimage_width(16), Some(8));
+        assert_eq!(STER::subimage_width(13), Some(5));
+        assert_eq!(STER::subimage_width(17), None);
+        assert_eq!(STER::subimage_width(1), None);
+    }
+}
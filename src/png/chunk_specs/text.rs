@@ -0,0 +1,179 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// The maximum length of a keyword in bytes, as described in the PNG spec.
+pub const MAX_KEYWORD_LENGTH: usize = 79;
+
+/// A textual data chunk. Each `tEXt` chunk holds a single keyword and its
+/// associated text, both encoded as Latin-1 (ISO 8859-1). See the PNG spec
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.tEXt
+pub struct TEXT {
+    keyword: String,
+    text: String
+}
+
+impl TEXT {
+    /// Creates a new `tEXt` entry after validating the keyword and text.
+    pub fn new(keyword: &str, text: &str) -> Result<TEXT, String> {
+        validate_keyword(keyword)?;
+        validate_latin1_text(text)?;
+
+        Ok(TEXT {
+            keyword: keyword.to_string(),
+            text: text.to_string()
+        })
+    }
+
+    /// The keyword that indicates what this text represents.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// The text associated with the keyword.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Serializes this entry into a `tEXt` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = encode_latin1(&self.keyword)
+            .into_iter()
+            .chain(std::iter::once(0))
+            .chain(encode_latin1(&self.text))
+            .collect();
+
+        Chunk::new(ChunkType::from_str("tEXt").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for TEXT {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("tEXt")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'tEXt'".into());
+        }
+
+        let separator: usize = match value.data().iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("tEXt chunk is missing the null separator after the keyword.".into())
+        };
+
+        let keyword: String = decode_latin1(&value.data()[..separator]);
+        let text: String = decode_latin1(&value.data()[separator + 1..]);
+
+        TEXT::new(&keyword, &text)
+    }
+}
+
+chunk_spec!(TEXT, "tEXt");
+
+impl fmt::Display for TEXT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tEXt Textual data {{",)?;
+        writeln!(f, "  Keyword: {}", self.keyword())?;
+        writeln!(f, "  Text: {}", self.text())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+/// Checks that a keyword is 1-79 bytes of printable Latin-1 characters with
+/// no leading, trailing, or consecutive spaces.
+pub fn validate_keyword(keyword: &str) -> Result<(), String> {
+    let length: usize = keyword.chars().count();
+
+    if length == 0 || length > MAX_KEYWORD_LENGTH {
+        return Err(format!("Keyword length {} is not within the range 1-{}.", length, MAX_KEYWORD_LENGTH));
+    }
+
+    if !keyword.chars().all(|c| matches!(c as u32, 32..=126 | 161..=255)) {
+        return Err("Keywords may only contain printable Latin-1 characters.".into());
+    }
+
+    if keyword.starts_with(' ') || keyword.ends_with(' ') {
+        return Err("Keywords may not contain leading or trailing spaces.".into());
+    }
+
+    if keyword.contains("  ") {
+        return Err("Keywords may not contain consecutive spaces.".into());
+    }
+
+    Ok(())
+}
+
+/// Checks that text can be encoded as Latin-1 and contains no null bytes.
+pub fn validate_latin1_text(text: &str) -> Result<(), String> {
+    if text.chars().any(|c| c as u32 > 255) {
+        return Err("Text may only contain Latin-1 characters.".into());
+    }
+
+    if text.contains('\0') {
+        return Err("Text may not contain null characters.".into());
+    }
+
+    Ok(())
+}
+
+/// Decodes Latin-1 bytes. Every byte maps directly to the unicode code point
+/// of the same value, so this can never fail.
+pub fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Encodes a string as Latin-1. Characters outside of Latin-1 should be
+/// rejected by validation before this is called.
+pub fn encode_latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| c as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        let text = TEXT::new("Title", "Dice").unwrap();
+        let parsed = TEXT::try_from(text.to_chunk()).unwrap();
+
+        assert_eq!(parsed.keyword(), "Title");
+        assert_eq!(parsed.text(), "Dice");
+    }
+
+    #[test]
+    fn test_text_latin1() {
+        let text = TEXT::new("Author", "Ren\u{e9}e").unwrap();
+        let chunk = text.to_chunk();
+
+        assert_eq!(chunk.data(), b"Author\0Ren\xe9e");
+        assert_eq!(TEXT::try_from(chunk).unwrap().text(), "Ren\u{e9}e");
+    }
+
+    #[test]
+    fn test_invalid_keywords() {
+        assert!(TEXT::new("", "text").is_err());
+        assert!(TEXT::new(&"a".repeat(80), "text").is_err());
+        assert!(TEXT::new(" Title", "text").is_err());
+        assert!(TEXT::new("Title ", "text").is_err());
+        assert!(TEXT::new("Creation  Time", "text").is_err());
+        assert!(TEXT::new("Tab\tbed", "text").is_err());
+        assert!(TEXT::new("Creation Time", "text").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_text() {
+        assert!(TEXT::new("Title", "\u{263a}").is_err());
+        assert!(TEXT::new("Title", "a\0b").is_err());
+    }
+
+    #[test]
+    fn test_missing_separator() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"Title".to_vec());
+        assert!(TEXT::try_from(chunk).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::png::{Chunk, ChunkType};
+use std::convert::{TryFrom, TryInto};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::str::FromStr;
+use std::fmt;
+
+/// The image last-modification time, always given in UTC. See the PNG spec
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.tIME
+#[derive(Debug, PartialEq, Eq)]
+pub struct TIME {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8
+}
+
+impl TIME {
+    /// Creates a new `tIME` after checking that each field is within range.
+    /// A `second` of 60 is permitted to allow for leap seconds.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8)
+            -> Result<TIME, String> {
+        if !(1..=12).contains(&month) {
+            return Err(format!("Month {} is not within the range 1-12.", month));
+        }
+
+        let days: u8 = days_in_month(year, month);
+        if !(1..=days).contains(&day) {
+            return Err(format!("Day {} is not within the range 1-{}.", day, days));
+        }
+
+        if hour > 23 {
+            return Err(format!("Hour {} is not within the range 0-23.", hour));
+        }
+
+        if minute > 59 {
+            return Err(format!("Minute {} is not within the range 0-59.", minute));
+        }
+
+        if second > 60 {
+            return Err(format!("Second {} is not within the range 0-60.", second));
+        }
+
+        Ok(TIME { year, month, day, hour, minute, second })
+    }
+
+    /// Creates a `tIME` holding the current UTC time.
+    pub fn now() -> TIME {
+        let seconds: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        TIME::from_unix_timestamp(seconds)
+    }
+
+    /// Creates a `tIME` from the number of seconds since the Unix epoch.
+    pub fn from_unix_timestamp(seconds: u64) -> TIME {
+        let days: i64 = (seconds / 86400) as i64;
+        let seconds_of_day: u64 = seconds % 86400;
+
+        // civil date from days since 1970-01-01 in the proleptic Gregorian
+        // calendar, counted in 400 year eras starting on March 1st
+        let z: i64 = days + 719468;
+        let era: i64 = z.div_euclid(146097);
+        let day_of_era: i64 = z.rem_euclid(146097);
+        let year_of_era: i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+            - day_of_era / 146096) / 365;
+        let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index: i64 = (5 * day_of_year + 2) / 153;
+        let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month: i64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year: i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        TIME {
+            year: year.clamp(0, u16::MAX as i64) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds_of_day / 3600) as u8,
+            minute: (seconds_of_day % 3600 / 60) as u8,
+            second: (seconds_of_day % 60) as u8
+        }
+    }
+
+    /// The complete year, such as 1995.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month of the year (1-12).
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month (1-31).
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour of the day (0-23).
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute of the hour (0-59).
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second of the minute (0-60, to allow for leap seconds).
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// Serializes this time into a `tIME` chunk.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = self.year
+            .to_be_bytes()
+            .iter()
+            .chain([self.month, self.day, self.hour, self.minute, self.second].iter())
+            .copied()
+            .collect();
+
+        Chunk::new(ChunkType::from_str("tIME").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for TIME {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("tIME")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'tIME'".into());
+        }
+
+        if value.length() != 7 {
+            return Err(format!("Chunk length {} does not match expected length 7.", value.length()));
+        }
+
+        let data: &[u8] = value.data();
+        TIME::new(
+            u16::from_be_bytes(data[0..2].try_into().unwrap()),
+            data[2],
+            data[3],
+            data[4],
+            data[5],
+            data[6]
+        )
+    }
+}
+
+chunk_spec!(TIME, "tIME");
+
+impl fmt::Display for TIME {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tIME Image last-modification time {{",)?;
+        writeln!(f, "  Time: {:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year(), self.month(), self.day(), self.hour(), self.minute(), self.second())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let leap_year: bool = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+
+    match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_round_trip() {
+        let time = TIME::new(2021, 7, 1, 22, 59, 14).unwrap();
+        let chunk = time.to_chunk();
+
+        assert_eq!(chunk.data(), &[7, 229, 7, 1, 22, 59, 14]);
+        assert_eq!(TIME::try_from(chunk).unwrap(), time);
+    }
+
+    #[test]
+    fn test_time_ranges() {
+        assert!(TIME::new(2021, 0, 1, 0, 0, 0).is_err());
+        assert!(TIME::new(2021, 13, 1, 0, 0, 0).is_err());
+        assert!(TIME::new(2021, 4, 31, 0, 0, 0).is_err());
+        assert!(TIME::new(2021, 2, 29, 0, 0, 0).is_err());
+        assert!(TIME::new(2020, 2, 29, 0, 0, 0).is_ok());
+        assert!(TIME::new(2021, 1, 1, 24, 0, 0).is_err());
+        assert!(TIME::new(2021, 1, 1, 0, 60, 0).is_err());
+        assert!(TIME::new(2016, 12, 31, 23, 59, 60).is_ok());
+        assert!(TIME::new(2021, 1, 1, 0, 0, 61).is_err());
+    }
+
+    #[test]
+    fn test_time_from_unix_timestamp() {
+        assert_eq!(TIME::from_unix_timestamp(0), TIME::new(1970, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(TIME::from_unix_timestamp(951782400), TIME::new(2000, 2, 29, 0, 0, 0).unwrap());
+        assert_eq!(TIME::from_unix_timestamp(1625180354), TIME::new(2021, 7, 1, 22, 59, 14).unwrap());
+    }
+
+    #[test]
+    fn test_time_invalid_length() {
+        let chunk = Chunk::new(ChunkType::from_str("tIME").unwrap(), vec![7, 229, 7, 1]);
+        assert!(TIME::try_from(chunk).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::png::chunk_specs::{decode_latin1, encode_latin1, validate_keyword, validate_latin1_text};
+use crate::png::{zlib, Chunk, ChunkType};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::fmt;
+
+/// A compressed textual data chunk. A `zTXt` chunk holds a Latin-1 keyword
+/// followed by zlib-compressed Latin-1 text. The text is decompressed when
+/// the chunk is parsed. See the PNG spec for more details.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html#C.zTXt
+pub struct ZTXT {
+    keyword: String,
+    text: String
+}
+
+impl ZTXT {
+    /// The only compression method defined by the PNG spec (zlib deflate).
+    pub const COMPRESSION_METHOD: u8 = 0;
+
+    /// Creates a new `zTXt` entry after validating the keyword and text.
+    pub fn new(keyword: &str, text: &str) -> Result<ZTXT, String> {
+        validate_keyword(keyword)?;
+        validate_latin1_text(text)?;
+
+        Ok(ZTXT {
+            keyword: keyword.to_string(),
+            text: text.to_string()
+        })
+    }
+
+    /// The keyword that indicates what this text represents.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// The decompressed text associated with the keyword.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Serializes this entry into a `zTXt` chunk, compressing the text.
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = encode_latin1(&self.keyword)
+            .into_iter()
+            .chain([0, ZTXT::COMPRESSION_METHOD])
+            .chain(zlib::deflate(&encode_latin1(&self.text)))
+            .collect();
+
+        Chunk::new(ChunkType::from_str("zTXt").unwrap(), data)
+    }
+}
+
+impl TryFrom<Chunk> for ZTXT {
+    type Error = String;
+
+    fn try_from(value: Chunk) -> Result<Self, Self::Error> {
+        let expected_type: ChunkType = ChunkType::from_str("zTXt")?;
+
+        if &expected_type != value.chunk_type() {
+            return Err("Chunk type does not match expected chunk type 'zTXt'".into());
+        }
+
+        let data: &[u8] = value.data();
+        let separator: usize = match data.iter().position(|&b| b == 0) {
+            Some(idx) => idx,
+            None => return Err("zTXt chunk is missing the null separator after the keyword.".into())
+        };
+
+        match data.get(separator + 1) {
+            Some(&ZTXT::COMPRESSION_METHOD) => (),
+            Some(method) => return Err(format!("Unknown zTXt compression method {}.", method)),
+            None => return Err("zTXt chunk is missing the compression method.".into())
+        }
+
+        let keyword: String = decode_latin1(&data[..separator]);
+        let text: String = decode_latin1(&zlib::inflate_with_limit(&data[separator + 2..], zlib::MAX_METADATA_SIZE)?);
+
+        ZTXT::new(&keyword, &text)
+    }
+}
+
+chunk_spec!(ZTXT, "zTXt");
+
+impl fmt::Display for ZTXT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "zTXt Compressed textual data {{",)?;
+        writeln!(f, "  Keyword: {}", self.keyword())?;
+        writeln!(f, "  Text: {}", self.text())?;
+        writeln!(f, "}}",)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ztxt_round_trip() {
+        let license: String = "Permission is hereby granted, free of charge. ".repeat(20);
+        let ztxt = ZTXT::new("Copyright", &license).unwrap();
+        let chunk = ztxt.to_chunk();
+
+        assert!((chunk.length() as usize) < license.len());
+
+        let parsed = ZTXT::try_from(chunk).unwrap();
+        assert_eq!(parsed.keyword(), "Copyright");
+        assert_eq!(parsed.text(), license);
+    }
+
+    #[test]
+    fn test_ztxt_unknown_compression_method() {
+        let data: Vec<u8> = b"Title\0\x01"
+            .iter()
+            .copied()
+            .chain(zlib::deflate(b"Dice"))
+            .collect();
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), data);
+
+        assert!(ZTXT::try_from(chunk).is_err());
+    }
+
+    #[test]
+    fn test_ztxt_decompression_limit() {
+        let data: Vec<u8> = b"Bomb\0\0"
+            .iter()
+            .copied()
+            .chain(zlib::deflate(&vec![b'a'; zlib::MAX_METADATA_SIZE + 1]))
+            .collect();
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), data);
+
+        assert!(matches!(ZTXT::try_from(chunk), Err(e) if e.contains("exceeds")));
+    }
+
+    #[test]
+    fn test_ztxt_corrupt_datastream() {
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), b"Title\0\0garbage".to_vec());
+        assert!(ZTXT::try_from(chunk).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::png::error::PngError;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use std::{fmt, str};
@@ -6,7 +7,7 @@ use std::{fmt, str};
 /// http://www.libpng.org/pub/png/spec/1.2/PNG-Structure.html
 /// 
 /// Little Endian Implementation: 0th (first) byte and/or bit at index 0.
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Hash, Debug, Clone)]
 pub struct ChunkType {
     type_code: [u8; 4]
 }
@@ -22,7 +23,7 @@ impl ChunkType {
     /// should always be valid as it is validated during construction.
     pub fn is_valid(&self) -> bool {
         for b in &self.type_code {
-            if Self::is_valid_byte(b) {
+            if !Self::is_valid_byte(b) {
                 return false;
             }
         }
@@ -57,36 +58,29 @@ impl ChunkType {
 }
 
 impl FromStr for ChunkType {
-    type Err = String;
+    type Err = PngError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut bytes: [u8; 4] = Default::default();
+        let invalid = || PngError::InvalidChunkType { offset: 0, chunk_index: 0, bytes: s.as_bytes().to_vec() };
         let input: &[u8] = s.as_bytes();
 
-        if input.len() < bytes.len() {
-            return Err("Invalid ChunkType. String must contain 4 characters to satisfy the requirement of a 4-byte chunk type code.".into())
+        if input.len() < 4 {
+            return Err(invalid());
         }
 
-        for idx in 0..bytes.len() {
-            bytes[idx] = input[idx];
-
-            if !ChunkType::is_valid_byte(&bytes[idx]) {
-                return Err("Type codes are restricted to consist of uppercase and lowercase ASCII letters (A-Z and a-z, or 65-90 and 97-122 decimal).".into());
-            }
-        }
+        let mut bytes: [u8; 4] = Default::default();
+        bytes.copy_from_slice(&input[..4]);
 
-        Ok(ChunkType{type_code: bytes})
+        ChunkType::try_from(bytes).map_err(|_| invalid())
     }
 }
 
 impl TryFrom<[u8; 4]> for ChunkType {
-    type Error = String;
+    type Error = PngError;
 
     fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
-        for byte in value.iter() {
-            if !ChunkType::is_valid_byte(byte) {
-                return Err("Type codes are restricted to consist of uppercase and lowercase ASCII letters (A-Z and a-z, or 65-90 and 97-122 decimal).".into());
-            }
+        if !value.iter().all(ChunkType::is_valid_byte) {
+            return Err(PngError::InvalidChunkType { offset: 0, chunk_index: 0, bytes: value.to_vec() });
         }
 
         Ok(ChunkType{type_code: value})
@@ -184,6 +178,13 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_standard_chunk_types_are_valid() {
+        for chunk_type in ["IHDR", "PLTE", "IDAT", "IEND", "tEXt", "zTXt", "iTXt", "pHYs", "bKGD"] {
+            assert!(ChunkType::from_str(chunk_type).unwrap().is_valid(), "{}", chunk_type);
+        }
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
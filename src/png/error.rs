@@ -0,0 +1,177 @@
+use crate::png::chunk_type::ChunkType;
+use crate::png::structure::StructureViolation;
+use crate::png::transfer::TransferCorruption;
+use std::path::PathBuf;
+use std::{error, fmt, io};
+
+/// An error encountered while reading a PNG file, its chunks, or their types.
+///
+/// Offsets are byte offsets into the parsed input and chunk indices count the
+/// chunks that precede the failure, so a lone chunk or chunk type parsed on
+/// its own is reported as chunk 0 at offset 0.
+#[derive(Debug)]
+pub enum PngError {
+    /// The input does not begin with the PNG signature. `offset` is the first
+    /// byte that differs from the signature, and `corruption` is the text-mode
+    /// transfer corruption the signature was mangled by, if it was.
+    BadSignature { offset: usize, found: Vec<u8>, corruption: Option<TransferCorruption> },
+    /// The input ends before the chunk starting at `offset` is complete.
+    Truncated { offset: usize, chunk_index: usize, expected: usize, available: usize },
+    /// The CRC stored at `offset` does not match the chunk type and data.
+    CrcMismatch { offset: usize, chunk_index: usize, chunk_type: ChunkType, stored: u32, computed: u32 },
+    /// The chunk type code at `offset` is not made up of four ASCII letters.
+    InvalidChunkType { offset: usize, chunk_index: usize, bytes: Vec<u8> },
+    /// The chunk starting at `offset` is complete, but its contents are invalid.
+    InvalidChunk { offset: usize, chunk_index: usize, chunk_type: ChunkType, reason: String },
+    /// The chunks break the ordering or multiplicity rules of the PNG spec.
+    /// `offset` and `chunk_index` locate the offending chunk, or where a
+    /// missing chunk was expected.
+    InvalidStructure { offset: usize, chunk_index: usize, violation: StructureViolation },
+    /// The line endings of the chunk starting at `offset` could not be restored
+    /// after a text-mode transfer.
+    Unrestorable { offset: usize, chunk_index: usize, chunk_type: ChunkType, reason: String },
+    /// `length` bytes that are not part of any chunk follow the IEND chunk,
+    /// starting at `offset`.
+    TrailingData { offset: usize, chunk_index: usize, length: usize },
+    /// The file could not be read.
+    Io { path: PathBuf, source: io::Error }
+}
+
+impl PngError {
+    /// The byte offset of the failure, if it happened while parsing.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            PngError::BadSignature { offset, .. }
+            | PngError::Truncated { offset, .. }
+            | PngError::CrcMismatch { offset, .. }
+            | PngError::InvalidChunkType { offset, .. }
+            | PngError::InvalidChunk { offset, .. }
+            | PngError::InvalidStructure { offset, .. }
+            | PngError::Unrestorable { offset, .. }
+            | PngError::TrailingData { offset, .. } => Some(*offset),
+            PngError::Io { .. } => None
+        }
+    }
+
+    /// The index of the chunk that failed to parse, if any.
+    pub fn chunk_index(&self) -> Option<usize> {
+        match self {
+            PngError::Truncated { chunk_index, .. }
+            | PngError::CrcMismatch { chunk_index, .. }
+            | PngError::InvalidChunkType { chunk_index, .. }
+            | PngError::InvalidChunk { chunk_index, .. }
+            | PngError::InvalidStructure { chunk_index, .. }
+            | PngError::Unrestorable { chunk_index, .. }
+            | PngError::TrailingData { chunk_index, .. } => Some(*chunk_index),
+            PngError::BadSignature { .. } | PngError::Io { .. } => None
+        }
+    }
+
+    /// Moves an error reported relative to a single chunk to the position
+    /// of that chunk within a file.
+    pub(crate) fn relocate(mut self, base: usize, index: usize) -> PngError {
+        match &mut self {
+            PngError::Truncated { offset, chunk_index, .. }
+            | PngError::CrcMismatch { offset, chunk_index, .. }
+            | PngError::InvalidChunkType { offset, chunk_index, .. }
+            | PngError::InvalidChunk { offset, chunk_index, .. }
+            | PngError::InvalidStructure { offset, chunk_index, .. }
+            | PngError::Unrestorable { offset, chunk_index, .. }
+            | PngError::TrailingData { offset, chunk_index, .. } => {
+                *offset += base;
+                *chunk_index = index;
+            },
+            PngError::BadSignature { offset, .. } => *offset += base,
+            PngError::Io { .. } => ()
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::BadSignature { offset, found, corruption } => {
+                write!(f, "PNG header does not match standard signature at offset {} (found {:02x?}).", offset, found)?;
+
+                match corruption {
+                    Some(corruption) if corruption.is_reversible() =>
+                        write!(f, " The file was transferred in text mode, which {}.", corruption),
+                    Some(corruption) =>
+                        write!(f, " The file was transferred in text mode, which {}. This can't be undone.", corruption),
+                    None => Ok(())
+                }
+            },
+            PngError::Truncated { offset, chunk_index, expected, available } =>
+                write!(f, "Chunk {} at offset {} is truncated: expected {} bytes but only {} remain.",
+                    chunk_index, offset, expected, available),
+            PngError::CrcMismatch { offset, chunk_index, chunk_type, stored, computed } =>
+                write!(f, "Computed CRC {:#010x} does not match given CRC {:#010x} for chunk {} ({}) at offset {}.",
+                    computed, stored, chunk_index, chunk_type, offset),
+            PngError::InvalidChunkType { offset, chunk_index, bytes } =>
+                write!(f, "Invalid chunk type {:?} for chunk {} at offset {}. Type codes are restricted to four \
+                    uppercase and lowercase ASCII letters (A-Z and a-z).",
+                    String::from_utf8_lossy(bytes), chunk_index, offset),
+            PngError::InvalidChunk { offset, chunk_index, chunk_type, reason } =>
+                write!(f, "Invalid chunk {} ({}) at offset {}: {}", chunk_index, chunk_type, offset, reason),
+            PngError::InvalidStructure { offset, chunk_index, violation } =>
+                write!(f, "Invalid chunk order at chunk {} (offset {}): {}", chunk_index, offset, violation),
+            PngError::Unrestorable { offset, chunk_index, chunk_type, reason } =>
+                write!(f, "Could not restore chunk {} ({}) at offset {}: {}", chunk_index, chunk_type, offset, reason),
+            PngError::TrailingData { offset, length, .. } =>
+                write!(f, "{} bytes of trailing data follow the IEND chunk at offset {}.", length, offset),
+            PngError::Io { path, source } =>
+                write!(f, "Could not open file {:?}: {}", path, source)
+        }
+    }
+}
+
+impl error::Error for PngError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PngError::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+/// Allows `PngError`s to be propagated with `?` by the APIs that still
+/// report their errors as messages.
+impl From<PngError> for String {
+    fn from(error: PngError) -> String {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_relocate() {
+        let error = PngError::CrcMismatch {
+            offset: 20,
+            chunk_index: 0,
+            chunk_type: ChunkType::from_str("IDAT").unwrap(),
+            stored: 1,
+            computed: 2
+        }.relocate(33, 2);
+
+        assert_eq!(error.offset(), Some(53));
+        assert_eq!(error.chunk_index(), Some(2));
+        assert!(error.to_string().contains("chunk 2 (IDAT) at offset 53"));
+    }
+
+    #[test]
+    fn test_io_source() {
+        let error = PngError::Io {
+            path: PathBuf::from("missing.png"),
+            source: io::Error::new(io::ErrorKind::NotFound, "not found")
+        };
+
+        assert_eq!(error.offset(), None);
+        assert!(error::Error::source(&error).is_some());
+    }
+}
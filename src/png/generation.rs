@@ -0,0 +1,377 @@
+use crate::png::{Chunk, Png};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// The textual chunk keywords used by image generation tools to record how
+/// an image was generated.
+pub const GENERATION_KEYWORDS: [&str; 3] = ["parameters", "prompt", "workflow"];
+
+/// The deepest chain of ComfyUI node links that will be followed.
+const MAX_LINK_DEPTH: usize = 16;
+
+/// The format the generation parameters were stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationSource {
+    /// A `parameters` text in the format of the Stable Diffusion web UI
+    /// (AUTOMATIC1111), which is also written by Forge and similar tools.
+    Automatic1111,
+    /// A ComfyUI `prompt` graph in JSON, usually alongside a `workflow` graph.
+    ComfyUi
+}
+
+/// The parameters used to generate an image with Stable Diffusion based
+/// tools, extracted from the textual chunks of the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationParameters {
+    pub source: GenerationSource,
+    pub prompt: Option<String>,
+    pub negative_prompt: Option<String>,
+    pub seed: Option<u64>,
+    pub sampler: Option<String>,
+    pub steps: Option<u64>,
+    pub cfg_scale: Option<f64>,
+    pub model: Option<String>,
+    pub model_hash: Option<String>,
+    /// Every setting recorded by the tool, in the order it was written.
+    pub settings: Vec<(String, String)>,
+    /// The ComfyUI workflow graph, as written by the editor.
+    pub workflow: Option<Value>
+}
+
+impl GenerationParameters {
+    fn empty(source: GenerationSource) -> GenerationParameters {
+        GenerationParameters {
+            source,
+            prompt: None,
+            negative_prompt: None,
+            seed: None,
+            sampler: None,
+            steps: None,
+            cfg_scale: None,
+            model: None,
+            model_hash: None,
+            settings: Vec::new(),
+            workflow: None
+        }
+    }
+
+    /// Extracts the generation parameters from the `parameters`, `prompt`,
+    /// and `workflow` textual chunks of a PNG file. Returns `None` if there
+    /// are no such chunks.
+    pub fn from_png(png: &Png) -> Option<Result<GenerationParameters, String>> {
+        let metadata = png.metadata();
+        let text = |keyword: &str| metadata.text
+            .iter()
+            .find(|t| t.keyword() == keyword)
+            .map(|t| t.text().to_string());
+
+        if let Some(parameters) = text("parameters") {
+            return Some(Ok(GenerationParameters::parse_automatic1111(&parameters)));
+        }
+
+        match (text("prompt"), text("workflow")) {
+            (None, None) => None,
+            (prompt, workflow) => Some(GenerationParameters::parse_comfyui(prompt.as_deref(), workflow.as_deref()))
+        }
+    }
+
+    /// Parses a `parameters` text, which holds the prompt, an optional line
+    /// starting with `Negative prompt:`, and a final line of settings such as
+    /// `Steps: 20, Sampler: Euler a, Seed: 1`. Prompts may span several lines.
+    pub fn parse_automatic1111(text: &str) -> GenerationParameters {
+        let mut parameters = GenerationParameters::empty(GenerationSource::Automatic1111);
+        let mut lines: Vec<&str> = text.trim_end().split('\n').collect();
+
+        if lines.last().is_some_and(|l| l.trim_start().starts_with("Steps: ")) {
+            parameters.settings = parse_settings(lines.pop().unwrap());
+        }
+
+        let negative_start: Option<usize> = lines.iter().position(|l| l.starts_with("Negative prompt:"));
+        let (prompt, negative): (&[&str], Option<&[&str]>) = match negative_start {
+            Some(idx) => (&lines[..idx], Some(&lines[idx..])),
+            None => (&lines, None)
+        };
+
+        parameters.prompt = Some(prompt.join("\n").trim().to_string()).filter(|p| !p.is_empty());
+        parameters.negative_prompt = negative
+            .map(|n| n.join("\n")["Negative prompt:".len()..].trim().to_string());
+
+        let settings: Vec<(String, String)> = parameters.settings.clone();
+        let setting = |key: &str| settings
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone());
+
+        parameters.seed = setting("Seed").and_then(|s| s.parse().ok());
+        parameters.sampler = setting("Sampler");
+        parameters.steps = setting("Steps").and_then(|s| s.parse().ok());
+        parameters.cfg_scale = setting("CFG scale").and_then(|s| s.parse().ok());
+        parameters.model = setting("Model");
+        parameters.model_hash = setting("Model hash");
+
+        parameters
+    }
+
+    /// Parses a ComfyUI `prompt` graph, which maps node IDs to nodes holding
+    /// a `class_type` and `inputs`. The settings come from the first sampler
+    /// node, and the prompts and model are found by following its links.
+    pub fn parse_comfyui(prompt: Option<&str>, workflow: Option<&str>)
+            -> Result<GenerationParameters, String> {
+        let mut parameters = GenerationParameters::empty(GenerationSource::ComfyUi);
+
+        if let Some(workflow) = workflow {
+            parameters.workflow = Some(serde_json::from_str(workflow)
+                .map_err(|e| format!("ComfyUI workflow is not valid JSON: {}", e))?);
+        }
+
+        let graph: Value = match prompt {
+            Some(prompt) => serde_json::from_str(prompt)
+                .map_err(|e| format!("ComfyUI prompt is not valid JSON: {}", e))?,
+            None => return Ok(parameters)
+        };
+
+        let nodes: &Map<String, Value> = graph
+            .as_object()
+            .ok_or_else(|| "ComfyUI prompt is not a JSON object.".to_string())?;
+
+        let mut ids: Vec<&String> = nodes.keys().collect();
+        ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.to_string()));
+
+        let sampler: Option<&Value> = ids
+            .iter()
+            .map(|id| &nodes[*id]["inputs"])
+            .find(|inputs| inputs.get("sampler_name").is_some());
+
+        if let Some(inputs) = sampler {
+            parameters.seed = inputs.get("seed").or_else(|| inputs.get("noise_seed")).and_then(Value::as_u64);
+            parameters.sampler = inputs.get("sampler_name").and_then(Value::as_str).map(String::from);
+            parameters.steps = inputs.get("steps").and_then(Value::as_u64);
+            parameters.cfg_scale = inputs.get("cfg").and_then(Value::as_f64);
+            parameters.prompt = follow(nodes, inputs.get("positive"), &["text", "text_g"], "conditioning");
+            parameters.negative_prompt = follow(nodes, inputs.get("negative"), &["text", "text_g"], "conditioning");
+            parameters.model = follow(nodes, inputs.get("model"), &["ckpt_name", "unet_name"], "model");
+
+            for (key, value) in inputs.as_object().into_iter().flatten() {
+                // links to other nodes are arrays, and are not settings
+                if !value.is_array() {
+                    let value: String = value.as_str().map(String::from).unwrap_or_else(|| value.to_string());
+                    parameters.settings.push((key.clone(), value));
+                }
+            }
+        }
+
+        Ok(parameters)
+    }
+
+    /// The parameters as a JSON object, for reporting at scale.
+    pub fn to_json(&self) -> Value {
+        let settings: Map<String, Value> = self.settings
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+
+        json!({
+            "source": match self.source {
+                GenerationSource::Automatic1111 => "automatic1111",
+                GenerationSource::ComfyUi => "comfyui"
+            },
+            "prompt": self.prompt,
+            "negative_prompt": self.negative_prompt,
+            "seed": self.seed,
+            "sampler": self.sampler,
+            "steps": self.steps,
+            "cfg_scale": self.cfg_scale,
+            "model": self.model,
+            "model_hash": self.model_hash,
+            "settings": settings,
+            "workflow": self.workflow
+        })
+    }
+}
+
+impl fmt::Display for GenerationParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: [(&str, Option<String>); 8] = [
+            ("Prompt", self.prompt.clone()),
+            ("Negative Prompt", self.negative_prompt.clone()),
+            ("Seed", self.seed.map(|s| s.to_string())),
+            ("Sampler", self.sampler.clone()),
+            ("Steps", self.steps.map(|s| s.to_string())),
+            ("CFG Scale", self.cfg_scale.map(|c| c.to_string())),
+            ("Model", self.model.clone()),
+            ("Model Hash", self.model_hash.clone())
+        ];
+
+        match self.source {
+            GenerationSource::Automatic1111 => writeln!(f, "Source: AUTOMATIC1111")?,
+            GenerationSource::ComfyUi => writeln!(f, "Source: ComfyUI")?
+        }
+        for (name, value) in fields.iter() {
+            if let Some(value) = value {
+                writeln!(f, "{}: {}", name, value)?;
+            }
+        }
+        if let Some(nodes) = self.workflow.as_ref().and_then(|w| w.get("nodes")).and_then(Value::as_array) {
+            writeln!(f, "Workflow: {} nodes", nodes.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a settings line such as `Steps: 20, Sampler: Euler a` into key
+/// and value pairs. Values containing commas are written in double quotes.
+fn parse_settings(line: &str) -> Vec<(String, String)> {
+    let mut settings: Vec<(String, String)> = Vec::new();
+    let mut rest: &str = line.trim();
+
+    while let Some(colon) = rest.find(':') {
+        let key: &str = rest[..colon].trim();
+        rest = rest[colon + 1..].trim_start();
+
+        let value: String = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut escaped: bool = false;
+            let end: usize = quoted
+                .char_indices()
+                .find(|&(_, c)| {
+                    let closes: bool = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closes
+                })
+                .map(|(idx, _)| idx)
+                .unwrap_or(quoted.len());
+
+            rest = quoted.get(end + 1..).unwrap_or_default();
+            quoted[..end].replace("\\\"", "\"")
+        } else {
+            let end: usize = rest.find(',').unwrap_or(rest.len());
+            let value: &str = rest[..end].trim();
+            rest = &rest[end..];
+            value.to_string()
+        };
+
+        settings.push((key.to_string(), value));
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+    }
+
+    settings
+}
+
+/// Follows a ComfyUI link (`[node_id, output]`) to the first node holding
+/// a string in one of `fields`, passing through nodes via their `through` input.
+fn follow(nodes: &Map<String, Value>, link: Option<&Value>, fields: &[&str], through: &str) -> Option<String> {
+    let mut link: &Value = link?;
+
+    for _ in 0..MAX_LINK_DEPTH {
+        let id: String = match link.get(0)? {
+            Value::String(id) => id.clone(),
+            id => id.to_string()
+        };
+        let inputs: &Value = nodes.get(&id)?.get("inputs")?;
+
+        if let Some(text) = fields.iter().find_map(|f| inputs.get(*f).and_then(Value::as_str)) {
+            return Some(text.to_string());
+        }
+
+        link = inputs.get(through)?;
+    }
+
+    None
+}
+
+/// Returns the keyword of a tEXt, zTXt, or iTXt chunk, which is always the
+/// bytes before the first null separator.
+fn text_keyword(chunk: &Chunk) -> Option<&[u8]> {
+    match chunk.chunk_type().to_string().as_str() {
+        "tEXt" | "zTXt" | "iTXt" => chunk.data().split(|&b| b == 0).next(),
+        _ => None
+    }
+}
+
+/// Removes the textual chunks holding generation parameters from a PNG file,
+/// leaving all other text untouched. Returns the number of chunks removed.
+pub fn strip_generation_parameters(png: &mut Png) -> usize {
+    let before: usize = png.chunks().len();
+    png.retain_chunks(|chunk| {
+        !text_keyword(chunk).is_some_and(|k| GENERATION_KEYWORDS.iter().any(|g| g.as_bytes() == k))
+    });
+
+    before - png.chunks().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::chunk_specs::{ITXT, TEXT};
+
+    const PARAMETERS: &str = "a red die on a table,\nstudio lighting\n\
+        Negative prompt: blurry, lowres\n\
+        Steps: 30, Sampler: DPM++ 2M Karras, CFG scale: 6.5, Seed: 1234567890, Size: 512x768, \
+        Model hash: 6ce0161689, Model: v1-5-pruned-emaonly, Lora hashes: \"die: abc123, wood: def456\", Version: v1.6.0";
+
+    const COMFY_PROMPT: &str = r#"{
+        "3": {"class_type": "KSampler", "inputs": {"seed": 42, "steps": 20, "cfg": 8.0,
+            "sampler_name": "euler", "scheduler": "normal", "denoise": 1.0,
+            "model": ["10", 0], "positive": ["6", 0], "negative": ["7", 0], "latent_image": ["5", 0]}},
+        "4": {"class_type": "CheckpointLoaderSimple", "inputs": {"ckpt_name": "sd_xl_base_1.0.safetensors"}},
+        "10": {"class_type": "LoraLoader", "inputs": {"model": ["4", 0], "lora_name": "dice.safetensors"}},
+        "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "two dice", "clip": ["4", 1]}},
+        "7": {"class_type": "CLIPTextEncode", "inputs": {"text": "text, watermark", "clip": ["4", 1]}}
+    }"#;
+
+    #[test]
+    fn test_parse_automatic1111() {
+        let parameters = GenerationParameters::parse_automatic1111(PARAMETERS);
+
+        assert_eq!(parameters.prompt.as_deref(), Some("a red die on a table,\nstudio lighting"));
+        assert_eq!(parameters.negative_prompt.as_deref(), Some("blurry, lowres"));
+        assert_eq!(parameters.seed, Some(1234567890));
+        assert_eq!(parameters.sampler.as_deref(), Some("DPM++ 2M Karras"));
+        assert_eq!(parameters.steps, Some(30));
+        assert_eq!(parameters.cfg_scale, Some(6.5));
+        assert_eq!(parameters.model_hash.as_deref(), Some("6ce0161689"));
+        assert_eq!(parameters.model.as_deref(), Some("v1-5-pruned-emaonly"));
+        assert_eq!(parameters.settings[7], ("Lora hashes".to_string(), "die: abc123, wood: def456".to_string()));
+        assert_eq!(parameters.settings[8], ("Version".to_string(), "v1.6.0".to_string()));
+
+        let bare = GenerationParameters::parse_automatic1111("just a prompt");
+        assert_eq!(bare.prompt.as_deref(), Some("just a prompt"));
+        assert!(bare.negative_prompt.is_none() && bare.settings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_comfyui() {
+        let workflow = r#"{"nodes": [{"id": 3}, {"id": 4}], "links": []}"#;
+        let parameters = GenerationParameters::parse_comfyui(Some(COMFY_PROMPT), Some(workflow)).unwrap();
+
+        assert_eq!(parameters.prompt.as_deref(), Some("two dice"));
+        assert_eq!(parameters.negative_prompt.as_deref(), Some("text, watermark"));
+        assert_eq!(parameters.seed, Some(42));
+        assert_eq!(parameters.sampler.as_deref(), Some("euler"));
+        assert_eq!(parameters.cfg_scale, Some(8.0));
+        assert_eq!(parameters.model.as_deref(), Some("sd_xl_base_1.0.safetensors"));
+        assert!(parameters.settings.contains(&("scheduler".to_string(), "normal".to_string())));
+        assert_eq!(parameters.to_json()["workflow"]["nodes"][1]["id"], 4);
+
+        assert!(GenerationParameters::parse_comfyui(Some("{"), None).is_err());
+        assert!(GenerationParameters::parse_comfyui(Some("[]"), None).is_err());
+    }
+
+    #[test]
+    fn test_from_png_and_strip() {
+        let mut png = Png::from_chunks(vec![
+            TEXT::new("Title", "Dice").unwrap().to_chunk(),
+            ITXT::new("prompt", "", "", COMFY_PROMPT, true).unwrap().to_chunk(),
+            TEXT::new("workflow", "{}").unwrap().to_chunk(),
+            TEXT::new("promptly", "kept").unwrap().to_chunk()
+        ]);
+
+        let parameters = GenerationParameters::from_png(&png).unwrap().unwrap();
+        assert_eq!(parameters.source, GenerationSource::ComfyUi);
+        assert_eq!(parameters.seed, Some(42));
+
+        assert_eq!(strip_generation_parameters(&mut png), 2);
+        assert_eq!(png.chunks().len(), 2);
+        assert!(GenerationParameters::from_png(&png).is_none());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::png::chunk_specs::{IHDR, SBIT};
+use crate::png::{zlib, Chunk, ChunkType, Png};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// Decoded image samples. Each pixel holds `channels` samples, stored
+/// interleaved row by row, at the bit depth of the image. See the PNG spec
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-DataRep.html
+pub struct Image {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    samples: Vec<u16>
+}
+
+impl Image {
+    /// Creates a new `Image` after checking that there is exactly one sample
+    /// per channel of every pixel.
+    pub fn new(width: u32, height: u32, bit_depth: u8, color_type: u8, samples: Vec<u16>)
+            -> Result<Image, String> {
+        let ihdr: IHDR = IHDR::new(width, height, bit_depth, color_type, 0)?;
+        let expected: usize = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(ihdr.channels()))
+            .ok_or_else(|| format!("A {}x{} image is too large to hold in memory.", width, height))?;
+
+        if samples.len() != expected {
+            return Err(format!("Image holds {} samples, but {} are required.", samples.len(), expected));
+        }
+
+        Ok(Image { width, height, bit_depth, color_type, samples })
+    }
+
+    /// Decodes the samples of a non-interlaced 8- or 16-bit grayscale or
+    /// truecolor image (with or without alpha) by inflating and unfiltering
+    /// the concatenated `IDAT` chunks. Images holding a critical chunk not
+    /// defined by the PNG spec are refused, as they can't be shown correctly.
+    pub fn decode(png: &Png) -> Result<Image, String> {
+        if let Some(chunk) = png.unknown_critical_chunk() {
+            return Err(format!("PNG file holds the unknown critical chunk {}. The image cannot be shown correctly \
+                without it.", chunk.chunk_type()));
+        }
+
+        let ihdr: IHDR = match png.chunk_by_type("IHDR") {
+            Some(chunk) => IHDR::try_from(chunk.clone())?,
+            None => return Err("PNG file does not contain an IHDR chunk.".into())
+        };
+
+        if ihdr.interlace_method() != 0 {
+            return Err("Decoding interlaced images is not supported.".into());
+        }
+
+        if ihdr.color_type() == 3 || ihdr.bit_depth() < 8 {
+            return Err("Decoding indexed or sub-byte images is not supported.".into());
+        }
+
+        let compressed: Vec<u8> = png.chunks()
+            .iter()
+            .filter(|c| c.chunk_type().to_string() == "IDAT")
+            .flat_map(|c| c.data().iter().copied())
+            .collect();
+
+        let expected: usize = usize::try_from(ihdr.image_data_size())
+            .map_err(|_| "Image is too large to decode.".to_string())?;
+        let raw: Vec<u8> = unfilter(&zlib::inflate_with_limit(&compressed, expected)?, &ihdr)?;
+        let samples: Vec<u16> = match ihdr.bit_depth() {
+            16 => raw.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect(),
+            _ => raw.iter().map(|&b| b as u16).collect()
+        };
+
+        Image::new(ihdr.width(), ihdr.height(), ihdr.bit_depth(), ihdr.color_type(), samples)
+    }
+
+    /// Decodes the samples like `Image::decode`, then discards the low-order
+    /// bits that the `sBIT` chunk marks as insignificant. Each sample then
+    /// holds the value of the original image before it was scaled up to the
+    /// bit depth of the PNG, so samples no longer span the full bit depth.
+    /// Images without an `sBIT` chunk decode unchanged.
+    pub fn decode_original_precision(png: &Png) -> Result<Image, String> {
+        let mut image: Image = Image::decode(png)?;
+
+        if let Some(sbit) = png.typed_chunk::<SBIT>() {
+            let sbit: SBIT = sbit?;
+            sbit.validate(&IHDR::new(image.width, image.height, image.bit_depth, image.color_type, 0)?)?;
+            sbit.original_samples(&mut image.samples, image.bit_depth);
+        }
+
+        Ok(image)
+    }
+
+    /// Encodes these samples into a new `Png` holding only the `IHDR`,
+    /// `IDAT`, and `IEND` chunks. Every scanline is stored unfiltered.
+    pub fn encode(&self) -> Result<Png, String> {
+        let ihdr: IHDR = IHDR::new(self.width, self.height, self.bit_depth, self.color_type, 0)?;
+        let row_samples: usize = self.width as usize * ihdr.channels();
+
+        let mut raw: Vec<u8> = Vec::new();
+        for row in self.samples.chunks(row_samples.max(1)) {
+            raw.push(0);
+            for &sample in row {
+                match self.bit_depth {
+                    16 => raw.extend_from_slice(&sample.to_be_bytes()),
+                    _ => raw.push(sample as u8)
+                }
+            }
+        }
+
+        Ok(Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT")?, zlib::deflate(&raw)),
+            Chunk::new(ChunkType::from_str("IEND")?, Vec::new())
+        ]))
+    }
+
+    /// The width of the image in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of bits in each sample, either 8 or 16.
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    /// The color type of the image as described in the PNG spec.
+    pub fn color_type(&self) -> u8 {
+        self.color_type
+    }
+
+    /// Whether the last sample of each pixel is an alpha sample.
+    pub fn has_alpha(&self) -> bool {
+        matches!(self.color_type, 4 | 6)
+    }
+
+    /// The interleaved samples of every pixel, row by row.
+    pub fn samples(&self) -> &[u16] {
+        &self.samples
+    }
+}
+
+/// Reverses the per-scanline filters described in the PNG spec.
+/// http://www.libpng.org/pub/png/spec/1.2/PNG-Filters.html
+fn unfilter(data: &[u8], ihdr: &IHDR) -> Result<Vec<u8>, String> {
+    let bytes_per_pixel: usize = ihdr.channels() * ihdr.bit_depth() as usize / 8;
+    let height: usize = ihdr.height() as usize;
+    let too_large = || format!("A {}x{} image is too large to hold in memory.", ihdr.width(), ihdr.height());
+    let stride: usize = (ihdr.width() as usize).checked_mul(bytes_per_pixel).ok_or_else(too_large)?;
+    let expected: usize = stride.checked_add(1).and_then(|row| row.checked_mul(height)).ok_or_else(too_large)?;
+
+    if data.len() != expected {
+        return Err(format!("Decompressed image data holds {} bytes, but {} are required.", data.len(), expected));
+    }
+
+    let mut raw: Vec<u8> = vec![0; stride * height];
+    for row in 0..height {
+        let filter_type: u8 = data[row * (stride + 1)];
+        let line: &[u8] = &data[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        let (previous, current) = raw.split_at_mut(row * stride);
+        let previous: &[u8] = match row {
+            0 => &[],
+            _ => &previous[(row - 1) * stride..]
+        };
+        let current: &mut [u8] = &mut current[..stride];
+
+        for idx in 0..stride {
+            let a: u8 = if idx >= bytes_per_pixel { current[idx - bytes_per_pixel] } else { 0 };
+            let b: u8 = previous.get(idx).copied().unwrap_or(0);
+            let c: u8 = if idx >= bytes_per_pixel { previous.get(idx - bytes_per_pixel).copied().unwrap_or(0) } else { 0 };
+
+            current[idx] = match filter_type {
+                0 => line[idx],
+                1 => line[idx].wrapping_add(a),
+                2 => line[idx].wrapping_add(b),
+                3 => line[idx].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                4 => line[idx].wrapping_add(paeth(a, b, c)),
+                _ => return Err(format!("Unknown filter type {} on scanline {}.", filter_type, row))
+            };
+        }
+    }
+
+    Ok(raw)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p: i16 = a as i16 + b as i16 - c as i16;
+    let pa: i16 = (p - a as i16).abs();
+    let pb: i16 = (p - b as i16).abs();
+    let pc: i16 = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_round_trip() {
+        let samples: Vec<u16> = (0..2 * 3 * 3).map(|s| s * 3000).collect();
+        let image = Image::new(2, 3, 16, 2, samples.clone()).unwrap();
+        let decoded = Image::decode(&image.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded.width(), 2);
+        assert_eq!(decoded.height(), 3);
+        assert_eq!(decoded.samples(), &samples[..]);
+    }
+
+    #[test]
+    fn test_decode_original_precision() {
+        let samples: Vec<u16> = vec![0b1111_1000, 0b1111_1100, 0b1111_1000, 0b1000_0100, 0b1000_0100, 0b1000_0100];
+        let mut png: Png = Image::new(2, 1, 8, 2, samples.clone()).unwrap().encode().unwrap();
+
+        assert_eq!(Image::decode_original_precision(&png).unwrap().samples(), &samples[..]);
+
+        png.insert_chunk_before(SBIT::new(vec![5, 6, 5]).unwrap().to_chunk(), &["IDAT"]);
+        let bytes: Vec<u8> = png.as_bytes();
+        let png: Png = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(Image::decode(&png).unwrap().samples(), &samples[..]);
+        assert_eq!(Image::decode_original_precision(&png).unwrap().samples(), &[31, 63, 31, 16, 33, 16]);
+
+        let mut png: Png = Image::new(1, 1, 8, 0, vec![0]).unwrap().encode().unwrap();
+        png.insert_chunk_before(SBIT::new(vec![5, 6, 5]).unwrap().to_chunk(), &["IDAT"]);
+        assert!(Image::decode_original_precision(&png).is_err());
+    }
+
+    #[test]
+    fn test_unfilter() {
+        let ihdr = IHDR::new(2, 2, 8, 0, 0).unwrap();
+        // a sub-filtered scanline followed by an up-filtered scanline
+        let data: Vec<u8> = vec![1, 10, 5, 2, 1, 1];
+        assert_eq!(unfilter(&data, &ihdr).unwrap(), vec![10, 15, 11, 16]);
+
+        // an average-filtered scanline followed by a paeth-filtered scanline
+        let data: Vec<u8> = vec![3, 10, 5, 4, 1, 1];
+        assert_eq!(unfilter(&data, &ihdr).unwrap(), vec![10, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_image_invalid() {
+        assert!(Image::new(2, 2, 8, 2, vec![0; 4]).is_err());
+
+        let ihdr = IHDR::new(2, 2, 8, 0, 0).unwrap();
+        assert!(unfilter(&[5, 0, 0, 0, 0, 0], &ihdr).is_err());
+        assert!(unfilter(&[0, 0, 0], &ihdr).is_err());
+
+        let ihdr = IHDR::new(i32::MAX as u32, i32::MAX as u32, 16, 6, 0).unwrap();
+        assert!(unfilter(&[0, 0, 0], &ihdr).is_err());
+        assert!(Image::new(i32::MAX as u32, i32::MAX as u32, 16, 6, vec![]).is_err());
+
+        let huge: Png = Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), zlib::deflate(&[0; 64])),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new())
+        ]);
+        assert!(Image::decode(&huge).is_err());
+
+        let mut png: Png = Image::new(1, 1, 8, 0, vec![0]).unwrap().encode().unwrap();
+        png.insert_chunk_before(Chunk::new(ChunkType::from_str("EXtR").unwrap(), vec![]), &["IDAT"]);
+        assert!(matches!(Image::decode(&png), Err(e) if e.contains("EXtR")));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::png::chunk_specs::{
+    ChunkSpec, BKGD, CHRM, CICP, CLLI, EXIF, GAMA, ICCP, IHDR, ITXT, MDCV, PHYS, SRGB, TEXT, TIME, ZTXT
+};
+use crate::png::{Chunk, Png};
+
+/// The ancillary chunk types collected into a `Metadata`. These are the
+/// chunks removed and rewritten by `Png::set_metadata`.
+pub const METADATA_CHUNK_TYPES: [&str; 14] = [
+    "tEXt", "zTXt", "iTXt", "tIME", "pHYs", "gAMA", "cHRM", "sRGB", "cICP", "mDCV", "cLLI", "bKGD",
+    "eXIf", "iCCP"
+];
+
+/// A textual entry stored in any of the three textual chunks.
+pub enum TextChunk {
+    Text(TEXT),
+    Compressed(ZTXT),
+    International(ITXT)
+}
+
+impl TextChunk {
+    /// The keyword the text is stored under.
+    pub fn keyword(&self) -> &str {
+        match self {
+            TextChunk::Text(t) => t.keyword(),
+            TextChunk::Compressed(t) => t.keyword(),
+            TextChunk::International(t) => t.keyword()
+        }
+    }
+
+    /// The language of the text. This is empty unless the entry is
+    /// stored in an `iTXt` chunk with a language tag.
+    pub fn language_tag(&self) -> &str {
+        match self {
+            TextChunk::International(t) => t.language_tag(),
+            _ => ""
+        }
+    }
+
+    /// The text itself.
+    pub fn text(&self) -> &str {
+        match self {
+            TextChunk::Text(t) => t.text(),
+            TextChunk::Compressed(t) => t.text(),
+            TextChunk::International(t) => t.text()
+        }
+    }
+
+    /// Serializes this into the chunk it was read from.
+    pub fn to_chunk(&self) -> Chunk {
+        match self {
+            TextChunk::Text(t) => t.to_chunk(),
+            TextChunk::Compressed(t) => t.to_chunk(),
+            TextChunk::International(t) => t.to_chunk()
+        }
+    }
+}
+
+/// The chunks describing how the samples of the image map to colors.
+#[derive(Default)]
+pub struct ColorInfo {
+    pub gamma: Option<GAMA>,
+    pub chromaticities: Option<CHRM>,
+    pub srgb: Option<SRGB>,
+    pub cicp: Option<CICP>,
+    pub mastering_display: Option<MDCV>,
+    pub content_light_level: Option<CLLI>
+}
+
+/// Every recognized ancillary chunk of a PNG file, parsed into its typed
+/// spec. See `Png::metadata` and `Png::set_metadata`.
+#[derive(Default)]
+pub struct Metadata {
+    pub text: Vec<TextChunk>,
+    pub time: Option<TIME>,
+    pub physical_size: Option<PHYS>,
+    pub color: ColorInfo,
+    pub background: Option<BKGD>,
+    pub exif: Option<EXIF>,
+    pub icc_profile: Option<ICCP>,
+    /// Ancillary chunks that are not listed in `METADATA_CHUNK_TYPES`, along
+    /// with any metadata chunks that are malformed or repeated.
+    pub unrecognized: Vec<Chunk>
+}
+
+/// Parses a chunk into `slot` unless the chunk is malformed or the slot is
+/// already taken, in which case the chunk is returned.
+fn fill<T: ChunkSpec>(slot: &mut Option<T>, chunk: &Chunk) -> Option<Chunk> {
+    if slot.is_some() {
+        return Some(chunk.clone());
+    }
+
+    match T::parse(chunk) {
+        Ok(parsed) => {
+            *slot = Some(parsed);
+            None
+        },
+        Err(_) => Some(chunk.clone())
+    }
+}
+
+/// Parses a tEXt, zTXt, or iTXt chunk into a `TextChunk`.
+fn parse_text(chunk: &Chunk) -> Option<TextChunk> {
+    match chunk.chunk_type().to_string().as_str() {
+        "tEXt" => TEXT::parse(chunk).ok().map(TextChunk::Text),
+        "zTXt" => ZTXT::parse(chunk).ok().map(TextChunk::Compressed),
+        _ => ITXT::parse(chunk).ok().map(TextChunk::International)
+    }
+}
+
+impl Metadata {
+    /// Collects the metadata of a PNG file. Critical chunks are ignored.
+    pub fn from_png(png: &Png) -> Metadata {
+        let mut metadata = Metadata::default();
+
+        for chunk in png.chunks().iter().filter(|c| !c.chunk_type().is_critical()) {
+            let rejected: Option<Chunk> = match chunk.chunk_type().to_string().as_str() {
+                "tEXt" | "zTXt" | "iTXt" => match parse_text(chunk) {
+                    Some(text) => {
+                        metadata.text.push(text);
+                        None
+                    },
+                    None => Some(chunk.clone())
+                },
+                "tIME" => fill(&mut metadata.time, chunk),
+                "pHYs" => fill(&mut metadata.physical_size, chunk),
+                "gAMA" => fill(&mut metadata.color.gamma, chunk),
+                "cHRM" => fill(&mut metadata.color.chromaticities, chunk),
+                "sRGB" => fill(&mut metadata.color.srgb, chunk),
+                "cICP" => fill(&mut metadata.color.cicp, chunk),
+                "mDCV" => fill(&mut metadata.color.mastering_display, chunk),
+                "cLLI" => fill(&mut metadata.color.content_light_level, chunk),
+                "bKGD" => fill(&mut metadata.background, chunk),
+                "eXIf" => fill(&mut metadata.exif, chunk),
+                "iCCP" => fill(&mut metadata.icc_profile, chunk),
+                _ => Some(chunk.clone())
+            };
+
+            metadata.unrecognized.extend(rejected);
+        }
+
+        metadata
+    }
+
+    /// The types of the chunks this `Metadata` writes that may appear at
+    /// most once in a PNG file.
+    fn single_chunk_types(&self) -> Vec<&'static str> {
+        vec![
+            ("tIME", self.time.is_some()),
+            ("pHYs", self.physical_size.is_some()),
+            ("gAMA", self.color.gamma.is_some()),
+            ("cHRM", self.color.chromaticities.is_some()),
+            ("sRGB", self.color.srgb.is_some()),
+            ("cICP", self.color.cicp.is_some()),
+            ("mDCV", self.color.mastering_display.is_some()),
+            ("cLLI", self.color.content_light_level.is_some()),
+            ("bKGD", self.background.is_some()),
+            ("eXIf", self.exif.is_some()),
+            ("iCCP", self.icc_profile.is_some())
+        ].into_iter().filter(|(_, set)| *set).map(|(chunk_type, _)| chunk_type).collect()
+    }
+
+    /// Replaces the metadata chunks of a PNG file with the chunks held by
+    /// this `Metadata`, placing each one where the PNG spec allows it.
+    /// Chunks listed in `unrecognized` are left in place, even when they are
+    /// of a metadata type, unless this `Metadata` holds a chunk of that type
+    /// and the type may appear only once.
+    pub fn apply_to(&self, png: &mut Png) -> Result<(), String> {
+        if self.color.srgb.is_some() && self.icc_profile.is_some() {
+            return Err("Metadata must not hold both an sRGB chunk and an ICC profile.".into());
+        }
+
+        if let (Some(background), Some(Ok(ihdr))) = (&self.background, png.typed_chunk::<IHDR>()) {
+            background.validate(&ihdr)?;
+        }
+
+        // keep the malformed and repeated metadata chunks listed in `unrecognized`,
+        // unless they would repeat a chunk that may appear only once
+        let written: Vec<&str> = self.single_chunk_types();
+        let mut kept: Vec<&Chunk> = self.unrecognized.iter().collect();
+        png.retain_chunks(|c| {
+            let chunk_type: String = c.chunk_type().to_string();
+            if !METADATA_CHUNK_TYPES.contains(&chunk_type.as_str()) {
+                return true;
+            }
+
+            if written.contains(&chunk_type.as_str()) {
+                return false;
+            }
+
+            match kept.iter().position(|u| u.chunk_type() == c.chunk_type() && u.data() == c.data()) {
+                Some(idx) => {
+                    kept.remove(idx);
+                    true
+                },
+                None => false
+            }
+        });
+
+        // color chunks must precede PLTE and IDAT
+        let color_chunks: Vec<Chunk> = vec![
+            self.color.chromaticities.as_ref().map(CHRM::to_chunk),
+            self.color.gamma.as_ref().map(GAMA::to_chunk),
+            self.icc_profile.as_ref().map(ICCP::to_chunk),
+            self.color.srgb.as_ref().map(SRGB::to_chunk),
+            self.color.cicp.as_ref().map(CICP::to_chunk),
+            self.color.mastering_display.as_ref().map(MDCV::to_chunk),
+            self.color.content_light_level.as_ref().map(CLLI::to_chunk)
+        ].into_iter().flatten().collect();
+
+        for chunk in color_chunks {
+            png.insert_chunk_before(chunk, &["PLTE", "IDAT"]);
+        }
+
+        // bKGD must follow PLTE, and all of these must precede IDAT
+        let image_chunks: Vec<Chunk> = vec![
+            self.background.as_ref().map(BKGD::to_chunk),
+            self.physical_size.as_ref().map(PHYS::to_chunk),
+            self.exif.as_ref().map(EXIF::to_chunk)
+        ].into_iter().flatten().collect();
+
+        for chunk in image_chunks {
+            png.insert_chunk_before(chunk, &["IDAT"]);
+        }
+
+        // textual chunks and tIME may appear anywhere
+        for text in &self.text {
+            png.append_chunk(text.to_chunk());
+        }
+
+        if let Some(time) = &self.time {
+            png.append_chunk(time.to_chunk());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        let chunk = |chunk_type: &str, data: Vec<u8>| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data);
+
+        Png::from_chunks(vec![
+            IHDR::new(1, 1, 8, 2, 0).unwrap().to_chunk(),
+            GAMA::new(45455).unwrap().to_chunk(),
+            chunk("prVt", b"private".to_vec()),
+            PHYS::from_dpi(300.0, 300.0).unwrap().to_chunk(),
+            chunk("IDAT", vec![]),
+            TEXT::new("Title", "Dice").unwrap().to_chunk(),
+            TIME::new(2020, 1, 2, 3, 4, 5).unwrap().to_chunk(),
+            TIME::new(2021, 1, 2, 3, 4, 5).unwrap().to_chunk(),
+            chunk("eXIf", b"not exif".to_vec()),
+            chunk("IEND", vec![])
+        ])
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks().iter().map(|c| c.chunk_type().to_string()).collect()
+    }
+
+    #[test]
+    fn test_metadata_from_png() {
+        let metadata = Metadata::from_png(&testing_png());
+
+        assert_eq!(metadata.text.len(), 1);
+        assert_eq!(metadata.text[0].keyword(), "Title");
+        assert_eq!(metadata.time.unwrap().year(), 2020);
+        assert_eq!(metadata.color.gamma.unwrap().gamma(), 45455);
+        assert!(metadata.physical_size.is_some());
+        assert!(metadata.icc_profile.is_none());
+
+        // the private chunk, the repeated tIME chunk, and the malformed eXIf chunk
+        assert_eq!(metadata.unrecognized.len(), 3);
+        assert_eq!(metadata.unrecognized[0].chunk_type().to_string(), "prVt");
+        assert!(metadata.exif.is_none());
+    }
+
+    #[test]
+    fn test_metadata_apply_to() {
+        let mut png = testing_png();
+        let mut metadata = Metadata::from_png(&png);
+
+        metadata.color.gamma = None;
+        metadata.color.srgb = Some(SRGB::new(SRGB::INTENT_PERCEPTUAL).unwrap());
+        metadata.background = Some(BKGD::Rgb(0, 0, 0));
+        metadata.text.push(TextChunk::Compressed(ZTXT::new("Comment", "Rolled").unwrap()));
+        metadata.apply_to(&mut png).unwrap();
+
+        assert_eq!(chunk_types(&png),
+            vec!["IHDR", "prVt", "sRGB", "bKGD", "pHYs", "IDAT", "eXIf", "tEXt", "zTXt", "tIME", "IEND"]);
+        assert!(png.check_structure().is_empty());
+    }
+
+    #[test]
+    fn test_metadata_apply_to_keeps_unrecognized() {
+        let mut png = testing_png();
+        let mut metadata = Metadata::from_png(&png);
+
+        metadata.time = None;
+        metadata.apply_to(&mut png).unwrap();
+
+        let times: Vec<u16> = png.chunks()
+            .iter()
+            .filter(|c| c.chunk_type().to_string() == "tIME")
+            .map(|c| TIME::parse(c).unwrap().year())
+            .collect();
+
+        assert_eq!(times, vec![2021]);
+        assert_eq!(png.chunk_by_type("eXIf").unwrap().data(), b"not exif");
+        assert!(png.check_structure().is_empty());
+    }
+
+    #[test]
+    fn test_metadata_apply_to_invalid() {
+        let mut png = testing_png();
+        let mut metadata = Metadata::from_png(&png);
+
+        metadata.background = Some(BKGD::Gray(0));
+        assert!(metadata.apply_to(&mut png).is_err());
+        assert_eq!(png.chunks().len(), testing_png().chunks().len());
+    }
+}
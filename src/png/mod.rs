@@ -1,10 +1,35 @@
+mod cbor;
+mod check;
 mod chunk;
 mod chunk_type;
+mod error;
+mod generation;
+mod image;
+mod metadata;
+mod recovery;
+mod repair;
+mod structure;
+mod tone_map;
+mod transfer;
+mod xmp;
+mod zlib;
 pub mod chunk_specs;
 
+pub use check::*;
 pub use chunk::*;
 pub use chunk_type::*;
-
+pub use error::*;
+pub use generation::*;
+pub use image::*;
+pub use metadata::*;
+pub use recovery::*;
+pub use repair::*;
+pub use structure::*;
+pub use tone_map::*;
+pub use transfer::*;
+pub use xmp::*;
+
+use crate::png::chunk_specs::{ChunkRegistry, ChunkSpec};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use std::path::Path;
@@ -13,7 +38,8 @@ use std::{fs, fmt};
 /// A PNG container as described by the PNG spec
 /// http://www.libpng.org/pub/png/spec/1.2/PNG-Contents.html
 pub struct Png {
-    chunks: Vec<Chunk>
+    chunks: Vec<Chunk>,
+    trailing_data: Vec<u8>
 }
 
 impl Png {
@@ -23,21 +49,114 @@ impl Png {
 
     /// Creates a `Png` from a list of chunks using the correct header
     pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Png { chunks }
+        Png { chunks, trailing_data: Vec::new() }
     }
 
     /// Creates a `Png` from a file path
     pub fn from_file<P: AsRef<Path> + fmt::Debug>(path: P) 
-            -> Result<Self, String> {
-        let bytes: Vec<u8> = match fs::read(&path) {
-            Ok(b) => b,
-            Err(_) => return Err(format!("Could not open file '{:#?}'", &path))
-        };
+            -> Result<Self, PngError> {
+        let bytes: Vec<u8> = fs::read(&path)
+            .map_err(|source| PngError::Io { path: path.as_ref().to_path_buf(), source })?;
+
+        Png::try_from(bytes.as_ref())
+    }
+
+    /// Creates a `Png` from bytes that may be damaged, recovering as much as
+    /// possible. Ancillary chunks with bad CRCs are skipped, garbage between
+    /// chunks is skipped up to the next plausible chunk, a missing IEND chunk
+    /// is accepted, and the complete part of a truncated IDAT chunk is kept.
+    /// Anything after IEND is kept as trailing data. Every problem is
+    /// returned as a `Diagnostic` alongside the `Png`, and only a missing
+    /// signature is an error.
+    pub fn from_bytes_lenient(bytes: &[u8]) -> Result<(Png, Vec<Diagnostic>), PngError> {
+        let recovered = recovery::read_lenient(bytes, ChunkType::is_critical)?;
+
+        Ok((Png { chunks: recovered.chunks, trailing_data: recovered.trailing_data }, recovered.diagnostics))
+    }
+
+    /// Creates a `Png` from a file path that may be damaged. See
+    /// `Png::from_bytes_lenient`.
+    pub fn from_file_lenient<P: AsRef<Path>>(path: P) -> Result<(Png, Vec<Diagnostic>), PngError> {
+        let bytes: Vec<u8> = fs::read(&path)
+            .map_err(|source| PngError::Io { path: path.as_ref().to_path_buf(), source })?;
+
+        Png::from_bytes_lenient(&bytes)
+    }
+
+    /// Repairs a damaged file so that it follows the PNG spec, returning the
+    /// repaired `Png` along with every change made. On top of the recovery
+    /// done by `Png::from_bytes_lenient`, chunks with a wrong CRC are handled
+    /// according to `options`, a missing IEND chunk is appended, misplaced
+    /// chunks are moved to legal positions, repeats of chunks that may appear
+    /// only once are dropped, and anything after IEND is removed. Files that
+    /// can't be repaired, e.g. because IHDR or IDAT is missing, are an error.
+    pub fn repair(bytes: &[u8], options: RepairOptions) -> Result<(Png, Vec<RepairChange>), PngError> {
+        repair::repair(bytes, options)
+    }
+
+    /// Checks that bytes begin with the PNG signature.
+    pub(crate) fn check_signature(value: &[u8]) -> Result<(), PngError> {
+        if !value.starts_with(&Png::STANDARD_HEADER) {
+            let offset: usize = value
+                .iter()
+                .zip(Png::STANDARD_HEADER.iter())
+                .position(|(a, b)| a != b)
+                .unwrap_or(value.len());
+
+            return Err(PngError::BadSignature {
+                offset,
+                found: value[..value.len().min(8)].to_vec(),
+                corruption: TransferCorruption::detect(value)
+            });
+        }
+
+        Ok(())
+    }
 
-        match Png::try_from(bytes.as_ref()) {
-            Ok(png) => Ok(png),
-            Err(e) => Err(e.to_string())
+    /// Splits a PNG file into its chunks after checking the signature. The
+    /// chunks end at the IEND chunk, and any bytes after it are returned as
+    /// trailing data.
+    fn read_chunks(value: &[u8]) -> Result<(Vec<Chunk>, &[u8]), PngError> {
+        Png::check_signature(value)?;
+
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut start: usize = 8;
+
+        while start < value.len() {
+            let remaining: &[u8] = &value[start..];
+            let truncated = |expected: usize| PngError::Truncated {
+                offset: start,
+                chunk_index: chunks.len(),
+                expected,
+                available: remaining.len()
+            };
+
+            // parse the length of the data of the next chunk
+            let length_bytes: [u8; 4] = remaining
+                .get(..4)
+                .ok_or_else(|| truncated(12))?
+                .try_into()
+                .unwrap();
+
+            // length, chunk type, and crc fields compose 12 bytes in a chunk
+            let chunk_length: usize = (u32::from_be_bytes(length_bytes) as usize).saturating_add(12);
+            let chunk_bytes: &[u8] = remaining
+                .get(..chunk_length)
+                .ok_or_else(|| truncated(chunk_length))?;
+
+            let chunk: Chunk = Chunk::try_from(chunk_bytes).map_err(|e| e.relocate(start, chunks.len()))?;
+            let is_iend: bool = chunk.chunk_type().bytes() == *b"IEND";
+            chunks.push(chunk);
+
+            // start the next chunk at the end of the previous chunk
+            start += chunk_length;
+
+            if is_iend {
+                break;
+            }
         }
+
+        Ok((chunks, &value[start..]))
     }
 
     /// Appends a chunk to the end of this `Png` file's `Chunk` list.
@@ -49,6 +168,21 @@ impl Png {
         self.chunks.swap(end - 1, end);
     }
     
+    /// Inserts a chunk directly before the first chunk whose type is one of 
+    /// `chunk_types`. This is used to place ancillary chunks that must precede 
+    /// chunks such as `PLTE` or `IDAT`. If none of the chunk types are present, 
+    /// the chunk is appended before the `IEND` chunk instead.
+    pub fn insert_chunk_before(&mut self, chunk: Chunk, chunk_types: &[&str]) {
+        let position: Option<usize> = self.chunks
+            .iter()
+            .position(|c| chunk_types.contains(&c.chunk_type().to_string().as_str()));
+
+        match position {
+            Some(idx) => self.chunks.insert(idx, chunk),
+            None => self.append_chunk(chunk)
+        }
+    }
+
     /// Searches for a `Chunk` with the specified `chunk_type` and removes the 
     /// first matching `Chunk` from this `Png` list of chunks.
     pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk, String> {
@@ -66,6 +200,11 @@ impl Png {
         Err("Could not find chunk with specified chunk type.".into())
     }
     
+    /// Removes every `Chunk` from this `Png` for which `keep` returns false.
+    pub fn retain_chunks<F: FnMut(&Chunk) -> bool>(&mut self, keep: F) {
+        self.chunks.retain(keep);
+    }
+
     /// The header of this PNG.
     pub fn header(&self) -> &[u8; 8] {
         &Png::STANDARD_HEADER
@@ -84,17 +223,88 @@ impl Png {
             Err(_) => return None
         };
 
-        for chunk in self.chunks.iter() {
-            if chunk.chunk_type() == &cmp {
-                return Some(&chunk);
-            }
-        }
+        self.chunks.iter().find(|chunk| chunk.chunk_type() == &cmp)
+    }
 
-        None
+    /// Parses the first chunk of type `T::CHUNK_TYPE` with the spec `T`.
+    /// Returns `None` if there is no such chunk.
+    pub fn typed_chunk<T: ChunkSpec>(&self) -> Option<Result<T, String>> {
+        self.chunk_by_type(T::CHUNK_TYPE).map(T::parse)
+    }
+
+    /// Parses every chunk of type `T::CHUNK_TYPE` with the spec `T`, in
+    /// file order.
+    pub fn typed_chunks<T: ChunkSpec>(&self) -> Vec<Result<T, String>> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == T::CHUNK_TYPE)
+            .map(T::parse)
+            .collect()
     }
     
+    /// Checks the chunks of this `Png` against the ordering and multiplicity
+    /// rules of the PNG spec, returning every violation found.
+    pub fn check_structure(&self) -> Vec<PngError> {
+        structure::check_structure(&self.chunks)
+    }
+
+    /// Returns the first critical chunk that is not defined by the PNG spec.
+    /// An image holding one cannot be shown correctly.
+    pub fn unknown_critical_chunk(&self) -> Option<&Chunk> {
+        self.chunks
+            .iter()
+            .find(|c| c.chunk_type().is_critical() && !structure::is_defined(c.chunk_type()))
+    }
+
+    /// Removes the ancillary chunks that are not safe to copy and are neither
+    /// defined by the PNG spec nor registered in `ChunkRegistry::standard`.
+    /// Such chunks may depend on the critical chunks, so the PNG spec requires
+    /// editors that change IHDR, PLTE, or IDAT to drop them. Returns the
+    /// removed chunks.
+    pub fn remove_unsafe_to_copy_chunks(&mut self) -> Vec<Chunk> {
+        let registry: &ChunkRegistry = ChunkRegistry::shared();
+        let (removed, kept): (Vec<Chunk>, Vec<Chunk>) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|c| Png::is_unknown_unsafe_to_copy(c.chunk_type(), registry));
+
+        self.chunks = kept;
+        removed
+    }
+
+    /// Whether `chunk_type` is an unknown ancillary chunk type that is not
+    /// safe to copy. See `Png::remove_unsafe_to_copy_chunks`.
+    pub(crate) fn is_unknown_unsafe_to_copy(chunk_type: &ChunkType, registry: &ChunkRegistry) -> bool {
+        !chunk_type.is_critical()
+            && !chunk_type.is_safe_to_copy()
+            && !structure::is_defined(chunk_type)
+            && !registry.is_registered(chunk_type)
+    }
+
+    /// Collects every recognized ancillary chunk into a `Metadata`.
+    pub fn metadata(&self) -> Metadata {
+        Metadata::from_png(self)
+    }
+
+    /// Replaces the metadata chunks of this `Png` with those in `metadata`,
+    /// placing each chunk where the PNG spec allows it.
+    pub fn set_metadata(&mut self, metadata: &Metadata) -> Result<(), String> {
+        metadata.apply_to(self)
+    }
+
+    /// The bytes following the IEND chunk, such as an appended archive. They
+    /// aren't part of the image, but are kept when the file is rewritten.
+    pub fn trailing_data(&self) -> &[u8] {
+        &self.trailing_data
+    }
+
+    /// Removes and returns the bytes following the IEND chunk.
+    pub fn remove_trailing_data(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.trailing_data)
+    }
+
     /// Returns this `Png` as a byte sequence. These bytes will contain the 
-    /// header followed by the bytes of all of the chunks.
+    /// header followed by the bytes of all of the chunks and any trailing
+    /// data.
     pub fn as_bytes(&self) -> Vec<u8> {
         let chunk_bytes: Vec<u8> = self.chunks
             .iter()
@@ -104,48 +314,44 @@ impl Png {
         Png::STANDARD_HEADER
             .iter()
             .chain(chunk_bytes.iter())
+            .chain(self.trailing_data.iter())
             .copied()
             .collect()
     }
 }
 
 impl TryFrom<&[u8]> for Png {
-    type Error = String;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {        
-        if value[..8] != Png::STANDARD_HEADER {
-            return Err("PNG header does not match standard signature.".into());
+    type Error = PngError;
+
+    /// Parses a complete PNG file, which must follow the chunk ordering rules
+    /// of the PNG spec. Bytes after the IEND chunk are kept as trailing data.
+    /// Arbitrary input never causes a panic, as every length is checked
+    /// against the bytes that remain.
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (chunks, trailing_data) = Png::read_chunks(value)?;
+        let png = Png { chunks, trailing_data: trailing_data.to_vec() };
+
+        match png.check_structure().into_iter().next() {
+            Some(violation) => Err(violation),
+            None => Ok(png)
         }
-
-        let mut chunks: Vec<Chunk> = Vec::new();
-
-        let mut start: usize = 8;
-        let mut end: usize = start;
-        let mut next_length: usize;
-        let mut length_bytes: [u8; 4];
-
-        while end < value.len() {
-            // parse the length of the data of the next chunk
-            length_bytes = value[start..start + 4].try_into().unwrap();
-            next_length = u32::from_be_bytes(length_bytes) as usize;
-
-            // length, chunk type, and crc fields compose 12 bytes in a chunk
-            end = start + next_length + 12;
-
-            chunks.push(Chunk::try_from(&value[start..end])?);
-
-            // start the next chunk at the end of the previous chunk
-            start = end;
-        }
-
-        Ok(Png { chunks })
     }
 }
 
 impl fmt::Display for Png {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let registry: &ChunkRegistry = ChunkRegistry::shared();
+
         for chunk in self.chunks() {
-            writeln!(f, "{}", chunk);
+            writeln!(f, "{}", chunk)?;
+
+            if let Some(description) = registry.describe(chunk) {
+                writeln!(f, "{}", description)?;
+            }
+        }
+
+        if !self.trailing_data.is_empty() {
+            writeln!(f, "Trailing data: {} bytes after IEND", self.trailing_data.len())?;
         }
 
         Ok(())
@@ -153,8 +359,10 @@ impl fmt::Display for Png {
 }
 
 #[cfg(test)]
+#[allow(clippy::vec_init_then_push, clippy::iter_cloned_collect)]
 mod tests {
     use super::*;
+    use crate::png::chunk_specs::IHDR;
     use std::convert::TryFrom;
 
     fn testing_chunks() -> Vec<Chunk> {
@@ -190,9 +398,18 @@ mod tests {
         assert_eq!(png.chunks().len(), 3);
     }
 
+    /// The testing chunks wrapped in the chunks every PNG file requires.
+    fn structured_chunks() -> Vec<Chunk> {
+        let mut chunks: Vec<Chunk> = vec![IHDR::new(1, 1, 8, 0, 0).unwrap().to_chunk()];
+        chunks.extend(testing_chunks());
+        chunks.push(Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![120, 156, 99, 0, 0, 0, 1, 0, 1]));
+        chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()));
+        chunks
+    }
+
     #[test]
     fn test_valid_from_bytes() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
+        let chunk_bytes: Vec<u8> = structured_chunks()
             .into_iter()
             .flat_map(|chunk| chunk.as_bytes())
             .collect();
@@ -208,6 +425,40 @@ mod tests {
         assert!(png.is_ok());
     }
 
+    #[test]
+    fn test_unsafe_to_copy_chunks() {
+        let mut png: Png = Png::from_chunks(
+            ["IHDR", "bKGD", "prVT", "tRNS", "prVt", "IDAT", "EXtR", "IEND"]
+                .iter()
+                .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+                .collect()
+        );
+
+        assert_eq!(png.unknown_critical_chunk().unwrap().chunk_type().to_string(), "EXtR");
+
+        let removed: Vec<Chunk> = png.remove_unsafe_to_copy_chunks();
+        let chunk_types: Vec<String> = png.chunks().iter().map(|c| c.chunk_type().to_string()).collect();
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].chunk_type().to_string(), "prVT");
+        assert_eq!(chunk_types, vec!["IHDR", "bKGD", "tRNS", "prVt", "IDAT", "EXtR", "IEND"]);
+    }
+
+    #[test]
+    fn test_invalid_structure() {
+        let bytes: Vec<u8> = Png::from_chunks(testing_chunks()).as_bytes();
+
+        assert!(matches!(
+            Png::try_from(bytes.as_ref()),
+            Err(PngError::InvalidStructure { chunk_index: 0, violation: StructureViolation::MissingChunk("IHDR"), .. })
+        ));
+
+        let (png, diagnostics) = Png::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(diagnostics.len(), 3);
+        assert!(diagnostics.iter().all(|d| d.recovery == Recovery::Reported));
+    }
+
     #[test]
     fn test_invalid_header() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -249,6 +500,51 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_error_locations() {
+        let mut bytes: Vec<u8> = Png::from_chunks(testing_chunks()).as_bytes();
+        let second_chunk: usize = 8 + testing_chunks()[0].as_bytes().len();
+
+        assert!(matches!(
+            Png::try_from(&bytes[..bytes.len() - 3]),
+            Err(PngError::Truncated { chunk_index: 2, .. })
+        ));
+        assert!(matches!(Png::try_from(&bytes[..5]), Err(PngError::BadSignature { offset: 5, .. })));
+
+        bytes[second_chunk + 8] ^= 0xff;
+        let error = Png::try_from(bytes.as_ref()).err().unwrap();
+        assert!(matches!(error, PngError::CrcMismatch { chunk_index: 1, .. }));
+        assert_eq!(error.offset(), Some(second_chunk + 8 + 18));
+
+        bytes[second_chunk + 4] = b'1';
+        assert!(matches!(
+            Png::try_from(bytes.as_ref()),
+            Err(PngError::InvalidChunkType { offset, chunk_index: 1, .. }) if offset == second_chunk + 4
+        ));
+        assert!(matches!(Png::from_file("does/not/exist.png"), Err(PngError::Io { .. })));
+    }
+
+    #[test]
+    fn test_damaged_input_does_not_panic() {
+        let bytes: Vec<u8> = Png::from_chunks(testing_chunks()).as_bytes();
+
+        for end in 0..bytes.len() {
+            let _ = Png::try_from(&bytes[..end]);
+        }
+
+        for idx in 0..bytes.len() {
+            for value in [0x00, 0x7f, 0x80, 0xff].iter() {
+                let mut damaged: Vec<u8> = bytes.clone();
+                damaged[idx] = *value;
+                let _ = Png::try_from(damaged.as_ref());
+            }
+        }
+
+        let mut oversized: Vec<u8> = bytes;
+        oversized[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(Png::try_from(oversized.as_ref()), Err(PngError::Truncated { offset: 8, .. })));
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -283,6 +579,43 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_insert_chunk_before() {
+        let mut png = testing_png();
+        png.insert_chunk_before(chunk_from_strings("TeSt", "Message").unwrap(), &["miDl", "LASt"]);
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "TeSt");
+
+        png.insert_chunk_before(chunk_from_strings("AbSt", "Message").unwrap(), &["IDAT"]);
+        assert_eq!(&png.chunks()[3].chunk_type().to_string(), "AbSt");
+    }
+
+    #[test]
+    fn test_retain_chunks() {
+        let mut png = testing_png();
+        png.retain_chunks(|chunk| chunk.chunk_type().is_critical());
+        assert_eq!(png.chunks().len(), 2);
+        assert!(png.chunk_by_type("miDl").is_none());
+    }
+
+    #[test]
+    fn test_typed_chunks() {
+        use crate::png::chunk_specs::TEXT;
+
+        let mut png = testing_png();
+        assert!(png.typed_chunk::<TEXT>().is_none());
+
+        png.append_chunk(TEXT::new("Title", "First").unwrap().to_chunk());
+        png.append_chunk(TEXT::new("Author", "Second").unwrap().to_chunk());
+        png.append_chunk(chunk_from_strings("tEXt", "malformed").unwrap());
+
+        assert_eq!(png.typed_chunk::<TEXT>().unwrap().unwrap().text(), "First");
+
+        let texts = png.typed_chunks::<TEXT>();
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts[1].as_ref().unwrap().keyword(), "Author");
+        assert!(texts[2].is_err());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -297,9 +630,29 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_trailing_data() {
+        let mut bytes: Vec<u8> = PNG_FILE.to_vec();
+        let trailing: Vec<u8> = chunk_from_strings("tEXt", "after the end").unwrap().as_bytes();
+        bytes.extend_from_slice(&trailing);
+
+        let mut png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.trailing_data(), &trailing[..]);
+        assert_eq!(png.as_bytes(), bytes);
+        assert!(png.to_string().contains(&format!("Trailing data: {} bytes", trailing.len())));
+
+        assert_eq!(png.remove_trailing_data(), trailing);
+        assert!(png.trailing_data().is_empty());
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+
+        let (png, diagnostics) = Png::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(png.trailing_data(), &trailing[..]);
+        assert!(diagnostics.is_empty());
+    }
+
     #[test]
     fn test_png_trait_impls() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
+        let chunk_bytes: Vec<u8> = structured_chunks()
             .into_iter()
             .flat_map(|chunk| chunk.as_bytes())
             .collect();
@@ -0,0 +1,292 @@
+use crate::png::{structure, Chunk, ChunkType, Png, PngError};
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+
+/// The largest chunk length allowed by the PNG spec.
+const MAX_CHUNK_LENGTH: u32 = i32::MAX as u32;
+
+/// The most bytes whose CRC is computed while resynchronizing on a damaged
+/// file. Garbage can be crafted so that many offsets look like large chunks,
+/// so without a bound, resynchronizing takes quadratic time.
+const MAX_RESYNC_BYTES: usize = 32 * 1024 * 1024;
+
+/// What lenient parsing did about a problem in a damaged file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Nothing was changed, the problem is only reported.
+    Reported,
+    /// The damaged chunk was left out.
+    SkippedChunk,
+    /// The damaged chunk was kept with a recomputed CRC.
+    KeptChunk,
+    /// The given number of bytes were skipped to reach the next plausible
+    /// chunk header, or the end of the file.
+    SkippedBytes(usize),
+    /// The complete part of a truncated IDAT chunk was kept.
+    SalvagedData
+}
+
+/// A problem found while leniently parsing a damaged file, along with what
+/// was done to recover from it.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub error: PngError,
+    pub recovery: Recovery
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.recovery {
+            Recovery::Reported => write!(f, "{}", self.error),
+            Recovery::SkippedChunk => write!(f, "{} Skipped the chunk.", self.error),
+            Recovery::KeptChunk => write!(f, "{} Kept the chunk with a recomputed CRC.", self.error),
+            Recovery::SkippedBytes(count) => write!(f, "{} Skipped {} bytes.", self.error, count),
+            Recovery::SalvagedData => write!(f, "{} Kept the available image data.", self.error)
+        }
+    }
+}
+
+/// The chunks read from a damaged file, the offsets they were read from,
+/// and any bytes following the IEND chunk.
+pub(crate) struct Recovered {
+    pub chunks: Vec<Chunk>,
+    pub offsets: Vec<usize>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub trailing_data: Vec<u8>
+}
+
+/// Reads as many chunks as possible from a file that may be damaged. Only a
+/// missing signature is fatal. Chunks with a bad CRC are kept with a
+/// recomputed CRC if `recompute_crc` returns true for their type, and are
+/// skipped otherwise. Garbage between chunks is skipped up to the next
+/// chunk with a valid CRC, or to the end of the file if a bounded search
+/// finds none, and the available data of a truncated IDAT chunk is kept.
+/// Reading stops at the IEND chunk, and anything after it is kept as
+/// trailing data.
+/// Ordering violations, including a missing IEND chunk, are only reported.
+pub(crate) fn read_lenient(bytes: &[u8], recompute_crc: impl Fn(&ChunkType) -> bool) -> Result<Recovered, PngError> {
+    Png::check_signature(bytes)?;
+
+    let mut recovered = Recovered { chunks: Vec::new(), offsets: Vec::new(), diagnostics: Vec::new(), trailing_data: Vec::new() };
+    let mut start: usize = 8;
+    let mut budget: usize = MAX_RESYNC_BYTES;
+
+    while start < bytes.len() {
+        let index: usize = recovered.chunks.len();
+        let remaining: &[u8] = &bytes[start..];
+        let mut diagnose = |error: PngError, recovery: Recovery| recovered.diagnostics.push(Diagnostic {
+            error: error.relocate(start, index),
+            recovery
+        });
+
+        let header: Option<(u32, ChunkType)> = read_header(remaining);
+        let chunk_length: Option<usize> = header
+            .as_ref()
+            .map(|(length, _)| *length as usize + 12)
+            .filter(|&length| length <= remaining.len());
+
+        let error: PngError = match (header, chunk_length) {
+            (Some(_), Some(length)) => match Chunk::try_from(&remaining[..length]) {
+                Ok(chunk) => {
+                    let is_iend: bool = chunk.chunk_type().bytes() == *b"IEND";
+                    recovered.chunks.push(chunk);
+                    recovered.offsets.push(start);
+                    start += length;
+
+                    if is_iend {
+                        recovered.trailing_data = bytes[start..].to_vec();
+                        break;
+                    }
+                    continue;
+                },
+                Err(error @ PngError::CrcMismatch { .. }) => {
+                    let chunk_type: ChunkType = ChunkType::try_from(<[u8; 4]>::try_from(&remaining[4..8]).unwrap()).unwrap();
+
+                    if recompute_crc(&chunk_type) {
+                        diagnose(error, Recovery::KeptChunk);
+                        recovered.chunks.push(Chunk::new(chunk_type, remaining[8..length - 4].to_vec()));
+                        recovered.offsets.push(start);
+                    } else {
+                        diagnose(error, Recovery::SkippedChunk);
+                    }
+
+                    start += length;
+                    continue;
+                },
+                Err(error) => error
+            },
+            (Some((length, chunk_type)), None) if chunk_type.bytes() == *b"IDAT" && next_chunk(remaining, &mut budget).is_none() => {
+                let expected: usize = length as usize + 12;
+                let error = PngError::Truncated { offset: 0, chunk_index: 0, expected, available: remaining.len() };
+                diagnose(error, Recovery::SalvagedData);
+                recovered.chunks.push(Chunk::new(chunk_type, remaining[8..].to_vec()));
+                recovered.offsets.push(start);
+                break;
+            },
+            (Some((length, _)), None) => PngError::Truncated {
+                offset: 0,
+                chunk_index: 0,
+                expected: (length as usize).saturating_add(12),
+                available: remaining.len()
+            },
+            (None, _) => match Chunk::try_from(&remaining[..remaining.len().min(12)]) {
+                Err(error @ PngError::InvalidChunkType { .. }) => error,
+                _ => PngError::Truncated { offset: 0, chunk_index: 0, expected: 12, available: remaining.len() }
+            }
+        };
+
+        // resynchronize on the next chunk with a valid CRC
+        let skipped: usize = next_chunk(remaining, &mut budget).unwrap_or(remaining.len());
+        diagnose(error, Recovery::SkippedBytes(skipped));
+        start += skipped;
+    }
+
+    let violations: Vec<PngError> = structure::check_structure_at(&recovered.chunks, &recovered.offsets);
+    recovered.diagnostics.extend(violations.into_iter().map(|error| Diagnostic { error, recovery: Recovery::Reported }));
+    recovered.diagnostics.sort_by_key(|d| d.error.offset());
+
+    Ok(recovered)
+}
+
+/// Reads a chunk length and type, if they are plausible.
+fn read_header(bytes: &[u8]) -> Option<(u32, ChunkType)> {
+    let length: u32 = u32::from_be_bytes(bytes.get(..4)?.try_into().unwrap());
+    let chunk_type: ChunkType = ChunkType::try_from(<[u8; 4]>::try_from(bytes.get(4..8)?).unwrap()).ok()?;
+
+    match length <= MAX_CHUNK_LENGTH {
+        true => Some((length, chunk_type)),
+        false => None
+    }
+}
+
+/// Finds the offset of the first complete chunk with a valid CRC after the
+/// start of `bytes`. A candidate must have a valid type that is ancillary or
+/// defined by the PNG spec, fit in `bytes`, and be followed by another
+/// plausible header or the end of `bytes` before its CRC is computed. The
+/// bytes covered by each CRC are taken from `budget`, and the search gives up
+/// once it runs out.
+fn next_chunk(bytes: &[u8], budget: &mut usize) -> Option<usize> {
+    for offset in 1..bytes.len() {
+        let candidate: &[u8] = &bytes[offset..];
+        let length: usize = match read_header(candidate) {
+            Some((length, chunk_type)) if chunk_type.is_valid()
+                && (!chunk_type.is_critical() || structure::is_defined(&chunk_type)) => length as usize + 12,
+            _ => continue
+        };
+
+        let is_followed: bool = match candidate.get(length..) {
+            Some(rest) => rest.is_empty() || &candidate[4..8] == b"IEND" || read_header(rest).is_some(),
+            None => false
+        };
+        if !is_followed {
+            continue;
+        }
+
+        *budget = budget.checked_sub(length - 4)?;
+        if Chunk::try_from(&candidate[..length]).is_ok() {
+            return Some(offset);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::chunk_specs::TEXT;
+    use crate::png::{Image, StructureViolation};
+
+    fn damaged_png() -> Vec<u8> {
+        let mut png: Png = Image::new(2, 2, 8, 0, vec![0, 64, 128, 255]).unwrap().encode().unwrap();
+        png.insert_chunk_before(TEXT::new("Title", "Recovered").unwrap().to_chunk(), &["IDAT"]);
+        png.insert_chunk_before(TEXT::new("Author", "Damaged").unwrap().to_chunk(), &["IDAT"]);
+        png.as_bytes()
+    }
+
+    #[test]
+    fn test_skip_bad_ancillary_crc() {
+        let mut bytes: Vec<u8> = damaged_png();
+        bytes[8 + 25 + 8] ^= 0x20;
+
+        let recovered: Recovered = read_lenient(&bytes, ChunkType::is_critical).unwrap();
+        let types: Vec<String> = recovered.chunks.iter().map(|c| c.chunk_type().to_string()).collect();
+
+        assert_eq!(types, vec!["IHDR", "tEXt", "IDAT", "IEND"]);
+        assert_eq!(recovered.diagnostics.len(), 1);
+        assert_eq!(recovered.diagnostics[0].recovery, Recovery::SkippedChunk);
+        assert_eq!(recovered.offsets[1], 8 + 25 + 27);
+    }
+
+    #[test]
+    fn test_resynchronize_after_garbage() {
+        let mut bytes: Vec<u8> = damaged_png();
+        let garbage: Vec<u8> = vec![0xff; 7];
+        bytes.splice(8 + 25..8 + 25, garbage);
+
+        let recovered: Recovered = read_lenient(&bytes, ChunkType::is_critical).unwrap();
+
+        assert_eq!(recovered.chunks.len(), 5);
+        assert_eq!(recovered.diagnostics.len(), 1);
+        assert_eq!(recovered.diagnostics[0].recovery, Recovery::SkippedBytes(7));
+        assert_eq!(recovered.diagnostics[0].error.offset(), Some(8 + 25 + 4));
+    }
+
+    #[test]
+    fn test_resynchronize_after_large_garbage() {
+        let bytes: Vec<u8> = damaged_png();
+        let garbage = |record: &[u8], count: usize| -> Vec<u8> {
+            let mut damaged: Vec<u8> = bytes.clone();
+            let region = [0xff; 4].iter().chain(record.iter().cycle().take(record.len() * count)).copied();
+            damaged.splice(8 + 25..8 + 25, region);
+            damaged
+        };
+        let skipped = |recovered: &Recovered| recovered.diagnostics.iter().find_map(|d| match d.recovery {
+            Recovery::SkippedBytes(count) => Some(count),
+            _ => None
+        });
+
+        // every other offset holds a plausible header of a chunk spanning much of the garbage
+        let plausible: Vec<u8> = garbage(&[0, 4, 0, 0, b'a', b'a', b'A', b'a'], 64 * 1024);
+        let recovered: Recovered = read_lenient(&plausible, ChunkType::is_critical).unwrap();
+
+        assert_eq!(recovered.chunks.len(), 5);
+        assert_eq!(skipped(&recovered), Some(4 + 8 * 64 * 1024));
+
+        // each of those chunks is also followed by a plausible header, so the search gives up
+        let followed: Vec<u8> = garbage(&[0, 0, 0x0f, 0xf4, b'a', b'a', b'A', b'a'], 128 * 1024);
+        let recovered: Recovered = read_lenient(&followed, ChunkType::is_critical).unwrap();
+
+        assert_eq!(recovered.chunks.len(), 1);
+        assert_eq!(skipped(&recovered), Some(followed.len() - 8 - 25));
+    }
+
+    #[test]
+    fn test_salvage_truncated_idat() {
+        let bytes: Vec<u8> = damaged_png();
+        let iend_start: usize = bytes.len() - 12;
+        let recovered: Recovered = read_lenient(&bytes[..iend_start - 6], ChunkType::is_critical).unwrap();
+        let last: &Chunk = recovered.chunks.last().unwrap();
+
+        assert_eq!(last.chunk_type().to_string(), "IDAT");
+        assert_eq!(recovered.diagnostics[0].recovery, Recovery::SalvagedData);
+        assert!(matches!(
+            recovered.diagnostics[1].error,
+            PngError::InvalidStructure { violation: StructureViolation::MissingChunk("IEND"), .. }
+        ));
+        assert!(read_lenient(b"\x89PNG\r\n\x1a", ChunkType::is_critical).is_err());
+    }
+
+    #[test]
+    fn test_keep_trailing_data() {
+        let mut bytes: Vec<u8> = damaged_png();
+        let chunk_count: usize = read_lenient(&bytes, ChunkType::is_critical).unwrap().chunks.len();
+        bytes.extend_from_slice(b"PK\x03\x04 appended archive");
+
+        let recovered: Recovered = read_lenient(&bytes, ChunkType::is_critical).unwrap();
+
+        assert_eq!(recovered.chunks.len(), chunk_count);
+        assert!(recovered.diagnostics.is_empty());
+        assert_eq!(recovered.trailing_data, b"PK\x03\x04 appended archive");
+    }
+}
@@ -0,0 +1,385 @@
+use crate::png::chunk_specs::{ChunkRegistry, IHDR};
+use crate::png::recovery::{self, Diagnostic, Recovery};
+use crate::png::structure::{self, Position};
+use crate::png::{Chunk, ChunkType, Png, PngError, StructureViolation};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// How `Png::repair` handles chunks it would otherwise drop or refuse.
+#[derive(Default, Clone, Copy)]
+pub struct RepairOptions {
+    /// Recompute wrong CRCs of critical chunks. Otherwise, a critical chunk
+    /// with a wrong CRC makes the file unrepairable.
+    pub recompute_critical_crcs: bool,
+    /// Recompute wrong CRCs of ancillary chunks. Otherwise, they are dropped.
+    pub recompute_ancillary_crcs: bool,
+    /// Keep unknown chunks that are not safe to copy, even if the repair
+    /// changes IHDR, PLTE, or IDAT. See `Png::remove_unsafe_to_copy_chunks`.
+    pub keep_unsafe_to_copy: bool
+}
+
+/// The regions of a file, relative to the PLTE and IDAT chunks, that
+/// ancillary chunks are placed in.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Region {
+    BeforePlte,
+    BeforeIdat,
+    AfterIdat
+}
+
+impl Region {
+    fn describe(self, has_palette: bool) -> &'static str {
+        match (self, has_palette) {
+            (Region::BeforePlte, true) => "before PLTE",
+            (Region::BeforePlte, false) | (Region::BeforeIdat, false) => "before IDAT",
+            (Region::BeforeIdat, true) => "between PLTE and IDAT",
+            (Region::AfterIdat, _) => "after IDAT"
+        }
+    }
+}
+
+/// A change made to a damaged file by `Png::repair`. Offsets refer to the
+/// damaged file.
+#[derive(Debug)]
+pub enum RepairChange {
+    /// Damaged data was skipped or fixed while reading the file.
+    Recovered(Diagnostic),
+    /// A chunk was dropped because it breaks the given rule.
+    DroppedChunk { offset: usize, chunk_type: ChunkType, violation: StructureViolation },
+    /// A chunk was moved to the first position the PNG spec allows.
+    MovedChunk { offset: usize, chunk_type: ChunkType, destination: &'static str },
+    /// An unknown chunk that is not safe to copy was dropped, as the repair
+    /// changed the critical chunks.
+    DroppedUnsafeToCopy { offset: usize, chunk_type: ChunkType },
+    /// The missing IEND chunk was appended.
+    AppendedIend,
+    /// `length` bytes following the IEND chunk were removed.
+    TruncatedTrailingData { offset: usize, length: usize }
+}
+
+impl fmt::Display for RepairChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepairChange::Recovered(diagnostic) => write!(f, "{}", diagnostic),
+            RepairChange::DroppedChunk { offset, chunk_type, violation } =>
+                write!(f, "Dropped the {} chunk at offset {}: {}", chunk_type, offset, violation),
+            RepairChange::MovedChunk { offset, chunk_type, destination } =>
+                write!(f, "Moved the {} chunk at offset {} {}.", chunk_type, offset, destination),
+            RepairChange::DroppedUnsafeToCopy { offset, chunk_type } =>
+                write!(f, "Dropped the {} chunk at offset {}: unknown chunks that are not safe to copy must be dropped \
+                    when critical chunks change.", chunk_type, offset),
+            RepairChange::AppendedIend => write!(f, "Appended the missing IEND chunk."),
+            RepairChange::TruncatedTrailingData { offset, length } =>
+                write!(f, "Removed {} bytes of trailing data at offset {}.", length, offset)
+        }
+    }
+}
+
+/// Repairs a damaged file. See `Png::repair`.
+pub(crate) fn repair(bytes: &[u8], options: RepairOptions) -> Result<(Png, Vec<RepairChange>), PngError> {
+    let recovered = recovery::read_lenient(bytes, |chunk_type| match chunk_type.is_critical() {
+        true => options.recompute_critical_crcs,
+        false => options.recompute_ancillary_crcs
+    })?;
+    let mut changes: Vec<RepairChange> = Vec::new();
+
+    // reading stops at the IEND chunk, which is replaced below
+    let has_iend: bool = recovered.chunks.last().is_some_and(|c| c.chunk_type().bytes() == *b"IEND");
+    let iend: usize = recovered.chunks.len() - has_iend as usize;
+    let data_end: usize = bytes.len() - recovered.trailing_data.len();
+
+    for diagnostic in recovered.diagnostics {
+        match (&diagnostic.recovery, &diagnostic.error) {
+            (Recovery::Reported, _) => continue,
+            (Recovery::SkippedChunk, PngError::CrcMismatch { chunk_type, .. }) if chunk_type.is_critical() =>
+                return Err(diagnostic.error),
+            _ => changes.push(RepairChange::Recovered(diagnostic))
+        }
+    }
+
+    let chunks: Vec<(usize, Chunk)> = recovered.offsets
+        .into_iter()
+        .zip(recovered.chunks)
+        .take(iend)
+        .collect();
+    let critical_before: Vec<Vec<u8>> = critical_chunks(&chunks);
+    let chunks: Vec<(usize, Chunk)> = drop_invalid_chunks(chunks, &mut changes);
+    let mut chunks: Vec<(usize, Chunk)> = reorder_chunks(chunks, &mut changes);
+
+    if critical_chunks(&chunks) != critical_before && !options.keep_unsafe_to_copy {
+        let registry: &ChunkRegistry = ChunkRegistry::shared();
+
+        chunks.retain(|(offset, chunk)| match Png::is_unknown_unsafe_to_copy(chunk.chunk_type(), registry) {
+            true => {
+                changes.push(RepairChange::DroppedUnsafeToCopy { offset: *offset, chunk_type: chunk.chunk_type().clone() });
+                false
+            },
+            false => true
+        });
+    }
+
+    let mut chunks: Vec<Chunk> = chunks.into_iter().map(|(_, chunk)| chunk).collect();
+
+    match has_iend {
+        false => changes.push(RepairChange::AppendedIend),
+        true if data_end < bytes.len() =>
+            changes.push(RepairChange::TruncatedTrailingData { offset: data_end, length: bytes.len() - data_end }),
+        true => ()
+    }
+
+    chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()));
+
+    let png = Png::from_chunks(chunks);
+    match png.check_structure().into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok((png, changes))
+    }
+}
+
+/// The bytes of the critical chunks among `chunks`, in order.
+fn critical_chunks(chunks: &[(usize, Chunk)]) -> Vec<Vec<u8>> {
+    chunks
+        .iter()
+        .filter(|(_, c)| c.chunk_type().is_critical())
+        .map(|(_, c)| c.as_bytes())
+        .collect()
+}
+
+/// Drops repeated chunks that may appear at most once, keeping the first,
+/// and palettes in grayscale images.
+fn drop_invalid_chunks(chunks: Vec<(usize, Chunk)>, changes: &mut Vec<RepairChange>) -> Vec<(usize, Chunk)> {
+    let color_type: Option<u8> = chunks
+        .iter()
+        .find(|(_, c)| c.chunk_type().bytes() == *b"IHDR")
+        .and_then(|(_, c)| IHDR::try_from(c.clone()).ok())
+        .map(|header| header.color_type());
+    let mut seen: HashSet<[u8; 4]> = HashSet::new();
+
+    chunks
+        .into_iter()
+        .filter(|(offset, chunk)| {
+            let chunk_type: &ChunkType = chunk.chunk_type();
+            let (_, once): (Position, bool) = structure::ordering_rule(chunk_type);
+
+            let violation: Option<StructureViolation> = match &chunk_type.bytes() {
+                _ if once && !seen.insert(chunk_type.bytes()) => Some(StructureViolation::Duplicate(chunk_type.clone())),
+                b"PLTE" if matches!(color_type, Some(0) | Some(4)) => Some(StructureViolation::UnexpectedPalette),
+                _ => None
+            };
+
+            match violation {
+                Some(violation) => {
+                    changes.push(RepairChange::DroppedChunk { offset: *offset, chunk_type: chunk_type.clone(), violation });
+                    false
+                },
+                None => true
+            }
+        })
+        .collect()
+}
+
+/// Lays chunks out in the order the PNG spec requires: IHDR first, then the
+/// ancillary chunks that must precede PLTE, PLTE, the ancillary chunks that
+/// must precede IDAT, the contiguous IDAT chunks, and the remaining chunks.
+/// Chunks that are already in a legal region keep their relative order.
+fn reorder_chunks(chunks: Vec<(usize, Chunk)>, changes: &mut Vec<RepairChange>) -> Vec<(usize, Chunk)> {
+    let position = |chunk_type: &[u8; 4]| chunks.iter().position(|(_, c)| c.chunk_type().bytes() == *chunk_type);
+    let plte: Option<usize> = position(b"PLTE");
+    let first_idat: Option<usize> = position(b"IDAT");
+    let last_idat: Option<usize> = chunks.iter().rposition(|(_, c)| c.chunk_type().bytes() == *b"IDAT");
+
+    let mut header: Vec<(usize, Chunk)> = Vec::new();
+    let mut palette: Vec<(usize, Chunk)> = Vec::new();
+    let mut image_data: Vec<(usize, Chunk)> = Vec::new();
+    let mut regions: Vec<(Region, (usize, Chunk))> = Vec::new();
+
+    for (idx, (offset, chunk)) in chunks.into_iter().enumerate() {
+        let chunk_type: ChunkType = chunk.chunk_type().clone();
+        let after_idat: bool = first_idat.is_some_and(|first| idx > first);
+        let mut moved = |destination: &'static str| changes.push(RepairChange::MovedChunk {
+            offset,
+            chunk_type: chunk_type.clone(),
+            destination
+        });
+
+        match &chunk_type.bytes() {
+            b"IHDR" => {
+                if idx != 0 {
+                    moved("to the start of the file");
+                }
+                header.push((offset, chunk));
+                continue;
+            },
+            b"PLTE" => {
+                if after_idat {
+                    moved("before IDAT");
+                }
+                palette.push((offset, chunk));
+                continue;
+            },
+            b"IDAT" => {
+                image_data.push((offset, chunk));
+                continue;
+            },
+            _ => ()
+        }
+
+        let current: Region = match (after_idat, plte.is_some_and(|plte| idx > plte)) {
+            (true, _) => Region::AfterIdat,
+            (false, true) => Region::BeforeIdat,
+            (false, false) => Region::BeforePlte
+        };
+
+        let (lowest, highest): (Region, Region) = match structure::ordering_rule(&chunk_type).0 {
+            Position::BeforePlteAndIdat => (Region::BeforePlte, Region::BeforePlte),
+            Position::AfterPlteBeforeIdat if plte.is_some() => (Region::BeforeIdat, Region::BeforeIdat),
+            Position::AfterPlteBeforeIdat | Position::BeforeIdat => (Region::BeforePlte, Region::BeforeIdat),
+            Position::Anywhere => (Region::BeforePlte, Region::AfterIdat)
+        };
+
+        let region: Region = current.clamp(lowest, highest);
+        let between_idats: bool = region == Region::AfterIdat && last_idat.is_some_and(|last| idx < last);
+
+        if region != current || between_idats {
+            moved(region.describe(plte.is_some()));
+        }
+
+        regions.push((region, (offset, chunk)));
+    }
+
+    let region = |wanted: Region| regions
+        .iter()
+        .filter(|(region, _)| *region == wanted)
+        .map(|(_, chunk)| chunk.clone())
+        .collect::<Vec<(usize, Chunk)>>();
+
+    [
+        header,
+        region(Region::BeforePlte),
+        palette,
+        region(Region::BeforeIdat),
+        image_data,
+        region(Region::AfterIdat)
+    ].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn ihdr() -> Chunk {
+        chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0])
+    }
+
+    fn idat() -> Chunk {
+        chunk("IDAT", &[120, 156, 99, 0, 0, 0, 1, 0, 1])
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks().iter().map(|c| c.chunk_type().to_string()).collect()
+    }
+
+    /// Replaces the CRC of the chunk that ends at `end` with a wrong one.
+    fn corrupt_crc(bytes: &mut [u8], end: usize) {
+        bytes[end - 1] ^= 0xff;
+    }
+
+    #[test]
+    fn test_repair_structure() {
+        let chunks: Vec<Chunk> = vec![
+            chunk("tEXt", b"a\0b"),
+            ihdr(),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            idat(),
+            chunk("pHYs", &[0, 0, 11, 19, 0, 0, 11, 19, 1]),
+            idat(),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            chunk("IEND", &[])
+        ];
+        let mut bytes: Vec<u8> = Png::from_chunks(chunks).as_bytes();
+        bytes.extend_from_slice(b"PK\x03\x04");
+
+        let (png, changes) = Png::repair(&bytes, RepairOptions::default()).unwrap();
+
+        assert_eq!(chunk_types(&png), vec!["IHDR", "tEXt", "gAMA", "pHYs", "IDAT", "IDAT", "IEND"]);
+        assert!(png.check_structure().is_empty());
+        assert!(matches!(
+            changes[..],
+            [
+                RepairChange::DroppedChunk { violation: StructureViolation::Duplicate(_), .. },
+                RepairChange::MovedChunk { offset: 23, destination: "to the start of the file", .. },
+                RepairChange::MovedChunk { destination: "before IDAT", .. },
+                RepairChange::TruncatedTrailingData { length: 4, .. }
+            ]
+        ));
+
+        let (_, changes) = Png::repair(&png.as_bytes(), RepairOptions::default()).unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_repair_crcs() {
+        let chunks: Vec<Chunk> = vec![ihdr(), chunk("tEXt", b"a\0b"), idat()];
+        let mut bytes: Vec<u8> = Png::from_chunks(chunks).as_bytes();
+        let text_end: usize = 8 + 25 + 15;
+        corrupt_crc(&mut bytes, text_end);
+
+        let (png, changes) = Png::repair(&bytes, RepairOptions::default()).unwrap();
+        assert_eq!(chunk_types(&png), vec!["IHDR", "IDAT", "IEND"]);
+        assert!(matches!(changes[0], RepairChange::Recovered(Diagnostic { recovery: Recovery::SkippedChunk, .. })));
+        assert!(matches!(changes[1], RepairChange::AppendedIend));
+
+        let options = RepairOptions { recompute_ancillary_crcs: true, ..RepairOptions::default() };
+        let (png, _) = Png::repair(&bytes, options).unwrap();
+        assert_eq!(chunk_types(&png), vec!["IHDR", "tEXt", "IDAT", "IEND"]);
+
+        corrupt_crc(&mut bytes, text_end + 21);
+        assert!(matches!(Png::repair(&bytes, options), Err(PngError::CrcMismatch { .. })));
+
+        let options = RepairOptions { recompute_critical_crcs: true, ..options };
+        let (png, _) = Png::repair(&bytes, options).unwrap();
+        assert_eq!(png.chunks()[2].crc(), idat().crc());
+    }
+
+    #[test]
+    fn test_repair_unsafe_to_copy() {
+        let palette: Chunk = chunk("PLTE", &[0, 0, 0]);
+        let bytes: Vec<u8> = Png::from_chunks(vec![ihdr(), chunk("prVT", &[]), idat(), chunk("IEND", &[])]).as_bytes();
+        let (png, changes) = Png::repair(&bytes, RepairOptions::default()).unwrap();
+
+        assert!(changes.is_empty());
+        assert_eq!(png.chunks().len(), 4);
+
+        let bytes: Vec<u8> = Png::from_chunks(vec![ihdr(), chunk("prVT", &[]), palette, idat(), chunk("IEND", &[])]).as_bytes();
+        let (png, changes) = Png::repair(&bytes, RepairOptions::default()).unwrap();
+
+        assert_eq!(chunk_types(&png), vec!["IHDR", "IDAT", "IEND"]);
+        assert!(matches!(
+            changes[..],
+            [
+                RepairChange::DroppedChunk { violation: StructureViolation::UnexpectedPalette, .. },
+                RepairChange::DroppedUnsafeToCopy { offset: 33, .. }
+            ]
+        ));
+
+        let options = RepairOptions { keep_unsafe_to_copy: true, ..RepairOptions::default() };
+        let (png, _) = Png::repair(&bytes, options).unwrap();
+        assert_eq!(chunk_types(&png), vec!["IHDR", "prVT", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_unrepairable() {
+        let bytes: Vec<u8> = Png::from_chunks(vec![ihdr(), chunk("IEND", &[])]).as_bytes();
+
+        assert!(matches!(
+            Png::repair(&bytes, RepairOptions::default()),
+            Err(PngError::InvalidStructure { violation: StructureViolation::MissingChunk("IDAT"), .. })
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::png::chunk_specs::IHDR;
+use crate::png::{Chunk, ChunkType, PngError, KNOWN_CRITICAL_CHUNKS};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Where a chunk type may appear relative to the PLTE and IDAT chunks.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Position {
+    BeforePlteAndIdat,
+    AfterPlteBeforeIdat,
+    BeforeIdat,
+    Anywhere
+}
+
+/// The ordering and multiplicity rules of the PNG spec, for chunk types
+/// other than IHDR, PLTE, IDAT, and IEND.
+/// https://www.w3.org/TR/png-3/#5ChunkOrdering
+const ORDERING_RULES: [(&str, Position, bool); 24] = [
+    ("cHRM", Position::BeforePlteAndIdat, true),
+    ("cICP", Position::BeforePlteAndIdat, true),
+    ("cLLI", Position::BeforePlteAndIdat, true),
+    ("gAMA", Position::BeforePlteAndIdat, true),
+    ("iCCP", Position::BeforePlteAndIdat, true),
+    ("mDCV", Position::BeforePlteAndIdat, true),
+    ("sBIT", Position::BeforePlteAndIdat, true),
+    ("sRGB", Position::BeforePlteAndIdat, true),
+    ("bKGD", Position::AfterPlteBeforeIdat, true),
+    ("hIST", Position::AfterPlteBeforeIdat, true),
+    ("tRNS", Position::AfterPlteBeforeIdat, true),
+    ("eXIf", Position::Anywhere, true),
+    ("oFFs", Position::BeforeIdat, true),
+    ("pCAL", Position::BeforeIdat, true),
+    ("pHYs", Position::BeforeIdat, true),
+    ("sCAL", Position::BeforeIdat, true),
+    ("sPLT", Position::BeforeIdat, false),
+    ("sTER", Position::BeforeIdat, true),
+    ("tIME", Position::Anywhere, true),
+    ("gIFg", Position::Anywhere, false),
+    ("gIFx", Position::Anywhere, false),
+    ("iTXt", Position::Anywhere, false),
+    ("tEXt", Position::Anywhere, false),
+    ("zTXt", Position::Anywhere, false)
+];
+
+/// A violation of the chunk ordering and multiplicity rules of the PNG spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructureViolation {
+    /// A required chunk is missing. PLTE is only required for indexed-color images.
+    MissingChunk(&'static str),
+    /// The IHDR chunk is not the first chunk.
+    IhdrNotFirst,
+    /// A chunk follows the IEND chunk.
+    ChunkAfterIend(ChunkType),
+    /// The IDAT chunks are separated by other chunks.
+    IdatNotContiguous,
+    /// A PLTE chunk appears in a grayscale image.
+    UnexpectedPalette,
+    /// The chunk must come before the first chunk of the given type.
+    MustPrecede(ChunkType, &'static str),
+    /// The chunk must come after the chunk of the given type.
+    MustFollow(ChunkType, &'static str),
+    /// A chunk type that may appear at most once appears again.
+    Duplicate(ChunkType)
+}
+
+impl fmt::Display for StructureViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructureViolation::MissingChunk(chunk_type) => write!(f, "Missing required {} chunk.", chunk_type),
+            StructureViolation::IhdrNotFirst => write!(f, "IHDR must be the first chunk."),
+            StructureViolation::ChunkAfterIend(chunk_type) => write!(f, "{} appears after IEND.", chunk_type),
+            StructureViolation::IdatNotContiguous => write!(f, "IDAT chunks must be consecutive."),
+            StructureViolation::UnexpectedPalette => write!(f, "PLTE must not appear in grayscale images."),
+            StructureViolation::MustPrecede(chunk_type, other) => write!(f, "{} must come before {}.", chunk_type, other),
+            StructureViolation::MustFollow(chunk_type, other) => write!(f, "{} must come after {}.", chunk_type, other),
+            StructureViolation::Duplicate(chunk_type) => write!(f, "{} may appear at most once.", chunk_type)
+        }
+    }
+}
+
+/// Where a chunk type may appear, and whether it may appear at most once.
+/// Unknown chunk types may appear anywhere, any number of times.
+pub(crate) fn ordering_rule(chunk_type: &ChunkType) -> (Position, bool) {
+    match &chunk_type.bytes() {
+        b"IHDR" | b"IEND" => (Position::Anywhere, true),
+        b"PLTE" => (Position::BeforeIdat, true),
+        b"IDAT" => (Position::Anywhere, false),
+        bytes => ORDERING_RULES
+            .iter()
+            .find(|(name, _, _)| name.as_bytes() == bytes)
+            .map(|(_, position, once)| (*position, *once))
+            .unwrap_or((Position::Anywhere, false))
+    }
+}
+
+/// Whether the PNG spec defines `chunk_type`.
+pub(crate) fn is_defined(chunk_type: &ChunkType) -> bool {
+    let bytes: [u8; 4] = chunk_type.bytes();
+
+    KNOWN_CRITICAL_CHUNKS
+        .iter()
+        .chain(ORDERING_RULES.iter().map(|(name, _, _)| name))
+        .any(|name| name.as_bytes() == bytes)
+}
+
+/// Checks a sequence of chunks against the ordering and multiplicity rules
+/// of the PNG spec. Returns every violation found, in chunk order. Chunks
+/// are assumed to be laid out consecutively after the signature.
+pub(crate) fn check_structure(chunks: &[Chunk]) -> Vec<PngError> {
+    let offsets: Vec<usize> = chunks
+        .iter()
+        .scan(8, |offset, chunk| {
+            let start: usize = *offset;
+            *offset += chunk.data().len() + 12;
+            Some(start)
+        })
+        .collect();
+
+    check_structure_at(chunks, &offsets)
+}
+
+/// Checks a sequence of chunks that start at the given offsets, which may
+/// not be consecutive if damaged data was skipped between them.
+pub(crate) fn check_structure_at(chunks: &[Chunk], offsets: &[usize]) -> Vec<PngError> {
+    // violations for missing chunks are reported where the last chunk ends
+    let end: usize = chunks.last().map_or(8, |c| offsets[chunks.len() - 1] + c.data().len() + 12);
+    let offsets: Vec<usize> = offsets.iter().copied().chain(Some(end)).collect();
+
+    let mut violations: Vec<(usize, StructureViolation)> = Vec::new();
+    let position = |chunk_type: &str| chunks
+        .iter()
+        .position(|c| c.chunk_type().bytes() == chunk_type.as_bytes());
+
+    let ihdr: Option<usize> = position("IHDR");
+    let plte: Option<usize> = position("PLTE");
+    let first_idat: Option<usize> = position("IDAT");
+    let iend: Option<usize> = position("IEND");
+    let color_type: Option<u8> = ihdr
+        .and_then(|idx| IHDR::try_from(chunks[idx].clone()).ok())
+        .map(|header| header.color_type());
+
+    match ihdr {
+        None => violations.push((0, StructureViolation::MissingChunk("IHDR"))),
+        Some(idx) if idx != 0 => violations.push((idx, StructureViolation::IhdrNotFirst)),
+        _ => ()
+    }
+
+    match (color_type, plte) {
+        (Some(3), None) => violations.push((first_idat.unwrap_or(chunks.len()), StructureViolation::MissingChunk("PLTE"))),
+        (Some(0), Some(idx)) | (Some(4), Some(idx)) => violations.push((idx, StructureViolation::UnexpectedPalette)),
+        _ => ()
+    }
+
+    if first_idat.is_none() {
+        violations.push((iend.unwrap_or(chunks.len()), StructureViolation::MissingChunk("IDAT")));
+    }
+
+    let mut seen: HashSet<[u8; 4]> = HashSet::new();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let chunk_type: &ChunkType = chunk.chunk_type();
+        let is_repeat: bool = !seen.insert(chunk_type.bytes());
+        let after_idat: bool = first_idat.is_some_and(|first| idx > first);
+        let before_plte: bool = plte.is_some_and(|plte| idx < plte);
+        let after_plte: bool = plte.is_some_and(|plte| idx > plte);
+
+        if iend.is_some_and(|iend| idx > iend) {
+            violations.push((idx, StructureViolation::ChunkAfterIend(chunk_type.clone())));
+            continue;
+        }
+
+        if chunk_type.bytes() == *b"IDAT" && is_repeat && chunks[idx - 1].chunk_type() != chunk_type {
+            violations.push((idx, StructureViolation::IdatNotContiguous));
+        }
+
+        let (position, once): (Position, bool) = ordering_rule(chunk_type);
+
+        if once && is_repeat {
+            violations.push((idx, StructureViolation::Duplicate(chunk_type.clone())));
+        }
+
+        match position {
+            Position::BeforePlteAndIdat if after_plte =>
+                violations.push((idx, StructureViolation::MustPrecede(chunk_type.clone(), "PLTE"))),
+            Position::AfterPlteBeforeIdat if before_plte =>
+                violations.push((idx, StructureViolation::MustFollow(chunk_type.clone(), "PLTE"))),
+            Position::Anywhere => (),
+            _ if after_idat =>
+                violations.push((idx, StructureViolation::MustPrecede(chunk_type.clone(), "IDAT"))),
+            _ => ()
+        }
+    }
+
+    if iend.is_none() {
+        violations.push((chunks.len(), StructureViolation::MissingChunk("IEND")));
+    }
+
+    violations.sort_by_key(|(idx, _)| *idx);
+    violations
+        .into_iter()
+        .map(|(idx, violation)| PngError::InvalidStructure { offset: offsets[idx], chunk_index: idx, violation })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str) -> Chunk {
+        let data: Vec<u8> = match chunk_type {
+            "IHDR" => vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 3, 0, 0, 0],
+            _ => Vec::new()
+        };
+
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data)
+    }
+
+    fn violations(chunk_types: &[&str]) -> Vec<(usize, StructureViolation)> {
+        let chunks: Vec<Chunk> = chunk_types.iter().map(|t| chunk(t)).collect();
+
+        check_structure(&chunks)
+            .into_iter()
+            .map(|error| match error {
+                PngError::InvalidStructure { chunk_index, violation, .. } => (chunk_index, violation),
+                error => panic!("Unexpected error {}", error)
+            })
+            .collect()
+    }
+
+    fn chunk_type(chunk_type: &str) -> ChunkType {
+        ChunkType::from_str(chunk_type).unwrap()
+    }
+
+    #[test]
+    fn test_valid_structure() {
+        assert!(violations(&["IHDR", "gAMA", "PLTE", "tRNS", "pHYs", "IDAT", "IDAT", "tEXt", "tEXt", "IEND"]).is_empty());
+    }
+
+    #[test]
+    fn test_required_chunks() {
+        assert_eq!(violations(&["gAMA"]), vec![
+            (0, StructureViolation::MissingChunk("IHDR")),
+            (1, StructureViolation::MissingChunk("IDAT")),
+            (1, StructureViolation::MissingChunk("IEND"))
+        ]);
+        assert_eq!(violations(&["IHDR", "IDAT", "IEND"]), vec![(1, StructureViolation::MissingChunk("PLTE"))]);
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert_eq!(violations(&["IHDR", "PLTE", "gAMA", "bKGD", "IDAT", "tEXt", "IDAT", "pHYs", "IEND", "tIME"]), vec![
+            (2, StructureViolation::MustPrecede(chunk_type("gAMA"), "PLTE")),
+            (6, StructureViolation::IdatNotContiguous),
+            (7, StructureViolation::MustPrecede(chunk_type("pHYs"), "IDAT")),
+            (9, StructureViolation::ChunkAfterIend(chunk_type("tIME")))
+        ]);
+        assert_eq!(violations(&["IHDR", "tRNS", "PLTE", "IDAT", "PLTE", "IEND"]), vec![
+            (1, StructureViolation::MustFollow(chunk_type("tRNS"), "PLTE")),
+            (4, StructureViolation::Duplicate(chunk_type("PLTE"))),
+            (4, StructureViolation::MustPrecede(chunk_type("PLTE"), "IDAT"))
+        ]);
+    }
+
+    #[test]
+    fn test_offsets() {
+        let chunks: Vec<Chunk> = vec![chunk("IHDR"), chunk("PLTE"), chunk("IDAT"), chunk("sRGB"), chunk("IEND")];
+
+        match &check_structure(&chunks)[..] {
+            [PngError::InvalidStructure { offset, chunk_index: 3, .. }] => assert_eq!(*offset, 8 + 25 + 12 + 12),
+            errors => panic!("Unexpected errors {:?}", errors)
+        }
+    }
+}
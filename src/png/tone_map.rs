@@ -0,0 +1,297 @@
+use crate::png::chunk_specs::{CICP, CLLI, IHDR, MDCV};
+use crate::png::{Chunk, ChunkType, Image, Png};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The luminance of SDR reference white in cd/m², as given by ITU-R BT.2408.
+pub const SDR_REFERENCE_WHITE: f64 = 203.0;
+
+/// The peak luminance assumed when neither `cLLI` nor `mDCV` is present.
+pub const DEFAULT_PEAK_LUMINANCE: f64 = 1000.0;
+
+/// Converts linear BT.2020 RGB into linear BT.709 RGB (ITU-R BT.2087).
+const BT2020_TO_BT709: [[f64; 3]; 3] = [
+    [1.6605, -0.5876, -0.0728],
+    [-0.1246, 1.1329, -0.0083],
+    [-0.0182, -0.1006, 1.1187]
+];
+
+/// Converts linear Display P3 RGB into linear BT.709 RGB.
+const DISPLAY_P3_TO_BT709: [[f64; 3]; 3] = [
+    [1.2249, -0.2247, 0.0],
+    [-0.0420, 1.0419, 0.0],
+    [-0.0197, -0.0786, 1.0979]
+];
+
+/// The operator used to compress HDR luminance into the SDR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// Clips every luminance above SDR reference white.
+    Clip,
+    /// The extended Reinhard operator, which maps the peak luminance to white.
+    Reinhard,
+    /// The Hable (Uncharted 2) filmic curve, with its white point at the peak
+    /// luminance.
+    Hable
+}
+
+impl FromStr for ToneMapOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "clip" => Ok(ToneMapOperator::Clip),
+            "reinhard" => Ok(ToneMapOperator::Reinhard),
+            "hable" => Ok(ToneMapOperator::Hable),
+            _ => Err(format!("Unknown tone mapping operator '{}'. Expected clip, reinhard, or hable.", s))
+        }
+    }
+}
+
+impl ToneMapOperator {
+    /// Maps a luminance relative to SDR reference white into the range 0-1,
+    /// given the peak luminance relative to SDR reference white.
+    pub fn apply(&self, luminance: f64, peak: f64) -> f64 {
+        let peak: f64 = peak.max(1.0);
+
+        match self {
+            ToneMapOperator::Clip => luminance.min(1.0),
+            ToneMapOperator::Reinhard => {
+                (luminance * (1.0 + luminance / (peak * peak)) / (1.0 + luminance)).min(1.0)
+            },
+            ToneMapOperator::Hable => (hable(luminance) / hable(peak)).min(1.0)
+        }
+    }
+}
+
+/// Converts a PQ or HLG BT.2100 image tagged with `cICP` into an 8-bit sRGB
+/// preview. The peak luminance is read from `cLLI` or `mDCV` when present,
+/// unless `peak_luminance` overrides it. The result holds the `IHDR`,
+/// `sRGB`, `IDAT`, and `IEND` chunks.
+pub fn tone_map_to_srgb(png: &Png, operator: ToneMapOperator, peak_luminance: Option<f64>)
+        -> Result<Png, String> {
+    let cicp: CICP = match png.chunk_by_type("cICP") {
+        Some(chunk) => CICP::try_from(chunk.clone())?,
+        None => return Err("PNG file does not contain a cICP chunk.".into())
+    };
+
+    if !cicp.is_pq() && !cicp.is_hlg() {
+        return Err(format!("cICP transfer function {} is neither PQ nor HLG.", cicp.transfer_function()));
+    }
+
+    let matrix: Option<[[f64; 3]; 3]> = match cicp.colour_primaries() {
+        CICP::PRIMARIES_BT709 => None,
+        CICP::PRIMARIES_BT2020 => Some(BT2020_TO_BT709),
+        CICP::PRIMARIES_DISPLAY_P3 => Some(DISPLAY_P3_TO_BT709),
+        primaries => return Err(format!("cICP colour primaries {} are not supported.", primaries))
+    };
+
+    let peak: f64 = match peak_luminance {
+        Some(peak) => peak,
+        None => content_peak_luminance(png)?
+    };
+
+    if !(peak > 0.0 && peak.is_finite()) {
+        return Err(format!("Peak luminance {} cd/m2 must be positive.", peak));
+    }
+
+    let image: Image = Image::decode(png)?;
+    let max_sample: f64 = ((1u32 << image.bit_depth()) - 1) as f64;
+    let (black, white): (f64, f64) = match cicp.is_video_full_range() {
+        true => (0.0, max_sample),
+        false => {
+            let scale: f64 = (1u32 << (image.bit_depth() - 8)) as f64;
+            (16.0 * scale, 235.0 * scale)
+        }
+    };
+
+    let channels: usize = IHDR::new(image.width(), image.height(), image.bit_depth(), image.color_type(), 0)?.channels();
+    let color_channels: usize = if image.has_alpha() { channels - 1 } else { channels };
+    let mut samples: Vec<u16> = Vec::new();
+
+    for pixel in image.samples().chunks_exact(channels) {
+        let signal = |sample: u16| ((sample as f64 - black) / (white - black)).clamp(0.0, 1.0);
+
+        let mut rgb: [f64; 3] = match color_channels {
+            1 => [signal(pixel[0]); 3],
+            _ => [signal(pixel[0]), signal(pixel[1]), signal(pixel[2])]
+        };
+
+        // convert the non-linear signal into display light in cd/m²
+        rgb = match cicp.is_pq() {
+            true => [pq_eotf(rgb[0]), pq_eotf(rgb[1]), pq_eotf(rgb[2])],
+            false => hlg_eotf(rgb, peak)
+        };
+
+        if let Some(m) = matrix {
+            rgb = [
+                m[0][0] * rgb[0] + m[0][1] * rgb[1] + m[0][2] * rgb[2],
+                m[1][0] * rgb[0] + m[1][1] * rgb[1] + m[1][2] * rgb[2],
+                m[2][0] * rgb[0] + m[2][1] * rgb[1] + m[2][2] * rgb[2]
+            ];
+        }
+
+        // tone map the luminance and scale each channel to preserve the hue
+        let relative: [f64; 3] = [
+            rgb[0].max(0.0) / SDR_REFERENCE_WHITE,
+            rgb[1].max(0.0) / SDR_REFERENCE_WHITE,
+            rgb[2].max(0.0) / SDR_REFERENCE_WHITE
+        ];
+        let luminance: f64 = 0.2126 * relative[0] + 0.7152 * relative[1] + 0.0722 * relative[2];
+        let scale: f64 = match luminance > 0.0 {
+            true => operator.apply(luminance, peak / SDR_REFERENCE_WHITE) / luminance,
+            false => 0.0
+        };
+
+        for channel in relative.iter() {
+            samples.push((srgb_oetf((channel * scale).min(1.0)) * 255.0).round() as u16);
+        }
+
+        if image.has_alpha() {
+            samples.push((pixel[channels - 1] as f64 / max_sample * 255.0).round() as u16);
+        }
+    }
+
+    let color_type: u8 = if image.has_alpha() { 6 } else { 2 };
+    let mut sdr: Png = Image::new(image.width(), image.height(), 8, color_type, samples)?.encode()?;
+
+    // rendering intent 0 (perceptual)
+    sdr.insert_chunk_before(Chunk::new(ChunkType::from_str("sRGB")?, vec![0]), &["IDAT"]);
+
+    Ok(sdr)
+}
+
+/// The peak luminance of the content in cd/m², taken from MaxCLL in `cLLI`
+/// or the maximum luminance in `mDCV`, or `DEFAULT_PEAK_LUMINANCE`.
+pub fn content_peak_luminance(png: &Png) -> Result<f64, String> {
+    if let Some(chunk) = png.chunk_by_type("cLLI") {
+        let clli: CLLI = CLLI::try_from(chunk.clone())?;
+        if clli.max_content_light_level() > 0 {
+            return Ok(clli.max_content_light_level_nits());
+        }
+    }
+
+    if let Some(chunk) = png.chunk_by_type("mDCV") {
+        return Ok(MDCV::try_from(chunk.clone())?.max_luminance_nits());
+    }
+
+    Ok(DEFAULT_PEAK_LUMINANCE)
+}
+
+/// The SMPTE ST 2084 (PQ) EOTF, mapping a signal to display light in cd/m².
+fn pq_eotf(signal: f64) -> f64 {
+    const M1: f64 = 2610.0 / 16384.0;
+    const M2: f64 = 2523.0 / 4096.0 * 128.0;
+    const C1: f64 = 3424.0 / 4096.0;
+    const C2: f64 = 2413.0 / 4096.0 * 32.0;
+    const C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+    let p: f64 = signal.powf(1.0 / M2);
+    10000.0 * ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1)
+}
+
+/// The BT.2100 HLG EOTF for a display with the given peak luminance, mapping
+/// a signal to display light in cd/m².
+fn hlg_eotf(signal: [f64; 3], peak: f64) -> [f64; 3] {
+    const A: f64 = 0.17883277;
+    const B: f64 = 0.28466892;
+    const C: f64 = 0.55991073;
+
+    let inverse_oetf = |e: f64| match e <= 0.5 {
+        true => e * e / 3.0,
+        false => (((e - C) / A).exp() + B) / 12.0
+    };
+
+    let scene: [f64; 3] = [inverse_oetf(signal[0]), inverse_oetf(signal[1]), inverse_oetf(signal[2])];
+    let luminance: f64 = 0.2627 * scene[0] + 0.6780 * scene[1] + 0.0593 * scene[2];
+    let gamma: f64 = 1.2 + 0.42 * (peak / 1000.0).log10();
+    let ootf: f64 = peak * luminance.powf(gamma - 1.0);
+
+    [ootf * scene[0], ootf * scene[1], ootf * scene[2]]
+}
+
+/// The sRGB OETF, mapping linear light in the range 0-1 to a signal.
+fn srgb_oetf(linear: f64) -> f64 {
+    match linear <= 0.0031308 {
+        true => linear * 12.92,
+        false => 1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The Hable (Uncharted 2) filmic curve.
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_hdr_png(cicp: CICP, samples: Vec<u16>) -> Png {
+        let mut png = Image::new(2, 1, 16, 2, samples).unwrap().encode().unwrap();
+        png.insert_chunk_before(cicp.to_chunk(), &["IDAT"]);
+        png
+    }
+
+    #[test]
+    fn test_pq_eotf() {
+        assert_eq!(pq_eotf(0.0), 0.0);
+        assert!((pq_eotf(1.0) - 10000.0).abs() < 1e-6);
+        // 203 cd/m² is encoded at a PQ signal of roughly 0.58
+        assert!((pq_eotf(0.5807) - 203.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_operators() {
+        for operator in [ToneMapOperator::Clip, ToneMapOperator::Reinhard, ToneMapOperator::Hable].iter() {
+            assert_eq!(operator.apply(0.0, 5.0), 0.0);
+            assert!(operator.apply(5.0, 5.0) <= 1.0);
+            assert!(operator.apply(0.5, 5.0) < operator.apply(1.0, 5.0));
+        }
+
+        assert!((ToneMapOperator::Reinhard.apply(5.0, 5.0) - 1.0).abs() < 1e-9);
+        assert!((ToneMapOperator::Hable.apply(5.0, 5.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tone_map_pq() {
+        let cicp = CICP::new(CICP::PRIMARIES_BT2020, CICP::TRANSFER_PQ, 0, true).unwrap();
+        // a black pixel and a peak white (10000 cd/m²) pixel
+        let png = testing_hdr_png(cicp, vec![0, 0, 0, 65535, 65535, 65535]);
+
+        let sdr = tone_map_to_srgb(&png, ToneMapOperator::Reinhard, Some(10000.0)).unwrap();
+        assert!(sdr.chunk_by_type("sRGB").is_some());
+
+        let image = Image::decode(&sdr).unwrap();
+        assert_eq!(image.bit_depth(), 8);
+        assert_eq!(image.samples(), &[0, 0, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_content_peak_luminance() {
+        let cicp = CICP::new(CICP::PRIMARIES_BT2020, CICP::TRANSFER_HLG, 0, true).unwrap();
+        let mut png = testing_hdr_png(cicp, vec![0; 6]);
+        assert_eq!(content_peak_luminance(&png).unwrap(), DEFAULT_PEAK_LUMINANCE);
+
+        let mdcv = MDCV::new([(0, 0); 3], (0, 0), 40_000_000, 50).unwrap();
+        png.insert_chunk_before(mdcv.to_chunk(), &["IDAT"]);
+        assert_eq!(content_peak_luminance(&png).unwrap(), 4000.0);
+
+        png.insert_chunk_before(CLLI::new(6_000_000, 0).unwrap().to_chunk(), &["IDAT"]);
+        assert_eq!(content_peak_luminance(&png).unwrap(), 600.0);
+    }
+
+    #[test]
+    fn test_tone_map_requires_hdr_transfer() {
+        let cicp = CICP::new(CICP::PRIMARIES_BT709, CICP::TRANSFER_SRGB, 0, true).unwrap();
+        let png = testing_hdr_png(cicp, vec![0; 6]);
+        assert!(tone_map_to_srgb(&png, ToneMapOperator::Clip, None).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+use crate::png::{ChunkType, Png, PngError};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+
+/// The most ways of placing half of the line endings of a chunk that were
+/// restored the wrong way that are tried before giving up on the chunk.
+const MAX_CANDIDATES: usize = 1 << 21;
+
+/// A corruption caused by transferring a PNG file in text mode, e.g. over
+/// FTP in ASCII mode. The PNG signature holds CR, LF, and high bit bytes so
+/// that these corruptions can be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferCorruption {
+    /// CRLF line endings were converted to LF.
+    CrlfToLf,
+    /// LF line endings were converted to CRLF.
+    LfToCrlf,
+    /// The high bit of every byte was cleared by a 7-bit transfer.
+    HighBitStripped
+}
+
+/// The signatures of files mangled by each transfer corruption. Converting
+/// LF to CRLF may or may not also convert the LF of existing CRLF pairs.
+const MANGLED_SIGNATURES: [(TransferCorruption, &[u8]); 4] = [
+    (TransferCorruption::CrlfToLf, &[137, 80, 78, 71, 10, 26, 10]),
+    (TransferCorruption::LfToCrlf, &[137, 80, 78, 71, 13, 10, 26, 13, 10]),
+    (TransferCorruption::LfToCrlf, &[137, 80, 78, 71, 13, 13, 10, 26, 13, 10]),
+    (TransferCorruption::HighBitStripped, &[9, 80, 78, 71, 13, 10, 26, 10])
+];
+
+impl TransferCorruption {
+    /// Diagnoses the transfer corruption that mangled the signature at the
+    /// start of `bytes`, if any.
+    pub fn detect(bytes: &[u8]) -> Option<TransferCorruption> {
+        mangled_signature(bytes).map(|(corruption, _)| corruption)
+    }
+
+    /// Returns true if the corruption can be undone with
+    /// `undo_transfer_corruption`.
+    pub fn is_reversible(&self) -> bool {
+        *self != TransferCorruption::HighBitStripped
+    }
+}
+
+impl fmt::Display for TransferCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferCorruption::CrlfToLf => write!(f, "converted CRLF line endings to LF"),
+            TransferCorruption::LfToCrlf => write!(f, "converted LF line endings to CRLF"),
+            TransferCorruption::HighBitStripped => write!(f, "stripped the high bit of every byte")
+        }
+    }
+}
+
+/// Finds the mangled signature at the start of `bytes`, returning the
+/// corruption and the length of the signature.
+fn mangled_signature(bytes: &[u8]) -> Option<(TransferCorruption, usize)> {
+    MANGLED_SIGNATURES
+        .iter()
+        .find(|(_, signature)| bytes.starts_with(signature))
+        .map(|(corruption, signature)| (*corruption, signature.len()))
+}
+
+/// Undoes the line ending conversion of a text-mode transfer across a whole
+/// file, as diagnosed from its signature. Line endings are ambiguous once
+/// converted, so each chunk is restored the likely way (see `Restoration`),
+/// except for as many line endings as its length shows were restored the
+/// wrong way, which are searched for until its CRC matches. Any bytes after
+/// IEND are restored the likely way, as they have no CRC.
+///
+/// Returns the restored bytes and the corruption that was undone. Bytes with
+/// an intact signature are returned unchanged. A signature that was mangled
+/// irreversibly, or a chunk that can't be restored, is an error.
+pub fn undo_transfer_corruption(bytes: &[u8]) -> Result<(Vec<u8>, Option<TransferCorruption>), PngError> {
+    let (corruption, signature_length): (TransferCorruption, usize) = match mangled_signature(bytes) {
+        Some((corruption, length)) if corruption.is_reversible() => (corruption, length),
+        _ => return Png::check_signature(bytes).map(|_| (bytes.to_vec(), None))
+    };
+
+    let restoration = Restoration::new(corruption, &bytes[signature_length..]);
+    let crc = Crc32::new();
+    let mut restored: Vec<u8> = Png::STANDARD_HEADER.to_vec();
+    let mut start: usize = 0;
+    let mut index: usize = 0;
+
+    while start < restoration.bytes.len() {
+        let (chunk, consumed): (Vec<u8>, usize) = restoration
+            .restore_chunk(&crc, start)
+            .map_err(|e| e.relocate(signature_length + restoration.origins[start], index))?;
+        let is_iend: bool = chunk[4..8] == *b"IEND";
+
+        restored.extend(chunk);
+        start += consumed;
+        index += 1;
+
+        if is_iend {
+            restored.extend_from_slice(&restoration.bytes[start..]);
+            break;
+        }
+    }
+
+    Ok((restored, Some(corruption)))
+}
+
+/// The bytes following a mangled signature, restored the likely way. Lone
+/// LFs are far more common than CRLF pairs in binary data, so LFs are kept
+/// as they are, while CRs that precede LFs are dropped.
+struct Restoration {
+    bytes: Vec<u8>,
+    /// The LFs that may have been preceded by a CR, in ascending order.
+    sites: Vec<usize>,
+    /// The offset in the corrupted bytes that each restored byte came from.
+    origins: Vec<usize>
+}
+
+impl Restoration {
+    fn new(corruption: TransferCorruption, corrupted: &[u8]) -> Restoration {
+        let mut restoration = Restoration { bytes: Vec::new(), sites: Vec::new(), origins: Vec::new() };
+
+        for (i, &byte) in corrupted.iter().enumerate() {
+            match corruption {
+                TransferCorruption::LfToCrlf if byte == CR && corrupted.get(i + 1) == Some(&LF) => {
+                    restoration.sites.push(restoration.bytes.len());
+                    continue;
+                },
+                TransferCorruption::CrlfToLf if byte == LF => restoration.sites.push(restoration.bytes.len()),
+                _ => ()
+            }
+
+            restoration.bytes.push(byte);
+            restoration.origins.push(i);
+        }
+
+        restoration
+    }
+
+    /// Restores the chunk at `start`, returning its original bytes and the
+    /// number of restored bytes it took up. Each line ending restored the
+    /// wrong way lost a CR, so their number is found from how far short of
+    /// its declared length the chunk is followed by the next chunk header.
+    /// Line endings within the length and type of the chunk are assumed to be
+    /// restored correctly.
+    fn restore_chunk(&self, crc: &Crc32, start: usize) -> Result<(Vec<u8>, usize), PngError> {
+        let bytes: &[u8] = &self.bytes[start..];
+        let truncated = |expected: usize| PngError::Truncated { offset: 0, chunk_index: 0, expected, available: bytes.len() };
+
+        let header: &[u8] = bytes.get(..8).ok_or_else(|| truncated(12))?;
+        let chunk_length: usize = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize + 12;
+        let chunk_type: ChunkType = ChunkType::try_from([header[4], header[5], header[6], header[7]])?;
+        let unrestorable = |reason: String| PngError::Unrestorable { offset: 0, chunk_index: 0, chunk_type: chunk_type.clone(), reason };
+
+        let sites: Vec<usize> = self.sites[self.sites.partition_point(|&s| s < start + 8)..]
+            .iter()
+            .map(|&s| s - start)
+            .take_while(|&s| s < chunk_length)
+            .collect();
+
+        let counts: Vec<usize> = (0..=sites.len())
+            .filter(|&count| sites.partition_point(|&s| s < chunk_length - count) >= count)
+            .filter(|&count| bytes.get(chunk_length - count..).is_some_and(|next| is_chunk_boundary(next, &chunk_type)))
+            .collect();
+
+        if counts.is_empty() {
+            return Err(match bytes.len() < chunk_length - sites.len() {
+                true => truncated(chunk_length),
+                false => unrestorable("its length does not lead to the next chunk.".into())
+            });
+        }
+
+        // the fewest line endings restored the wrong way are the most likely
+        let mut reason: Option<String> = None;
+
+        for count in counts {
+            let span: usize = chunk_length - count;
+            let sites: &[usize] = &sites[..sites.partition_point(|&s| s < span)];
+
+            match find_exceptions(crc, bytes, sites, count, chunk_length) {
+                Ok(Some(exceptions)) => {
+                    let chunk: Vec<u8> = (0..chunk_length).map(|r| restored_byte(bytes, &exceptions, r)).collect();
+                    return Ok((chunk, span));
+                },
+                Ok(None) => (),
+                Err(error) => {
+                    reason.get_or_insert(error);
+                }
+            }
+        }
+
+        Err(unrestorable(reason.unwrap_or_else(|| "no way of restoring its line endings matches its CRC.".into())))
+    }
+}
+
+/// Returns true if a chunk of the given type may be followed by `next`,
+/// which is either the end of the file or a plausible chunk header. Anything
+/// may follow the IEND chunk.
+fn is_chunk_boundary(next: &[u8], chunk_type: &ChunkType) -> bool {
+    if next.is_empty() || chunk_type.bytes() == *b"IEND" {
+        return true;
+    }
+
+    match next.get(..8) {
+        Some(header) => u32::from_be_bytes([header[0], header[1], header[2], header[3]]) <= i32::MAX as u32
+            && header[4..8].iter().all(ChunkType::is_valid_byte),
+        None => false
+    }
+}
+
+/// Finds the `count` of the `sites` at which a CR must be inserted for the
+/// chunk at the start of `bytes` to match its CRC, or fails if several ways
+/// match or there are too many ways to try.
+///
+/// Whether a chunk matches its CRC is an affine function of its bits, so the
+/// effect of inserting the n-th CR before each site can be computed once, and
+/// a way of restoring the chunk matches if the effects of its insertions sum
+/// to the mismatch of the chunk with every CR inserted before its start. The
+/// ways of placing the first and the last half of the CRs are enumerated
+/// separately, and matched against each other.
+fn find_exceptions(
+    crc: &Crc32,
+    bytes: &[u8],
+    sites: &[usize],
+    count: usize,
+    chunk_length: usize
+) -> Result<Option<Vec<usize>>, String> {
+    let (first, second): (usize, usize) = (count / 2, count - count / 2);
+    if binomial(sites.len(), first).max(binomial(sites.len(), second)) > MAX_CANDIDATES {
+        return Err(format!("{} of its {} line endings must be restored the other way, too many to search.",
+            count, sites.len()));
+    }
+
+    // the stored CRC follows the bytes it covers
+    let end: usize = chunk_length - 4;
+    let byte = |position: usize, shift: usize| position.checked_sub(shift).and_then(|p| bytes.get(p)).copied().unwrap_or(0);
+    let stored = |position: usize, value: u8| (value as u32) << (8 * (chunk_length - 1 - position));
+
+    let target: u32 = crc.checksum((4..end).map(|p| byte(p, count)))
+        ^ (end..chunk_length).fold(0, |sum, p| sum ^ stored(p, byte(p, count)));
+
+    // the effect of inserting the CR of each rank before each site
+    let effects: Vec<Vec<u32>> = (0..count)
+        .map(|rank| {
+            let (mut register, mut folded, mut position): (u32, u32, usize) = (0, 0, 4);
+
+            sites.iter().map(|&site| {
+                let inserted: usize = site + rank;
+                while position < inserted {
+                    let difference: u8 = byte(position, rank) ^ byte(position, rank + 1);
+                    match position < end {
+                        true => register = crc.update(register, difference),
+                        false => folded ^= stored(position, difference)
+                    }
+                    position += 1;
+                }
+
+                let replaced: u8 = CR ^ byte(inserted, rank + 1);
+                let effect: u32 = match inserted < end {
+                    true => crc.combine(crc.update(0, replaced), 0, end - inserted - 1),
+                    false => stored(inserted, replaced)
+                };
+                crc.combine(register, 0, end - inserted.min(end)) ^ folded ^ effect
+            }).collect()
+        })
+        .collect();
+
+    let mut halves: Vec<(u32, usize)> = Vec::new();
+    let mut picks: Vec<usize> = Vec::new();
+    for_each_combination(sites.len(), first, |picked| {
+        halves.push((picked.iter().enumerate().fold(0, |sum, (rank, &i)| sum ^ effects[rank][i]), halves.len()));
+        picks.extend_from_slice(picked);
+        true
+    });
+    halves.sort_unstable();
+
+    let mut found: Vec<Vec<usize>> = Vec::new();
+    for_each_combination(sites.len(), second, |picked| {
+        let needed: u32 = picked.iter().enumerate().fold(target, |sum, (rank, &i)| sum ^ effects[first + rank][i]);
+        let matches = &halves[halves.partition_point(|&(sum, _)| sum < needed)..];
+
+        for &(_, id) in matches.iter().take_while(|&&(sum, _)| sum == needed) {
+            let head: &[usize] = &picks[id * first..(id + 1) * first];
+            if head.last().is_none_or(|&last| picked.first().is_none_or(|&next| last < next)) {
+                found.push(head.iter().chain(picked).map(|&i| sites[i]).collect());
+            }
+        }
+
+        found.len() < 2
+    });
+
+    match found.len() {
+        0 | 1 => Ok(found.pop()),
+        _ => Err("several ways of restoring its line endings match its CRC.".into())
+    }
+}
+
+/// The number of ways to pick `k` of `n` items, or `usize::MAX` once it
+/// exceeds `MAX_CANDIDATES`.
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+
+    let mut ways: usize = 1;
+    for i in 0..k.min(n - k) {
+        ways = match ways.checked_mul(n - i) {
+            Some(product) if product / (i + 1) <= MAX_CANDIDATES => product / (i + 1),
+            _ => return usize::MAX
+        };
+    }
+
+    ways
+}
+
+/// Calls `visit` with every ascending pick of `count` of the indices below
+/// `n`, until it returns false.
+fn for_each_combination(n: usize, count: usize, mut visit: impl FnMut(&[usize]) -> bool) {
+    if count > n {
+        return;
+    }
+
+    let mut picked: Vec<usize> = (0..count).collect();
+    while visit(&picked) {
+        // advance the last index that can still move, and reset those after it
+        match (0..count).rev().find(|&i| picked[i] < n - count + i) {
+            Some(i) => {
+                picked[i] += 1;
+                for j in i + 1..count {
+                    picked[j] = picked[j - 1] + 1;
+                }
+            },
+            None => return
+        }
+    }
+}
+
+/// The byte at `position` once a CR is inserted before the byte at each of
+/// the ascending `exceptions`.
+fn restored_byte(bytes: &[u8], exceptions: &[usize], position: usize) -> u8 {
+    let mut shift: usize = 0;
+
+    for &site in exceptions {
+        match (site + shift).cmp(&position) {
+            Ordering::Equal => return CR,
+            Ordering::Greater => break,
+            Ordering::Less => shift += 1
+        }
+    }
+
+    bytes[position - shift]
+}
+
+/// The reflected CRC-32 polynomial used by PNG.
+const POLYNOMIAL: u32 = 0xedb88320;
+
+/// CRC-32 arithmetic that can combine the checksums of adjacent byte ranges,
+/// so the checksum of a chunk can be found for each way of restoring it
+/// without rereading the chunk. See `crc32_combine` in zlib.
+struct Crc32 {
+    table: [u32; 256],
+    /// x^(2^k) modulo the polynomial, for each k.
+    powers: [u32; 32]
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        let mut crc = Crc32 { table: [0; 256], powers: [0; 32] };
+
+        for (n, entry) in crc.table.iter_mut().enumerate() {
+            *entry = (0..8).fold(n as u32, |c, _| match c & 1 {
+                1 => POLYNOMIAL ^ (c >> 1),
+                _ => c >> 1
+            });
+        }
+
+        crc.powers[0] = 1 << 30;
+        for k in 1..32 {
+            crc.powers[k] = multiply(crc.powers[k - 1], crc.powers[k - 1]);
+        }
+
+        crc
+    }
+
+    /// Feeds a byte into a CRC register.
+    fn update(&self, register: u32, byte: u8) -> u32 {
+        self.table[((register ^ byte as u32) & 0xff) as usize] ^ (register >> 8)
+    }
+
+    /// The checksum of `bytes`.
+    fn checksum(&self, bytes: impl Iterator<Item = u8>) -> u32 {
+        !bytes.fold(!0, |register, byte| self.update(register, byte))
+    }
+
+    /// The checksum of `a` followed by `length` bytes with checksum `b`.
+    fn combine(&self, a: u32, b: u32, length: usize) -> u32 {
+        let mut power: u32 = 1 << 31;
+        let mut k: usize = 3;
+        let mut n: usize = length;
+
+        while n != 0 {
+            if n & 1 == 1 {
+                power = multiply(self.powers[k & 31], power);
+            }
+            n >>= 1;
+            k += 1;
+        }
+
+        multiply(power, a) ^ b
+    }
+}
+
+/// Multiplies two polynomials modulo the CRC-32 polynomial, in reflected bit
+/// order.
+fn multiply(a: u32, mut b: u32) -> u32 {
+    let mut product: u32 = 0;
+    let mut bit: u32 = 1 << 31;
+
+    while bit != 0 {
+        if a & bit != 0 {
+            product ^= b;
+        }
+        b = match b & 1 {
+            1 => (b >> 1) ^ POLYNOMIAL,
+            _ => b >> 1
+        };
+        bit >>= 1;
+    }
+
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Chunk;
+    use std::str::FromStr;
+
+    /// A PNG whose chunks hold both CRLF pairs and lone LFs.
+    fn original() -> Vec<u8> {
+        let chunk = |chunk_type: &str, data: &[u8]| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec());
+
+        Png::from_chunks(vec![
+            chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]),
+            chunk("tEXt", b"Comment\0one\r\ntwo\nthree\r\n"),
+            chunk("IDAT", &[120, 156, 99, 0, 0, 0, 1, 0, 1]),
+            chunk("IEND", &[])
+        ]).as_bytes()
+    }
+
+    fn crlf_to_lf(bytes: &[u8]) -> Vec<u8> {
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|&(i, &byte)| !(byte == CR && bytes.get(i + 1) == Some(&LF)))
+            .map(|(_, &byte)| byte)
+            .collect()
+    }
+
+    fn lf_to_crlf(bytes: &[u8], convert_crlf: bool) -> Vec<u8> {
+        bytes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &byte)| match byte {
+                LF if convert_crlf || i == 0 || bytes[i - 1] != CR => vec![CR, LF],
+                byte => vec![byte]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        let bytes: Vec<u8> = original();
+        let stripped: Vec<u8> = bytes.iter().map(|byte| byte & 0x7f).collect();
+
+        assert_eq!(TransferCorruption::detect(&bytes), None);
+        assert_eq!(TransferCorruption::detect(&crlf_to_lf(&bytes)), Some(TransferCorruption::CrlfToLf));
+        assert_eq!(TransferCorruption::detect(&lf_to_crlf(&bytes, false)), Some(TransferCorruption::LfToCrlf));
+        assert_eq!(TransferCorruption::detect(&lf_to_crlf(&bytes, true)), Some(TransferCorruption::LfToCrlf));
+        assert_eq!(TransferCorruption::detect(&stripped), Some(TransferCorruption::HighBitStripped));
+
+        match Png::try_from(crlf_to_lf(&bytes).as_ref()) {
+            Err(PngError::BadSignature { corruption, .. }) => assert_eq!(corruption, Some(TransferCorruption::CrlfToLf)),
+            _ => panic!("Expected a bad signature")
+        }
+    }
+
+    #[test]
+    fn test_undo() {
+        let bytes: Vec<u8> = original();
+
+        for corrupted in [crlf_to_lf(&bytes), lf_to_crlf(&bytes, false), lf_to_crlf(&bytes, true)] {
+            let (restored, corruption) = undo_transfer_corruption(&corrupted).unwrap();
+
+            assert!(corruption.is_some());
+            assert_eq!(restored, bytes);
+        }
+
+        let (restored, corruption) = undo_transfer_corruption(&bytes).unwrap();
+        assert_eq!((restored, corruption), (bytes.clone(), None));
+    }
+
+    #[test]
+    fn test_undo_many_line_endings() {
+        let text: Vec<u8> = (0..25u8).flat_map(|i| match i % 5 {
+            0 => vec![b'a' + i, CR, LF],
+            _ => vec![b'a' + i, LF]
+        }).collect();
+        let bytes: Vec<u8> = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]),
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), [b"Comment\0".to_vec(), text].concat()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![])
+        ]).as_bytes();
+
+        for corrupted in [crlf_to_lf(&bytes), lf_to_crlf(&bytes, false)] {
+            assert_eq!(undo_transfer_corruption(&corrupted).unwrap().0, bytes);
+        }
+    }
+
+    #[test]
+    fn test_undo_large_chunk() {
+        let data: Vec<u8> = (0..160_000).flat_map(|i| [b'a' + (i % 26) as u8, LF]).collect();
+        let bytes: Vec<u8> = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), data),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![])
+        ]).as_bytes();
+
+        assert_eq!(undo_transfer_corruption(&lf_to_crlf(&bytes, false)).unwrap().0, bytes);
+    }
+
+    #[test]
+    fn test_undo_failures() {
+        let bytes: Vec<u8> = original();
+        let stripped: Vec<u8> = bytes.iter().map(|byte| byte & 0x7f).collect();
+        let mut corrupted: Vec<u8> = crlf_to_lf(&bytes);
+        let text_data: usize = 7 + 25 + 8;
+        corrupted[text_data] ^= 0x20;
+
+        assert!(matches!(undo_transfer_corruption(&stripped), Err(PngError::BadSignature { .. })));
+        assert!(matches!(
+            undo_transfer_corruption(&corrupted),
+            Err(PngError::Unrestorable { chunk_index: 1, .. })
+        ));
+        assert!(undo_transfer_corruption(&corrupted).unwrap_err().to_string().starts_with("Could not restore chunk 1 (tEXt)"));
+    }
+}
@@ -0,0 +1,569 @@
+use crate::png::chunk_specs::ITXT;
+use crate::png::{Chunk, Png};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The `iTXt` keyword under which XMP packets are stored, as described in
+/// part 3 of the XMP specification.
+pub const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The namespaces that property names may use without being declared in the
+/// packet, keyed by their conventional prefix.
+const STANDARD_NAMESPACES: [(&str, &str); 6] = [
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("xmpRights", "http://ns.adobe.com/xap/1.0/rights/"),
+    ("photoshop", "http://ns.adobe.com/photoshop/1.0/"),
+    ("exif", "http://ns.adobe.com/exif/1.0/"),
+    ("tiff", "http://ns.adobe.com/tiff/1.0/")
+];
+
+/// The value of an XMP property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmpValue {
+    /// A simple text value.
+    Text(String),
+    /// An unordered array, such as `dc:subject`.
+    Bag(Vec<String>),
+    /// An ordered array, such as `dc:creator`.
+    Seq(Vec<String>),
+    /// Alternatives keyed by language tag, such as `dc:title`.
+    Alt(Vec<(String, String)>),
+    /// A structured value, kept as the XML it was read from.
+    Raw(String)
+}
+
+impl XmpValue {
+    /// The text of the `x-default` (or first) alternative of a language
+    /// alternative, or the text of a simple value.
+    pub fn default_text(&self) -> Option<&str> {
+        match self {
+            XmpValue::Text(text) => Some(text),
+            XmpValue::Alt(alternatives) => alternatives
+                .iter()
+                .find(|(lang, _)| lang == "x-default")
+                .or_else(|| alternatives.first())
+                .map(|(_, text)| text.as_str()),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for XmpValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmpValue::Text(text) | XmpValue::Raw(text) => write!(f, "{}", text),
+            XmpValue::Bag(items) | XmpValue::Seq(items) => write!(f, "{}", items.join("; ")),
+            XmpValue::Alt(alternatives) => {
+                let values: Vec<String> = alternatives
+                    .iter()
+                    .map(|(lang, text)| format!("[{}] {}", lang, text))
+                    .collect();
+                write!(f, "{}", values.join("; "))
+            }
+        }
+    }
+}
+
+/// A property of an XMP packet, identified by its namespace URI and name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmpProperty {
+    namespace: String,
+    prefix: String,
+    name: String,
+    value: XmpValue
+}
+
+impl XmpProperty {
+    /// The namespace URI of the property.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The qualified name of the property, such as `dc:title`.
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.prefix, self.name)
+    }
+
+    /// The value of the property.
+    pub fn value(&self) -> &XmpValue {
+        &self.value
+    }
+}
+
+/// An XMP metadata packet, parsed into a namespace-aware property map. See
+/// the XMP specification for more details.
+/// https://www.adobe.com/devnet/xmp.html
+#[derive(Debug, Clone, Default)]
+pub struct XmpPacket {
+    namespaces: BTreeMap<String, String>,
+    properties: Vec<XmpProperty>,
+    /// The packet this was parsed from. Everything outside its descriptions
+    /// is written back unchanged by `to_xml`.
+    source: Option<String>
+}
+
+impl XmpPacket {
+    /// Creates an empty packet.
+    pub fn new() -> XmpPacket {
+        XmpPacket::default()
+    }
+
+    /// Parses the properties of every `rdf:Description` in an XMP packet.
+    pub fn parse(xml: &str) -> Result<XmpPacket, String> {
+        let document = roxmltree::Document::parse(xml)
+            .map_err(|e| format!("Invalid XMP packet: {}", e))?;
+        let mut packet = XmpPacket { source: Some(xml.to_string()), ..XmpPacket::default() };
+
+        for description in descriptions(&document) {
+            for namespace in description.namespaces() {
+                if let Some(prefix) = namespace.name() {
+                    if namespace.uri() != RDF_NAMESPACE && namespace.uri() != XML_NAMESPACE {
+                        packet.namespaces.entry(prefix.to_string()).or_insert_with(|| namespace.uri().to_string());
+                    }
+                }
+            }
+
+            // simple properties may be written as attributes of the description
+            for attribute in description.attributes() {
+                if let Some(namespace) = attribute.namespace() {
+                    if namespace != RDF_NAMESPACE && namespace != XML_NAMESPACE {
+                        packet.insert(namespace, attribute.name(), XmpValue::Text(attribute.value().to_string()));
+                    }
+                }
+            }
+
+            for element in description.children().filter(|n| n.is_element()) {
+                if let Some(namespace) = element.tag_name().namespace() {
+                    packet.insert(namespace, element.tag_name().name(), parse_value(&element, xml));
+                }
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// Parses the XMP packet stored in the `iTXt` chunks of a PNG file.
+    /// Returns `None` if the file has no XMP packet.
+    pub fn from_png(png: &Png) -> Option<Result<XmpPacket, String>> {
+        png.typed_chunks::<ITXT>()
+            .into_iter()
+            .flatten()
+            .find(|itxt| itxt.keyword() == XMP_KEYWORD)
+            .map(|itxt| XmpPacket::parse(itxt.text()))
+    }
+
+    /// Lists the properties of this packet.
+    pub fn properties(&self) -> &[XmpProperty] {
+        &self.properties
+    }
+
+    /// Returns the value of a property given by its qualified name, such
+    /// as `dc:title`.
+    pub fn get(&self, name: &str) -> Option<&XmpValue> {
+        let (namespace, local) = self.resolve(name).ok()?;
+
+        self.properties
+            .iter()
+            .find(|p| p.namespace == namespace && p.name == local)
+            .map(|p| &p.value)
+    }
+
+    /// Sets the value of a property given by its qualified name. The prefix
+    /// must be declared in the packet or be one of the standard prefixes
+    /// `dc`, `xmp`, `xmpRights`, `photoshop`, `exif`, and `tiff`.
+    pub fn set(&mut self, name: &str, value: XmpValue) -> Result<(), String> {
+        let (namespace, local) = self.resolve(name)?;
+        let prefix: &str = name.split(':').next().unwrap_or_default();
+
+        self.namespaces.entry(prefix.to_string()).or_insert_with(|| namespace.clone());
+        self.insert(&namespace, &local, value);
+
+        Ok(())
+    }
+
+    /// Sets a property from one or more strings, keeping the kind of value
+    /// it already holds. New properties become language alternatives or
+    /// arrays where the XMP specification defines them as such, and simple
+    /// text otherwise.
+    pub fn set_values(&mut self, name: &str, values: Vec<String>) -> Result<(), String> {
+        let kind: XmpValue = match self.get(name) {
+            Some(existing) => existing.clone(),
+            None => match name {
+                "dc:title" | "dc:description" | "dc:rights" | "xmpRights:UsageTerms" => XmpValue::Alt(vec![]),
+                "dc:creator" | "dc:date" => XmpValue::Seq(vec![]),
+                "dc:subject" | "dc:contributor" | "dc:publisher" | "dc:language" => XmpValue::Bag(vec![]),
+                _ => XmpValue::Text(String::new())
+            }
+        };
+
+        let value: XmpValue = match (kind, values.as_slice()) {
+            (XmpValue::Bag(_), _) => XmpValue::Bag(values),
+            (XmpValue::Seq(_), _) => XmpValue::Seq(values),
+            (XmpValue::Alt(mut alternatives), [text]) => {
+                alternatives.retain(|(lang, _)| lang != "x-default");
+                alternatives.insert(0, ("x-default".to_string(), text.clone()));
+                XmpValue::Alt(alternatives)
+            },
+            (XmpValue::Text(_), [text]) => XmpValue::Text(text.clone()),
+            (XmpValue::Raw(_), _) => return Err(format!("XMP property '{}' holds a structured value.", name)),
+            _ => return Err(format!("XMP property '{}' holds a single value.", name))
+        };
+
+        self.set(name, value)
+    }
+
+    /// Removes a property given by its qualified name, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<XmpValue> {
+        let (namespace, local) = self.resolve(name).ok()?;
+        let idx: usize = self.properties
+            .iter()
+            .position(|p| p.namespace == namespace && p.name == local)?;
+
+        Some(self.properties.remove(idx).value)
+    }
+
+    /// The `dc:title` of the resource in the default language.
+    pub fn title(&self) -> Option<&str> {
+        self.get("dc:title")?.default_text()
+    }
+
+    /// The `dc:creator` entries of the resource.
+    pub fn creators(&self) -> Vec<&str> {
+        match self.get("dc:creator") {
+            Some(XmpValue::Seq(items)) | Some(XmpValue::Bag(items)) => items.iter().map(|s| s.as_str()).collect(),
+            Some(XmpValue::Text(text)) => vec![text.as_str()],
+            _ => vec![]
+        }
+    }
+
+    /// The `xmp:CreateDate` of the resource.
+    pub fn create_date(&self) -> Option<&str> {
+        self.get("xmp:CreateDate")?.default_text()
+    }
+
+    /// The `dc:rights` statement of the resource in the default language.
+    pub fn rights(&self) -> Option<&str> {
+        self.get("dc:rights")?.default_text()
+    }
+
+    /// Serializes this packet into a complete XMP packet. A packet that was
+    /// parsed keeps everything but its descriptions, which are replaced by a
+    /// single description holding every property.
+    pub fn to_xml(&self) -> String {
+        if let Some(source) = &self.source {
+            let document = roxmltree::Document::parse(source).expect("the source of a packet was parsed before");
+            let ranges: Vec<std::ops::Range<usize>> = descriptions(&document).map(|n| n.range()).collect();
+
+            if let Some(first) = ranges.first() {
+                let about: &str = descriptions(&document)
+                    .find_map(|n| n.attribute((RDF_NAMESPACE, "about")))
+                    .unwrap_or_default();
+
+                let mut xml: String = source[..first.start].to_string();
+                xml.push_str(self.description_xml(about).trim_start().trim_end_matches('\n'));
+
+                for (idx, range) in ranges.iter().enumerate() {
+                    let next: usize = ranges.get(idx + 1).map_or(source.len(), |r| r.start);
+                    xml.push_str(&source[range.end..next]);
+                }
+
+                return xml;
+            }
+        }
+
+        let mut xml: String = String::new();
+        xml.push_str("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+        xml.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n");
+        xml.push_str(&format!(" <rdf:RDF xmlns:rdf=\"{}\">\n", RDF_NAMESPACE));
+        xml.push_str(&self.description_xml(""));
+        xml.push_str(" </rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>");
+        xml
+    }
+
+    /// Serializes the properties of this packet into an `rdf:Description`.
+    fn description_xml(&self, about: &str) -> String {
+        let mut xml: String = String::new();
+        xml.push_str(&format!("  <rdf:Description rdf:about=\"{}\"", escape(about)));
+
+        for (prefix, namespace) in &self.namespaces {
+            xml.push_str(&format!("\n    xmlns:{}=\"{}\"", prefix, escape(namespace)));
+        }
+        xml.push_str(">\n");
+
+        for property in &self.properties {
+            let name: String = property.qualified_name();
+
+            match &property.value {
+                XmpValue::Text(text) => xml.push_str(&format!("   <{0}>{1}</{0}>\n", name, escape(text))),
+                XmpValue::Raw(raw) => xml.push_str(&format!("   {}\n", raw)),
+                XmpValue::Bag(items) | XmpValue::Seq(items) => {
+                    let container: &str = match property.value {
+                        XmpValue::Bag(_) => "rdf:Bag",
+                        _ => "rdf:Seq"
+                    };
+
+                    xml.push_str(&format!("   <{}>\n    <{}>\n", name, container));
+                    for item in items {
+                        xml.push_str(&format!("     <rdf:li>{}</rdf:li>\n", escape(item)));
+                    }
+                    xml.push_str(&format!("    </{}>\n   </{}>\n", container, name));
+                },
+                XmpValue::Alt(alternatives) => {
+                    xml.push_str(&format!("   <{}>\n    <rdf:Alt>\n", name));
+                    for (lang, text) in alternatives {
+                        xml.push_str(&format!("     <rdf:li xml:lang=\"{}\">{}</rdf:li>\n", escape(lang), escape(text)));
+                    }
+                    xml.push_str(&format!("    </rdf:Alt>\n   </{}>\n", name));
+                }
+            }
+        }
+
+        xml.push_str("  </rdf:Description>\n");
+        xml
+    }
+
+    /// Serializes this packet into an uncompressed `iTXt` chunk, as
+    /// recommended by the XMP specification.
+    pub fn to_chunk(&self) -> Result<Chunk, String> {
+        Ok(ITXT::new(XMP_KEYWORD, "", "", &self.to_xml(), false)?.to_chunk())
+    }
+
+    /// Splits a qualified name into its namespace URI and local name.
+    fn resolve(&self, name: &str) -> Result<(String, String), String> {
+        let (prefix, local) = match name.split_once(':') {
+            Some((prefix, local)) if !prefix.is_empty() && !local.is_empty() => (prefix, local),
+            _ => return Err(format!("XMP property '{}' must be written as 'prefix:name'.", name))
+        };
+
+        let namespace: Option<String> = self.namespaces.get(prefix).cloned().or_else(|| {
+            STANDARD_NAMESPACES
+                .iter()
+                .find(|(p, _)| *p == prefix)
+                .map(|(_, uri)| uri.to_string())
+        });
+
+        match namespace {
+            Some(namespace) => Ok((namespace, local.to_string())),
+            None => Err(format!("Unknown XMP namespace prefix '{}'.", prefix))
+        }
+    }
+
+    /// Adds or replaces a property, using the declared prefix of its namespace.
+    /// A namespace without a prefix, such as a default namespace, is declared
+    /// under a new prefix.
+    fn insert(&mut self, namespace: &str, name: &str, value: XmpValue) {
+        if let Some(property) = self.properties.iter_mut().find(|p| p.namespace == namespace && p.name == name) {
+            property.value = value;
+            return;
+        }
+
+        let declared: Option<String> = self.namespaces
+            .iter()
+            .find(|(_, uri)| uri.as_str() == namespace)
+            .map(|(prefix, _)| prefix.clone());
+
+        let prefix: String = match declared {
+            Some(prefix) => prefix,
+            None => {
+                let prefix: String = STANDARD_NAMESPACES
+                    .iter()
+                    .find(|(p, uri)| *uri == namespace && !self.namespaces.contains_key(*p))
+                    .map(|(p, _)| p.to_string())
+                    .unwrap_or_else(|| (1..)
+                        .map(|n| format!("ns{}", n))
+                        .find(|p| !self.namespaces.contains_key(p) && !STANDARD_NAMESPACES.iter().any(|(s, _)| s == p))
+                        .unwrap());
+
+                self.namespaces.insert(prefix.clone(), namespace.to_string());
+                prefix
+            }
+        };
+
+        self.properties.push(XmpProperty {
+            namespace: namespace.to_string(),
+            prefix,
+            name: name.to_string(),
+            value
+        });
+    }
+}
+
+impl fmt::Display for XmpPacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for property in &self.properties {
+            writeln!(f, "{}: {}", property.qualified_name(), property.value())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The `rdf:Description` elements directly inside the `rdf:RDF` element of a
+/// packet. Descriptions nested in a property belong to its value.
+fn descriptions<'a, 'input>(
+    document: &'a roxmltree::Document<'input>
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    let is_rdf = |n: &roxmltree::Node, name: &str| n.tag_name().namespace() == Some(RDF_NAMESPACE)
+        && n.tag_name().name() == name;
+
+    document
+        .descendants()
+        .filter(move |n| is_rdf(n, "Description") && n.parent_element().is_some_and(|p| is_rdf(&p, "RDF")))
+}
+
+/// Parses the value of a property element, keeping anything other than
+/// simple text and `rdf:Bag`, `rdf:Seq`, or `rdf:Alt` arrays as raw XML.
+fn parse_value(element: &roxmltree::Node, xml: &str) -> XmpValue {
+    let children: Vec<roxmltree::Node> = element.children().filter(|n| n.is_element()).collect();
+    let has_attributes: bool = element.attributes().any(|a| a.namespace() != Some(XML_NAMESPACE));
+
+    if children.is_empty() && !has_attributes {
+        return XmpValue::Text(element.text().unwrap_or_default().to_string());
+    }
+
+    if let [container] = children.as_slice() {
+        if container.tag_name().namespace() == Some(RDF_NAMESPACE) {
+            let is_item = |n: &roxmltree::Node| n.tag_name().namespace() == Some(RDF_NAMESPACE)
+                && n.tag_name().name() == "li";
+            let items = container.children().filter(|n| n.is_element());
+            let simple: bool = items
+                .clone()
+                .all(|n| is_item(&n) && !n.children().any(|c| c.is_element()));
+
+            if simple {
+                let texts: Vec<String> = items.clone().map(|n| n.text().unwrap_or_default().to_string()).collect();
+
+                match container.tag_name().name() {
+                    "Bag" => return XmpValue::Bag(texts),
+                    "Seq" => return XmpValue::Seq(texts),
+                    "Alt" => {
+                        let alternatives: Vec<(String, String)> = items
+                            .map(|n| (
+                                n.attribute((XML_NAMESPACE, "lang")).unwrap_or("x-default").to_string(),
+                                n.text().unwrap_or_default().to_string()
+                            ))
+                            .collect();
+                        return XmpValue::Alt(alternatives);
+                    },
+                    _ => ()
+                }
+            }
+        }
+    }
+
+    XmpValue::Raw(xml[element.range()].to_string())
+}
+
+/// Escapes the characters that may not appear in XML text or attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:Iptc4xmpCore="http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/"
+    xmp:CreateDate="2021-04-01T10:00:00Z">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Dice</rdf:li><rdf:li xml:lang="de">W&#252;rfel</rdf:li></rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>Ann</rdf:li><rdf:li>Bo &amp; Co</rdf:li></rdf:Seq></dc:creator>
+   <dc:rights><rdf:Alt><rdf:li xml:lang="x-default">CC BY 4.0</rdf:li></rdf:Alt></dc:rights>
+   <Iptc4xmpCore:CreatorContactInfo rdf:parseType="Resource"><Iptc4xmpCore:CiEmailWork>a@b.c</Iptc4xmpCore:CiEmailWork></Iptc4xmpCore:CreatorContactInfo>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_xmp_parse() {
+        let packet = XmpPacket::parse(PACKET).unwrap();
+
+        assert_eq!(packet.title(), Some("Dice"));
+        assert_eq!(packet.creators(), vec!["Ann", "Bo & Co"]);
+        assert_eq!(packet.create_date(), Some("2021-04-01T10:00:00Z"));
+        assert_eq!(packet.rights(), Some("CC BY 4.0"));
+        assert!(matches!(packet.get("Iptc4xmpCore:CreatorContactInfo"), Some(XmpValue::Raw(_))));
+        assert!(packet.get("dc:subject").is_none());
+        assert!(packet.get("bogus:title").is_none());
+    }
+
+    #[test]
+    fn test_xmp_round_trip() {
+        let mut packet = XmpPacket::parse(PACKET).unwrap();
+        packet.set_values("dc:title", vec!["Two <dice>".to_string()]).unwrap();
+        packet.set_values("dc:subject", vec!["games".to_string(), "red".to_string()]).unwrap();
+        packet.set_values("photoshop:City", vec!["Oslo".to_string()]).unwrap();
+        assert!(packet.set_values("xmp:CreateDate", vec![]).is_err());
+        assert!(packet.remove("dc:rights").is_some());
+
+        let parsed = XmpPacket::parse(&packet.to_xml()).unwrap();
+
+        assert_eq!(parsed.title(), Some("Two <dice>"));
+        assert_eq!(parsed.get("dc:title"), Some(&XmpValue::Alt(vec![
+            ("x-default".to_string(), "Two <dice>".to_string()),
+            ("de".to_string(), "Würfel".to_string())
+        ])));
+        assert_eq!(parsed.get("dc:subject"), Some(&XmpValue::Bag(vec!["games".to_string(), "red".to_string()])));
+        assert_eq!(parsed.get("photoshop:City"), Some(&XmpValue::Text("Oslo".to_string())));
+        assert_eq!(parsed.rights(), None);
+        assert_eq!(parsed.properties().len(), packet.properties().len());
+        assert_eq!(parsed.get("Iptc4xmpCore:CreatorContactInfo"), packet.get("Iptc4xmpCore:CreatorContactInfo"));
+    }
+
+    #[test]
+    fn test_xmp_default_namespace() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description><Label xmlns="http://ns.example.com/">x</Label></rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+        let packet = XmpPacket::parse(xml).unwrap();
+        let parsed = XmpPacket::parse(&packet.to_xml()).unwrap();
+
+        assert_eq!(parsed.properties(), packet.properties());
+        assert_eq!(parsed.properties()[0].namespace(), "http://ns.example.com/");
+        assert_eq!(parsed.get("ns1:Label"), Some(&XmpValue::Text("x".to_string())));
+    }
+
+    #[test]
+    fn test_xmp_keeps_unmodelled_nodes() {
+        let xml = PACKET
+            .replace(r#"xmlns:x="adobe:ns:meta/">"#, r#"xmlns:x="adobe:ns:meta/" x:xmptk="Tool 1.0">"#)
+            .replace(" </rdf:RDF>", "  <!-- note -->\n </rdf:RDF>")
+            .replace(r#"<?xpacket end="w"?>"#, &format!("{}\n<?xpacket end=\"w\"?>", " ".repeat(64)));
+        let mut packet = XmpPacket::parse(&xml).unwrap();
+        packet.set_values("dc:title", vec!["Two dice".to_string()]).unwrap();
+
+        let written: String = packet.to_xml();
+
+        assert!(written.starts_with(r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>"#));
+        assert!(written.contains(r#"x:xmptk="Tool 1.0""#));
+        assert!(written.contains("<!-- note -->"));
+        assert!(written.ends_with(&format!("{}\n<?xpacket end=\"w\"?>", " ".repeat(64))));
+        assert_eq!(written.matches("<rdf:Description").count(), 1);
+        assert_eq!(XmpPacket::parse(&written).unwrap().title(), Some("Two dice"));
+    }
+
+    #[test]
+    fn test_xmp_from_png() {
+        let packet = XmpPacket::parse(PACKET).unwrap();
+        let png = Png::from_chunks(vec![packet.to_chunk().unwrap()]);
+
+        assert_eq!(XmpPacket::from_png(&png).unwrap().unwrap().title(), Some("Dice"));
+        assert!(XmpPacket::from_png(&Png::from_chunks(vec![])).is_none());
+        assert!(XmpPacket::parse("<x:xmpmeta").is_err());
+    }
+}
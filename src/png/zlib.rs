@@ -0,0 +1,61 @@
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use miniz_oxide::inflate::{decompress_to_vec_zlib_with_limit, TINFLStatus};
+use miniz_oxide::inflate::stream::{inflate, InflateState};
+use miniz_oxide::{DataFormat, MZFlush, MZStatus};
+
+/// The size of the buffer `inflated_size` decompresses into, which is the
+/// largest distance a deflate block may refer back.
+const WINDOW_SIZE: usize = 32 * 1024;
+
+/// The compression level used when writing zlib datastreams.
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// The most bytes the compressed text of a zTXt or iTXt chunk, or the
+/// profile of an iCCP chunk, may decompress to.
+pub const MAX_METADATA_SIZE: usize = 8 * 1024 * 1024;
+
+/// The start of the error returned by `inflate_with_limit` when a datastream
+/// would decompress to more than the limit.
+pub const LIMIT_EXCEEDED: &str = "Decompressed data exceeds the expected";
+
+/// Decompresses a zlib datastream, failing if it would produce more than
+/// `limit` bytes. This bounds the memory used by hostile datastreams.
+pub fn inflate_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    match decompress_to_vec_zlib_with_limit(data, limit) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) if e.status == TINFLStatus::HasMoreOutput =>
+            Err(format!("{} {} bytes.", LIMIT_EXCEEDED, limit)),
+        Err(e) => Err(format!("Could not decompress zlib datastream: {:?}", e.status))
+    }
+}
+
+/// Counts the bytes a zlib datastream decompresses to, failing if it would
+/// produce more than `limit` bytes. The output is decompressed into a fixed
+/// buffer and discarded, so hostile datastreams cost no more memory than
+/// intact ones.
+pub fn inflated_size(data: &[u8], limit: usize) -> Result<usize, String> {
+    let mut state: Box<InflateState> = InflateState::new_boxed(DataFormat::Zlib);
+    let mut buffer: Vec<u8> = vec![0; WINDOW_SIZE];
+    let (mut consumed, mut size): (usize, usize) = (0, 0);
+
+    loop {
+        let result = inflate(&mut state, &data[consumed..], &mut buffer, MZFlush::None);
+        consumed += result.bytes_consumed;
+        size += result.bytes_written;
+
+        if size > limit {
+            return Err(format!("{} {} bytes.", LIMIT_EXCEEDED, limit));
+        }
+
+        match result.status {
+            Ok(MZStatus::StreamEnd) => return Ok(size),
+            Ok(_) if result.bytes_consumed + result.bytes_written > 0 => (),
+            _ => return Err(format!("Could not decompress zlib datastream: {:?}", state.last_status()))
+        }
+    }
+}
+
+/// Compresses bytes into a zlib datastream as used by compressed PNG chunks.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    compress_to_vec_zlib(data, COMPRESSION_LEVEL)
+}